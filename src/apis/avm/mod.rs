@@ -0,0 +1,144 @@
+use crate::common::api_base::ApiBase;
+use crate::common::cache::ResponseCache;
+use crate::avalanche_core::AvalancheCore;
+use std::sync::Arc;
+use crate::errors::AvalancheError;
+use serde::{Serialize, Deserialize};
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::common::tx_status::{ResponseJRPCGetTxStatus, TxStatus};
+use crate::common::tx::{ParamsIssueTx, ResponseIssueTx, Tx};
+use crate::common::utxo::{self, UtxoIndex, UtxoPage, UtxoSet};
+use crate::utils::helper_functions::u64_string;
+
+pub struct AvmAPI {
+    core: Arc<dyn AvalancheCore>,
+    cache: ResponseCache
+}
+
+impl ApiBase for AvmAPI {
+    fn get_api_base_url(&self) -> &str {
+        "/ext/bc/X"
+    }
+    fn get_cache(&self) -> &ResponseCache {
+        &self.cache
+    }
+    fn get_core(&self) -> Box<&dyn AvalancheCore> {
+        Box::new(&(*self.core))
+    }
+}
+
+impl JsonRpcApi for AvmAPI {
+    fn get_json_rpc_version(&self) -> String {
+        String::from("2.0")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ResponseAssetDescription {
+    #[serde(alias = "assetID")]
+    pub asset_id: String,
+    pub name: String,
+    pub symbol: String,
+    pub denomination: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AssetBalance {
+    /// Asset ID, or alias such as `AVAX`.
+    pub asset: String,
+    #[serde(with = "u64_string")]
+    pub balance: u64
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ResponseGetAllBalances {
+    pub balances: Vec<AssetBalance>
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ResponseGetAddressTxs {
+    #[serde(alias = "txIDs")]
+    pub tx_ids: Vec<String>,
+    /// Cursor of the next page.
+    #[serde(with = "u64_string")]
+    pub cursor: u64
+}
+
+#[derive(Serialize)]
+pub(crate) struct ParamsGetAllBalances<'a> {
+    pub address: &'a str
+}
+
+#[derive(Serialize)]
+pub(crate) struct ParamsGetAddressTxs<'a> {
+    pub address: &'a str,
+    #[serde(rename = "assetID")]
+    pub asset_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none", with = "optional_u64_string")]
+    pub cursor: Option<u64>,
+    #[serde(rename = "pageSize", skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>
+}
+
+mod optional_u64_string {
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none()
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ParamsGetAssetDescription<'a> {
+    #[serde(rename = "assetID")]
+    asset_id: &'a str
+}
+
+#[derive(Serialize)]
+struct ParamsGetTxStatus<'a> {
+    #[serde(rename = "txID")]
+    tx_id: &'a str
+}
+
+impl AvmAPI {
+    pub fn new(core: Arc<dyn AvalancheCore>) -> AvmAPI {
+        AvmAPI {
+            core,
+            cache: ResponseCache::default()
+        }
+    }
+    /// `asset_id` is either an asset ID or an alias such as `AVAX`.
+    pub async fn get_asset_description(&self, asset_id: &str) -> Result<ResponseAssetDescription, AvalancheError> {
+        self.call_method("avm.getAssetDescription", ParamsGetAssetDescription { asset_id }, None, None).await
+    }
+    /// Balances of every asset held by `address`.
+    pub async fn get_all_balances(&self, address: &str) -> Result<Vec<AssetBalance>, AvalancheError> {
+        let response: ResponseGetAllBalances = self.call_method("avm.getAllBalances", ParamsGetAllBalances { address }, None, None).await?;
+        Ok(response.balances)
+    }
+    /// Page of the transactions of `address` moving `asset_id`, needs the node to run with its index enabled.
+    pub async fn get_address_txs(&self, address: &str, asset_id: &str, cursor: Option<u64>, page_size: Option<u32>) -> Result<ResponseGetAddressTxs, AvalancheError> {
+        self.call_method("avm.getAddressTxs", ParamsGetAddressTxs { address, asset_id, cursor, page_size }, None, None).await
+    }
+    /// Page of at most `limit` UTXOs of `addresses`, the UTXOs exported to the X-Chain from
+    /// `source_chain` when given.
+    pub async fn get_utxos(&self, addresses: &[String], source_chain: Option<&str>, limit: u32, start_index: Option<&UtxoIndex>) -> Result<UtxoPage, AvalancheError> {
+        utxo::get_utxos_page(self, "avm.getUTXOs", addresses, source_chain, limit, start_index).await
+    }
+    /// Every UTXO of `addresses`, fetched page after page.
+    pub async fn get_utxo_set(&self, addresses: &[String], source_chain: Option<&str>) -> Result<UtxoSet, AvalancheError> {
+        utxo::get_utxo_set(self, "avm.getUTXOs", addresses, source_chain).await
+    }
+    /// Issues the signed `tx`, returning its ID.
+    pub async fn issue_tx(&self, tx: &Tx) -> Result<String, AvalancheError> {
+        let response: ResponseIssueTx = self.call_method("avm.issueTx", ParamsIssueTx::new(tx), None, None).await?;
+        Ok(response.tx_id)
+    }
+    pub async fn get_tx_status(&self, tx_id: &str) -> Result<TxStatus, AvalancheError> {
+        let response: ResponseJRPCGetTxStatus = self.call_method("avm.getTxStatus", ParamsGetTxStatus { tx_id }, None, None).await?;
+        TxStatus::try_from(response)
+    }
+}
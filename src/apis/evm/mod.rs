@@ -1,23 +1,43 @@
 #[allow(dead_code)]
 pub mod constants;
+pub mod subscription;
+pub mod transaction;
 
 use crate::common::api_base::ApiBase;
+use crate::common::cache::ResponseCache;
 use crate::avalanche_core::AvalancheCore;
-use clru::CLruCache;
-use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use crate::AvalancheError;
-use crate::common::json_rpc_api::{JsonRpcApi, JsonRpcResponse, decode_json_rpc_body};
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::common::signer::{evm_address, Signer};
+use crate::common::tx::{ParamsIssueTx, ResponseIssueTx, Tx};
+use crate::common::tx_status::{ResponseJRPCGetTxStatus, TxStatus};
+use crate::common::utxo::{self, UtxoIndex, UtxoPage, UtxoSet};
+use crate::utils::address::ShortId;
+use serde::{Serialize, Serializer, Deserialize};
+use serde::ser::SerializeMap;
+use subscription::{BlockHeader, Log, LogFilter, Subscription, WsClient, WS_ENDPOINT};
+use transaction::{
+    EvmAddress, EvmTransaction, LegacyTransaction, Eip1559Transaction, SignedEvmTransaction,
+    format_evm_address, format_hex_quantity, parse_hex_quantity, parse_hex_u64
+};
+
+pub const RPC_ENDPOINT: &str = "/ext/bc/C/rpc";
+pub const DEFAULT_RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+pub const DEFAULT_RECEIPT_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct EvmAPI {
-    core: Box<dyn AvalancheCore>,
-    cache: CLruCache<String, String>
+    core: Arc<dyn AvalancheCore>,
+    cache: ResponseCache,
+    ws: tokio::sync::Mutex<Option<WsClient>>
 }
 
 impl ApiBase for EvmAPI {
     fn get_api_base_url(&self) -> &str {
         "/ext/bc/C/avax"
     }
-    fn get_cache(&self) -> &CLruCache<String, String> {
+    fn get_cache(&self) -> &ResponseCache {
         &self.cache
     }
     fn get_core(&self) -> Box<&dyn AvalancheCore> {
@@ -29,32 +49,316 @@ impl JsonRpcApi for EvmAPI {
     fn get_json_rpc_version(&self) -> String {
         String::from("2.0")
     }
+}
+
+/// Transaction to send, every field left to `None` is filled from the node.
+/// Setting `gas_price` produces a legacy transaction, an EIP-1559 one is built otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransactionRequest {
+    pub to: Option<EvmAddress>,
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub nonce: Option<u64>,
+    pub gas_limit: Option<u64>,
+    pub gas_price: Option<u128>,
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+    pub chain_id: Option<u64>
+}
+
+/// Block at which the state is read, serialized as a tag or a hex block number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockId {
+    Latest,
+    Pending,
+    Earliest,
+    Number(u64)
+}
+
+impl Serialize for BlockId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            BlockId::Latest => serializer.serialize_str("latest"),
+            BlockId::Pending => serializer.serialize_str("pending"),
+            BlockId::Earliest => serializer.serialize_str("earliest"),
+            BlockId::Number(number) => serializer.serialize_str(&format_hex_quantity(*number as u128)),
+        }
+    }
+}
+
+/// Call object of `eth_call` and `eth_estimateGas`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallRequest {
+    pub from: Option<EvmAddress>,
+    pub to: Option<EvmAddress>,
+    pub gas: Option<u64>,
+    pub value: u128,
+    pub data: Vec<u8>
+}
+
+impl CallRequest {
+    pub fn from_transaction_request(from: &EvmAddress, request: &TransactionRequest) -> CallRequest {
+        CallRequest {
+            from: Some(*from),
+            to: request.to,
+            gas: request.gas_limit,
+            value: request.value,
+            data: request.data.clone()
+        }
+    }
+}
+
+impl Serialize for CallRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(from) = &self.from {
+            map.serialize_entry("from", &format_evm_address(from))?;
+        }
+        if let Some(to) = &self.to {
+            map.serialize_entry("to", &format_evm_address(to))?;
+        }
+        if let Some(gas) = self.gas {
+            map.serialize_entry("gas", &format_hex_quantity(gas as u128))?;
+        }
+        map.serialize_entry("value", &format_hex_quantity(self.value))?;
+        if !self.data.is_empty() {
+            map.serialize_entry("data", &format!("0x{}", hex::encode(&self.data)))?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransactionReceipt {
+    #[serde(alias = "transactionHash")]
+    pub transaction_hash: String,
+    #[serde(alias = "blockHash")]
+    pub block_hash: String,
+    #[serde(alias = "blockNumber")]
+    pub block_number: String,
+    pub from: String,
+    pub to: Option<String>,
+    #[serde(alias = "gasUsed")]
+    pub gas_used: String,
+    #[serde(alias = "effectiveGasPrice")]
+    pub effective_gas_price: Option<String>,
+    #[serde(alias = "contractAddress")]
+    pub contract_address: Option<String>,
+    pub status: Option<String>,
+    #[serde(default)]
+    pub logs: Vec<serde_json::Value>
+}
 
-    fn get_json_rpc_id(&self) -> u32 {
-        1
+impl TransactionReceipt {
+    pub fn is_success(&self) -> bool {
+        self.status.as_deref() == Some("0x1")
+    }
+    /// Status of the transaction, a failed execution being rejected.
+    pub fn tx_status(&self) -> TxStatus {
+        if self.is_success() {
+            TxStatus::Accepted
+        } else {
+            TxStatus::Rejected { reason: Some(String::from("execution reverted")) }
+        }
     }
 }
 
+#[derive(Serialize)]
+struct ParamsGetAtomicTxStatus<'a> {
+    #[serde(rename = "txID")]
+    tx_id: &'a str
+}
+
 impl EvmAPI {
-    pub fn new(core: Box<dyn AvalancheCore>) -> EvmAPI {
+    pub fn new(core: Arc<dyn AvalancheCore>) -> EvmAPI {
         EvmAPI {
             core,
-            cache: CLruCache::new(NonZeroUsize::new(2).unwrap())
+            cache: ResponseCache::default(),
+            ws: tokio::sync::Mutex::new(None)
         }
     }
 
+    /// WebSocket connection shared by the subscriptions, opened on the first one and again once
+    /// it has closed.
+    async fn ws_client(&self) -> Result<WsClient, AvalancheError> {
+        let mut ws = self.ws.lock().await;
+        if let Some(client) = ws.as_ref().filter(|client| !client.is_closed()) {
+            return Ok(client.clone());
+        }
+        let protocol = if self.core.get_protocol() == "https" { "wss" } else { "ws" };
+        let url = format!("{}://{}:{}{}", protocol, self.core.get_host(), self.core.get_port(), WS_ENDPOINT);
+        let client = WsClient::connect(&url, self.core.get_request_options().retry).await?;
+        *ws = Some(client.clone());
+        Ok(client)
+    }
+
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<BlockHeader>, AvalancheError> {
+        self.ws_client().await?.subscribe_new_heads().await
+    }
+
+    pub async fn subscribe_logs(&self, filter: &LogFilter) -> Result<Subscription<Log>, AvalancheError> {
+        self.ws_client().await?.subscribe_logs(filter).await
+    }
+
+    pub async fn subscribe_new_pending_transactions(&self) -> Result<Subscription<String>, AvalancheError> {
+        self.ws_client().await?.subscribe_new_pending_transactions().await
+    }
+
     pub async fn get_base_fee(&self) -> Result<String, AvalancheError> {
-        let response = self.call_method("eth_baseFee", None, Some("/ext/bc/C/rpc"), None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<String> = decode_json_rpc_body("eth_baseFee", body)?;
-        Ok(response_formatted.result)
+        self.call_method("eth_baseFee", (), Some(RPC_ENDPOINT), None).await
     }
 
     pub async fn get_max_priority_fee_per_gas(&self) -> Result<String, AvalancheError> {
-        let response = self.call_method("eth_maxPriorityFeePerGas", None, Some("/ext/bc/C/rpc"), None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<String> = decode_json_rpc_body("eth_maxPriorityFeePerGas", body)?;
-        Ok(response_formatted.result)
+        self.call_method("eth_maxPriorityFeePerGas", (), Some(RPC_ENDPOINT), None).await
+    }
+
+    pub async fn get_chain_id(&self) -> Result<u64, AvalancheError> {
+        let response: String = self.call_method("eth_chainId", (), Some(RPC_ENDPOINT), None).await?;
+        parse_hex_u64(&response)
+    }
+
+    pub async fn get_transaction_count(&self, address: &EvmAddress, block: BlockId) -> Result<u64, AvalancheError> {
+        let params = (format_evm_address(address), block);
+        let response: String = self.call_method("eth_getTransactionCount", params, Some(RPC_ENDPOINT), None).await?;
+        parse_hex_u64(&response)
+    }
+
+    pub async fn get_balance(&self, address: &EvmAddress, block: BlockId) -> Result<u128, AvalancheError> {
+        let params = (format_evm_address(address), block);
+        let response: String = self.call_method("eth_getBalance", params, Some(RPC_ENDPOINT), None).await?;
+        parse_hex_quantity(&response)
+    }
+
+    /// Executes `request` against the state at `block` without creating a transaction.
+    pub async fn call(&self, request: &CallRequest, block: BlockId) -> Result<Vec<u8>, AvalancheError> {
+        let response: String = self.call_method("eth_call", (request, block), Some(RPC_ENDPOINT), None).await?;
+        hex::decode(response.trim_start_matches("0x")).map_err(|e| AvalancheError::InvalidValue {
+            value: response.clone(),
+            reason: e.to_string()
+        })
+    }
+
+    pub async fn estimate_gas(&self, from: &EvmAddress, request: &TransactionRequest) -> Result<u64, AvalancheError> {
+        let call = CallRequest {
+            gas: None,
+            ..CallRequest::from_transaction_request(from, request)
+        };
+        let response: String = self.call_method("eth_estimateGas", [call], Some(RPC_ENDPOINT), None).await?;
+        parse_hex_u64(&response)
+    }
+
+    pub async fn send_raw_transaction(&self, transaction: &SignedEvmTransaction) -> Result<String, AvalancheError> {
+        self.call_method("eth_sendRawTransaction", [transaction.raw_hex()], Some(RPC_ENDPOINT), None).await
+    }
+
+    pub async fn get_transaction_receipt(&self, tx_hash: &str) -> Result<Option<TransactionReceipt>, AvalancheError> {
+        self.call_method("eth_getTransactionReceipt", [tx_hash], Some(RPC_ENDPOINT), None).await
+    }
+
+    /// Page of at most `limit` UTXOs of the `C-` `addresses` exported to the C-Chain from
+    /// `source_chain`.
+    pub async fn get_utxos(&self, addresses: &[String], source_chain: &str, limit: u32, start_index: Option<&UtxoIndex>) -> Result<UtxoPage, AvalancheError> {
+        utxo::get_utxos_page(self, "avax.getUTXOs", addresses, Some(source_chain), limit, start_index).await
+    }
+
+    /// Every UTXO of the `C-` `addresses` exported to the C-Chain from `source_chain`.
+    pub async fn get_utxo_set(&self, addresses: &[String], source_chain: &str) -> Result<UtxoSet, AvalancheError> {
+        utxo::get_utxo_set(self, "avax.getUTXOs", addresses, Some(source_chain)).await
+    }
+
+    /// Issues the signed import or export `tx`, returning its ID.
+    pub async fn issue_tx(&self, tx: &Tx) -> Result<String, AvalancheError> {
+        let response: ResponseIssueTx = self.call_method("avax.issueTx", ParamsIssueTx::new(tx), None, None).await?;
+        Ok(response.tx_id)
+    }
+
+    /// Status of an import or export transaction of the C-Chain.
+    pub async fn get_atomic_tx_status(&self, tx_id: &str) -> Result<TxStatus, AvalancheError> {
+        let response: ResponseJRPCGetTxStatus = self.call_method("avax.getAtomicTxStatus", ParamsGetAtomicTxStatus { tx_id }, None, None).await?;
+        TxStatus::try_from(response)
+    }
+
+    /// Status of an EVM transaction, processing until it has a receipt.
+    pub async fn get_transaction_status(&self, tx_hash: &str) -> Result<TxStatus, AvalancheError> {
+        Ok(self.get_transaction_receipt(tx_hash).await?.map_or(TxStatus::Processing, |receipt| receipt.tx_status()))
+    }
+
+    pub async fn wait_for_transaction_receipt(&self, tx_hash: &str, poll_interval: Duration, timeout: Duration) -> Result<TransactionReceipt, AvalancheError> {
+        let start = Instant::now();
+        loop {
+            if let Some(receipt) = self.get_transaction_receipt(tx_hash).await? {
+                return Ok(receipt);
+            }
+            if start.elapsed() + poll_interval > timeout {
+                return Err(AvalancheError::Timeout {
+                    operation: format!("receipt of {}", tx_hash)
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Completes the missing fields of `request` using the node: nonce from the pending
+    /// transaction count, gas limit from `eth_estimateGas`, fees from the current base fee
+    /// and chain ID from `eth_chainId`.
+    pub async fn fill_transaction(&self, from: &EvmAddress, request: &TransactionRequest) -> Result<EvmTransaction, AvalancheError> {
+        let chain_id = match request.chain_id {
+            Some(chain_id) => chain_id,
+            None => self.get_chain_id().await?
+        };
+        let nonce = match request.nonce {
+            Some(nonce) => nonce,
+            None => self.get_transaction_count(from, BlockId::Pending).await?
+        };
+        let gas_limit = match request.gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => self.estimate_gas(from, request).await?
+        };
+        if let Some(gas_price) = request.gas_price {
+            return Ok(EvmTransaction::Legacy(LegacyTransaction {
+                chain_id,
+                nonce,
+                gas_price,
+                gas_limit,
+                to: request.to,
+                value: request.value,
+                data: request.data.clone()
+            }));
+        }
+        let max_priority_fee_per_gas = match request.max_priority_fee_per_gas {
+            Some(fee) => fee,
+            None => parse_hex_quantity(&self.get_max_priority_fee_per_gas().await?)?
+        };
+        let max_fee_per_gas = match request.max_fee_per_gas {
+            Some(fee) => fee,
+            None => parse_hex_quantity(&self.get_base_fee().await?)? * 2 + max_priority_fee_per_gas
+        };
+        Ok(EvmTransaction::Eip1559(Eip1559Transaction {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to: request.to,
+            value: request.value,
+            data: request.data.clone(),
+            access_list: vec![]
+        }))
+    }
+
+    /// Fills `request` for the EVM address of the key of `from`, has `signer` sign it, submits it
+    /// then waits for its receipt.
+    pub async fn send_transaction(&self, signer: &dyn Signer, from: &ShortId, request: &TransactionRequest) -> Result<TransactionReceipt, AvalancheError> {
+        let transaction = self.fill_transaction(&evm_address(signer, from).await?, request).await?;
+        let signed = transaction.sign_with(signer, from).await?;
+        let tx_hash = self.send_raw_transaction(&signed).await?;
+        self.wait_for_transaction_receipt(&tx_hash, DEFAULT_RECEIPT_POLL_INTERVAL, DEFAULT_RECEIPT_TIMEOUT).await
     }
 }
 
@@ -62,19 +366,95 @@ impl EvmAPI {
 mod tests {
     use super::*;
     use crate::Avalanche;
+    use crate::common::keychain::StandardKeyChain;
+    use crate::common::secp256k1::Secp256k1KeyChain;
+    use crate::utils::constants::C_CHAIN_ALIAS;
 
     #[tokio::test]
     async fn get_base_fee_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let evm_api: EvmAPI = EvmAPI::new(Box::new(avalanche));
+        let evm_api: &EvmAPI = avalanche.evm().unwrap();
         assert_eq!(evm_api.get_base_fee().await.unwrap(), "0x5d21dba00");
     }
 
     #[tokio::test]
     async fn get_max_priority_fee_per_gas_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let evm_api: EvmAPI = EvmAPI::new(Box::new(avalanche));
+        let evm_api: &EvmAPI = avalanche.evm().unwrap();
         assert_eq!(evm_api.get_max_priority_fee_per_gas().await.unwrap(), "0x0");
     }
+
+    #[tokio::test]
+    async fn call_sends_positional_call_object_and_block() {
+        let port = crate::test_utils::mock_json_rpc_server(|method, params| {
+            assert_eq!(method, "eth_call");
+            assert_eq!(params, &serde_json::json!([
+                {"to": "0x3535353535353535353535353535353535353535", "value": "0x0", "data": "0x70a08231"},
+                "0x10"
+            ]));
+            serde_json::json!("0x0000000000000000000000000000000000000000000000000000000000000001")
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let request = CallRequest {
+            to: Some([0x35; 20]),
+            data: vec![0x70, 0xa0, 0x82, 0x31],
+            ..Default::default()
+        };
+        let output = avalanche.evm().unwrap().call(&request, BlockId::Number(16)).await.unwrap();
+        assert_eq!(output.len(), 32);
+        assert_eq!(output[31], 1);
+    }
+
+    #[tokio::test]
+    async fn send_transaction_fills_signs_and_waits_for_receipt() {
+        let port = crate::test_utils::mock_json_rpc_server(|method, params| match method {
+            "eth_chainId" => serde_json::json!("0xa86a"),
+            "eth_getTransactionCount" => {
+                assert_eq!(params[1], "pending");
+                serde_json::json!("0x7")
+            }
+            "eth_estimateGas" => serde_json::json!("0x5208"),
+            "eth_maxPriorityFeePerGas" => serde_json::json!("0x1"),
+            "eth_baseFee" => serde_json::json!("0x5d21dba00"),
+            "eth_sendRawTransaction" => serde_json::json!("0xabcd"),
+            "eth_getTransactionReceipt" => serde_json::json!({
+                "transactionHash": "0xabcd",
+                "blockHash": "0x01",
+                "blockNumber": "0x2",
+                "from": "0x8db97c7cece249c2b98bdc0226cc4c2a57bf52fc",
+                "to": "0x3535353535353535353535353535353535353535",
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x5d21dba01",
+                "contractAddress": null,
+                "status": "0x1",
+                "logs": []
+            }),
+            _ => panic!("unexpected method {}", method)
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let evm_api: &EvmAPI = avalanche.evm().unwrap();
+        let mut keychain = Secp256k1KeyChain::new(C_CHAIN_ALIAS, "local");
+        let short_id = keychain.import_key(&[0x46; 32]).unwrap().get_short_id();
+        let request = TransactionRequest {
+            to: Some([0x35; 20]),
+            value: 1,
+            ..Default::default()
+        };
+        let from = evm_address(&keychain, &short_id).await.unwrap();
+        assert_eq!(evm_api.fill_transaction(&from, &request).await.unwrap(), EvmTransaction::Eip1559(Eip1559Transaction {
+            chain_id: 43114,
+            nonce: 7,
+            max_priority_fee_per_gas: 1,
+            max_fee_per_gas: 50_000_000_001,
+            gas_limit: 21000,
+            to: Some([0x35; 20]),
+            value: 1,
+            data: vec![],
+            access_list: vec![]
+        }));
+        let receipt = evm_api.send_transaction(&keychain, &short_id, &request).await.unwrap();
+        assert!(receipt.is_success());
+        assert_eq!(receipt.transaction_hash, "0xabcd");
+    }
 }
 
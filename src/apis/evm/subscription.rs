@@ -0,0 +1,516 @@
+use crate::common::retry::RetryPolicy;
+use crate::errors::AvalancheError;
+use futures::{SinkExt, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use super::transaction::{format_evm_address, EvmAddress};
+
+pub const WS_ENDPOINT: &str = "/ext/bc/C/ws";
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type Reply = oneshot::Sender<Result<u64, AvalancheError>>;
+type Notification = Result<Value, AvalancheError>;
+
+/// Filter of a `logs` subscription, an empty filter matching every log.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogFilter {
+    pub addresses: Vec<EvmAddress>,
+    /// Topics by position, `None` matching any topic and several topics matching either of them.
+    pub topics: Vec<Option<Vec<String>>>,
+}
+
+impl Serialize for LogFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        if !self.addresses.is_empty() {
+            let addresses: Vec<String> = self.addresses.iter().map(format_evm_address).collect();
+            map.serialize_entry("address", &addresses)?;
+        }
+        if !self.topics.is_empty() {
+            map.serialize_entry("topics", &self.topics)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockHeader {
+    pub number: String,
+    pub hash: String,
+    #[serde(alias = "parentHash")]
+    pub parent_hash: String,
+    pub timestamp: String,
+    pub miner: Option<String>,
+    #[serde(alias = "gasLimit")]
+    pub gas_limit: Option<String>,
+    #[serde(alias = "gasUsed")]
+    pub gas_used: Option<String>,
+    #[serde(alias = "baseFeePerGas")]
+    pub base_fee_per_gas: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    #[serde(alias = "blockNumber")]
+    pub block_number: Option<String>,
+    #[serde(alias = "transactionHash")]
+    pub transaction_hash: Option<String>,
+    #[serde(alias = "logIndex")]
+    pub log_index: Option<String>,
+    /// Set when the log is removed by a reorganization.
+    #[serde(default)]
+    pub removed: bool,
+}
+
+enum Command {
+    Subscribe {
+        params: Value,
+        sender: mpsc::UnboundedSender<Notification>,
+        reply: Reply,
+    },
+    Unsubscribe(u64),
+}
+
+/// Stream of the notifications of an `eth_subscribe` subscription, unsubscribed when dropped.
+/// It ends with an error when the connection is lost for good.
+pub struct Subscription<T> {
+    id: u64,
+    notifications: mpsc::UnboundedReceiver<Notification>,
+    commands: mpsc::UnboundedSender<Command>,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Stream for Subscription<T> {
+    type Item = Result<T, AvalancheError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.notifications.poll_recv(cx).map(|notification| {
+            notification.map(|notification| {
+                let value = notification?;
+                serde_json::from_value(value.clone()).map_err(|error| AvalancheError::InvalidValue {
+                    value: value.to_string(),
+                    reason: error.to_string(),
+                })
+            })
+        })
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Unsubscribe(self.id));
+    }
+}
+
+/// WebSocket connection to the C-Chain multiplexing the subscriptions. When the connection drops
+/// it reconnects up to `retry.max_retries` times with the backoff of `retry` and subscribes again
+/// to every subscription, ending the subscriptions with an error if every attempt fails. It
+/// closes once the connection drops with no subscription left, or every [`Subscription`] and
+/// clone of the client is dropped.
+#[derive(Clone, Debug)]
+pub struct WsClient {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl WsClient {
+    pub async fn connect(url: &str, retry: RetryPolicy) -> Result<WsClient, AvalancheError> {
+        let socket = open(url).await?;
+        let (commands, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Connection::new(String::from(url), retry, receiver).run(socket));
+        Ok(WsClient { commands })
+    }
+    /// Whether the connection has closed, the client failing every subscription since.
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+    /// Sends `eth_subscribe` with `params` and waits for the node to accept it.
+    pub async fn subscribe<T: DeserializeOwned>(&self, params: Value) -> Result<Subscription<T>, AvalancheError> {
+        let (sender, notifications) = mpsc::unbounded_channel();
+        let (reply, accepted) = oneshot::channel();
+        let closed = || AvalancheError::WebSocket {
+            message: String::from("connection closed"),
+        };
+        self.commands
+            .send(Command::Subscribe { params, sender, reply })
+            .map_err(|_| closed())?;
+        let id = accepted.await.map_err(|_| closed())??;
+        Ok(Subscription {
+            id,
+            notifications,
+            commands: self.commands.clone(),
+            item: PhantomData,
+        })
+    }
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<BlockHeader>, AvalancheError> {
+        self.subscribe(json!(["newHeads"])).await
+    }
+    pub async fn subscribe_logs(&self, filter: &LogFilter) -> Result<Subscription<Log>, AvalancheError> {
+        self.subscribe(json!(["logs", filter])).await
+    }
+    /// Hashes of the transactions entering the mempool of the node.
+    pub async fn subscribe_new_pending_transactions(&self) -> Result<Subscription<String>, AvalancheError> {
+        self.subscribe(json!(["newPendingTransactions"])).await
+    }
+}
+
+async fn open(url: &str) -> Result<Socket, AvalancheError> {
+    let (socket, _) = connect_async(url).await.map_err(|error| AvalancheError::WebSocket {
+        message: error.to_string(),
+    })?;
+    Ok(socket)
+}
+
+struct Entry {
+    params: Value,
+    sender: mpsc::UnboundedSender<Notification>,
+    server_id: Option<String>,
+}
+
+struct Connection {
+    url: String,
+    retry: RetryPolicy,
+    commands: mpsc::UnboundedReceiver<Command>,
+    subscriptions: HashMap<u64, Entry>,
+    by_server_id: HashMap<String, u64>,
+    /// `eth_subscribe` requests waiting for their answer, by request id.
+    pending: HashMap<u64, (u64, Option<Reply>)>,
+    next_id: u64,
+}
+
+impl Connection {
+    fn new(url: String, retry: RetryPolicy, commands: mpsc::UnboundedReceiver<Command>) -> Connection {
+        Connection {
+            url,
+            retry,
+            commands,
+            subscriptions: HashMap::new(),
+            by_server_id: HashMap::new(),
+            pending: HashMap::new(),
+            next_id: 0,
+        }
+    }
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+    async fn run(mut self, mut socket: Socket) {
+        loop {
+            let connected = tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => self.handle_command(&mut socket, command).await,
+                    None => return,
+                },
+                message = socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.handle_message(&mut socket, &text).await,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => false,
+                    Some(Ok(_)) => true,
+                },
+            };
+            if !connected {
+                match self.reconnect().await {
+                    Some(reconnected) => socket = reconnected,
+                    None => return,
+                }
+            }
+        }
+    }
+    /// Returns whether the socket is still usable.
+    async fn handle_command(&mut self, socket: &mut Socket, command: Command) -> bool {
+        match command {
+            Command::Subscribe { params, sender, reply } => {
+                let id = self.next_id();
+                self.subscriptions.insert(id, Entry { params: params.clone(), sender, server_id: None });
+                self.send_subscribe(socket, id, params, Some(reply)).await
+            }
+            Command::Unsubscribe(id) => match self.subscriptions.remove(&id).and_then(|entry| entry.server_id) {
+                Some(server_id) => self.send_unsubscribe(socket, server_id).await,
+                None => true,
+            },
+        }
+    }
+    async fn handle_message(&mut self, socket: &mut Socket, text: &str) -> bool {
+        let Ok(mut message) = serde_json::from_str::<Value>(text) else {
+            return true;
+        };
+        if message["method"] == "eth_subscription" {
+            let server_id = message["params"]["subscription"].as_str().unwrap_or_default().to_string();
+            let Some(&id) = self.by_server_id.get(&server_id) else {
+                return true;
+            };
+            let result = message["params"]["result"].take();
+            let delivered = self.subscriptions.get(&id).is_some_and(|entry| entry.sender.send(Ok(result)).is_ok());
+            if !delivered {
+                self.subscriptions.remove(&id);
+                return self.send_unsubscribe(socket, server_id).await;
+            }
+            return true;
+        }
+        let Some((id, reply)) = message["id"].as_u64().and_then(|request| self.pending.remove(&request)) else {
+            return true;
+        };
+        match (message["result"].as_str(), self.subscriptions.get_mut(&id)) {
+            (Some(server_id), Some(entry)) => {
+                entry.server_id = Some(String::from(server_id));
+                self.by_server_id.insert(String::from(server_id), id);
+                if let Some(reply) = reply {
+                    let _ = reply.send(Ok(id));
+                }
+            }
+            _ => {
+                self.subscriptions.remove(&id);
+                if let Some(reply) = reply {
+                    let _ = reply.send(Err(AvalancheError::ErrorJsonRpcCall {
+                        call: String::from("eth_subscribe"),
+                        code: message["error"]["code"].to_string(),
+                        message: message["error"]["message"].as_str().unwrap_or_default().to_string(),
+                    }));
+                }
+            }
+        }
+        true
+    }
+    async fn send_subscribe(
+        &mut self,
+        socket: &mut Socket,
+        id: u64,
+        params: Value,
+        reply: Option<Reply>,
+    ) -> bool {
+        let request = self.next_id();
+        self.pending.insert(request, (id, reply));
+        let call = json!({"jsonrpc": "2.0", "id": request, "method": "eth_subscribe", "params": params});
+        socket.send(Message::Text(call.to_string())).await.is_ok()
+    }
+    async fn send_unsubscribe(&mut self, socket: &mut Socket, server_id: String) -> bool {
+        self.by_server_id.remove(&server_id);
+        let request = self.next_id();
+        let call = json!({"jsonrpc": "2.0", "id": request, "method": "eth_unsubscribe", "params": [server_id]});
+        socket.send(Message::Text(call.to_string())).await.is_ok()
+    }
+    /// Opens a new socket and subscribes again, `None` once no subscription is left or every
+    /// attempt failed.
+    async fn reconnect(&mut self) -> Option<Socket> {
+        for attempt in 0..self.retry.max_retries {
+            loop {
+                match self.commands.try_recv() {
+                    Ok(Command::Subscribe { params, sender, reply }) => {
+                        let id = self.next_id();
+                        self.subscriptions.insert(id, Entry { params, sender, server_id: None });
+                        // Answered once subscribed on the new socket
+                        let request = self.next_id();
+                        self.pending.insert(request, (id, Some(reply)));
+                    }
+                    Ok(Command::Unsubscribe(id)) => {
+                        self.subscriptions.remove(&id);
+                    }
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => return None,
+                }
+            }
+            if self.subscriptions.is_empty() {
+                return None;
+            }
+            tokio::time::sleep(self.retry.backoff(attempt)).await;
+            let Ok(mut socket) = open(&self.url).await else {
+                continue;
+            };
+            self.by_server_id.clear();
+            let mut replies: HashMap<u64, Reply> = self
+                .pending
+                .drain()
+                .filter_map(|(_, (id, reply))| reply.map(|reply| (id, reply)))
+                .collect();
+            let subscriptions: Vec<(u64, Value)> =
+                self.subscriptions.iter().map(|(id, entry)| (*id, entry.params.clone())).collect();
+            let mut sent = true;
+            for (id, params) in subscriptions {
+                sent &= self.send_subscribe(&mut socket, id, params, replies.remove(&id)).await;
+            }
+            if sent {
+                return Some(socket);
+            }
+        }
+        self.close(&format!("connection lost after {} reconnection attempts", self.retry.max_retries));
+        None
+    }
+    /// Fails the subscriptions waiting for their answer and ends the streams of the others.
+    fn close(&mut self, message: &str) {
+        let error = || AvalancheError::WebSocket {
+            message: String::from(message),
+        };
+        for (_, (_, reply)) in self.pending.drain() {
+            if let Some(reply) = reply {
+                let _ = reply.send(Err(error()));
+            }
+        }
+        for (_, entry) in self.subscriptions.drain() {
+            let _ = entry.sender.send(Err(error()));
+        }
+        self.commands.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avalanche_core::AvalancheCore;
+    use crate::common::retry::RequestOptions;
+    use crate::Avalanche;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    /// Accepts two connections, answering the subscription of each with one head then
+    /// closing the first one.
+    async fn flaky_node() -> (u16, tokio::task::JoinHandle<Vec<Value>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let mut subscribes = Vec::new();
+            for connection in 1..=2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = accept_async(stream).await.unwrap();
+                let Some(Ok(Message::Text(text))) = socket.next().await else {
+                    panic!("expected eth_subscribe");
+                };
+                let call: Value = serde_json::from_str(&text).unwrap();
+                subscribes.push(call["params"].clone());
+                let server_id = format!("0x{}", connection);
+                let answer = json!({"jsonrpc": "2.0", "id": call["id"], "result": server_id});
+                socket.send(Message::Text(answer.to_string())).await.unwrap();
+                let head = json!({"jsonrpc": "2.0", "method": "eth_subscription", "params": {
+                    "subscription": server_id,
+                    "result": {"number": format!("0x{}", connection), "hash": "0xaa", "parentHash": "0xbb", "timestamp": "0x1"}
+                }});
+                socket.send(Message::Text(head.to_string())).await.unwrap();
+                if connection == 1 {
+                    socket.close(None).await.unwrap();
+                } else {
+                    // Wait for the unsubscribe sent when the stream is dropped
+                    while let Some(Ok(message)) = socket.next().await {
+                        if let Message::Text(text) = message {
+                            subscribes.push(serde_json::from_str(&text).unwrap());
+                            break;
+                        }
+                    }
+                }
+            }
+            subscribes
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn subscriptions_survive_reconnections() {
+        let (port, node) = flaky_node().await;
+        let avalanche = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        avalanche.set_request_options(RequestOptions::default().with_retry(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        }));
+        let evm = avalanche.evm().unwrap();
+        let mut heads = evm.subscribe_new_heads().await.unwrap();
+        assert_eq!(heads.next().await.unwrap().unwrap().number, "0x1");
+        assert_eq!(heads.next().await.unwrap().unwrap().number, "0x2");
+        drop(heads);
+        let calls = node.await.unwrap();
+        assert_eq!(calls[0], json!(["newHeads"]));
+        assert_eq!(calls[1], json!(["newHeads"]));
+        assert_eq!(calls[2]["method"], "eth_unsubscribe");
+        assert_eq!(calls[2]["params"], json!(["0x2"]));
+    }
+
+    /// Answers the subscription of the first connection with one head then closes it, drops the
+    /// next `refused` connections before their handshake then serves one more subscription.
+    async fn failing_node(refused: u32) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for connection in 0..=refused + 1 {
+                let (stream, _) = listener.accept().await.unwrap();
+                if connection > 0 && connection <= refused {
+                    continue;
+                }
+                let mut socket = accept_async(stream).await.unwrap();
+                let Some(Ok(Message::Text(text))) = socket.next().await else {
+                    panic!("expected eth_subscribe");
+                };
+                let call: Value = serde_json::from_str(&text).unwrap();
+                let answer = json!({"jsonrpc": "2.0", "id": call["id"], "result": "0x1"});
+                socket.send(Message::Text(answer.to_string())).await.unwrap();
+                let head = json!({"jsonrpc": "2.0", "method": "eth_subscription", "params": {
+                    "subscription": "0x1",
+                    "result": {"number": format!("0x{}", connection), "hash": "0xaa", "parentHash": "0xbb", "timestamp": "0x1"}
+                }});
+                socket.send(Message::Text(head.to_string())).await.unwrap();
+                socket.close(None).await.unwrap();
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn subscriptions_end_when_reconnections_fail() {
+        let port = failing_node(2).await;
+        let avalanche = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        avalanche.set_request_options(RequestOptions::default().with_retry(RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        }));
+        let evm = avalanche.evm().unwrap();
+        let mut heads = evm.subscribe_new_heads().await.unwrap();
+        assert_eq!(heads.next().await.unwrap().unwrap().number, "0x0");
+        assert!(matches!(heads.next().await, Some(Err(AvalancheError::WebSocket { .. }))));
+        assert!(heads.next().await.is_none());
+
+        // The closed client is replaced by a new connection, which would retry for good
+        avalanche.set_request_options(RequestOptions::default().with_retry(RetryPolicy {
+            max_retries: u32::MAX,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        }));
+        let mut heads = evm.subscribe_new_heads().await.unwrap();
+        assert_eq!(heads.next().await.unwrap().unwrap().number, "0x3");
+        let client = evm.ws_client().await.unwrap();
+        drop(heads);
+        // Dropped with no subscription left, the connection closes instead of reconnecting
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !client.is_closed() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn log_filters_serialize_addresses_and_topics() {
+        let filter = LogFilter {
+            addresses: vec![[0x35; 20]],
+            topics: vec![Some(vec![String::from("0xddf2")]), None],
+        };
+        assert_eq!(
+            json!(["logs", filter]),
+            json!(["logs", {"address": ["0x3535353535353535353535353535353535353535"], "topics": [["0xddf2"], null]}])
+        );
+        assert_eq!(json!(LogFilter::default()), json!({}));
+    }
+}
@@ -0,0 +1,370 @@
+use crate::common::signer::{evm_address, Signer};
+use crate::errors::AvalancheError;
+use crate::utils::address::ShortId;
+use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::{recoverable, SigningKey, VerifyingKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+pub const EIP_1559_TX_TYPE: u8 = 0x02;
+
+pub type EvmAddress = [u8; 20];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessListItem {
+    pub address: EvmAddress,
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+/// Pre EIP-1559 transaction, signed following EIP-155 (replay protected by chain ID).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LegacyTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: u128,
+    pub gas_limit: u64,
+    pub to: Option<EvmAddress>,
+    pub value: u128,
+    pub data: Vec<u8>,
+}
+
+/// Dynamic fee transaction (type 2) as defined by EIP-1559.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub gas_limit: u64,
+    pub to: Option<EvmAddress>,
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvmTransaction {
+    Legacy(LegacyTransaction),
+    Eip1559(Eip1559Transaction),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedEvmTransaction {
+    pub raw: Vec<u8>,
+    pub hash: [u8; 32],
+}
+
+impl SignedEvmTransaction {
+    pub fn raw_hex(&self) -> String {
+        format!("0x{}", hex::encode(&self.raw))
+    }
+    pub fn hash_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.hash))
+    }
+}
+
+fn append_to(stream: &mut RlpStream, to: &Option<EvmAddress>) {
+    match to {
+        Some(address) => stream.append(&address.as_ref()),
+        None => stream.append_empty_data(),
+    };
+}
+
+fn append_access_list(stream: &mut RlpStream, access_list: &[AccessListItem]) {
+    stream.begin_list(access_list.len());
+    for item in access_list {
+        stream.begin_list(2);
+        stream.append(&item.address.as_ref());
+        stream.begin_list(item.storage_keys.len());
+        for key in &item.storage_keys {
+            stream.append(&key.as_ref());
+        }
+    }
+}
+
+// RLP encodes integers as big endian bytes without leading zeros.
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[first..]
+}
+
+fn append_signature(stream: &mut RlpStream, v: u64, signature: &recoverable::Signature) {
+    let bytes = signature.as_ref();
+    stream.append(&v);
+    stream.append(&trim_leading_zeros(&bytes[..32]));
+    stream.append(&trim_leading_zeros(&bytes[32..64]));
+}
+
+impl LegacyTransaction {
+    fn append_fields(&self, stream: &mut RlpStream) {
+        stream.append(&self.nonce);
+        stream.append(&self.gas_price);
+        stream.append(&self.gas_limit);
+        append_to(stream, &self.to);
+        stream.append(&self.value);
+        stream.append(&self.data);
+    }
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(9);
+        self.append_fields(&mut stream);
+        stream.append(&self.chain_id);
+        stream.append_empty_data();
+        stream.append_empty_data();
+        stream.out().to_vec()
+    }
+    fn encode_signed(&self, signature: &recoverable::Signature) -> Vec<u8> {
+        let v = u8::from(signature.recovery_id()) as u64 + self.chain_id * 2 + 35;
+        let mut stream = RlpStream::new_list(9);
+        self.append_fields(&mut stream);
+        append_signature(&mut stream, v, signature);
+        stream.out().to_vec()
+    }
+}
+
+impl Eip1559Transaction {
+    fn append_fields(&self, stream: &mut RlpStream) {
+        stream.append(&self.chain_id);
+        stream.append(&self.nonce);
+        stream.append(&self.max_priority_fee_per_gas);
+        stream.append(&self.max_fee_per_gas);
+        stream.append(&self.gas_limit);
+        append_to(stream, &self.to);
+        stream.append(&self.value);
+        stream.append(&self.data);
+        append_access_list(stream, &self.access_list);
+    }
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(9);
+        self.append_fields(&mut stream);
+        let mut payload = vec![EIP_1559_TX_TYPE];
+        payload.extend_from_slice(&stream.out());
+        payload
+    }
+    fn encode_signed(&self, signature: &recoverable::Signature) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(12);
+        self.append_fields(&mut stream);
+        append_signature(&mut stream, u8::from(signature.recovery_id()) as u64, signature);
+        let mut raw = vec![EIP_1559_TX_TYPE];
+        raw.extend_from_slice(&stream.out());
+        raw
+    }
+}
+
+impl EvmTransaction {
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            EvmTransaction::Legacy(tx) => tx.chain_id,
+            EvmTransaction::Eip1559(tx) => tx.chain_id,
+        }
+    }
+    pub fn signing_payload(&self) -> Vec<u8> {
+        match self {
+            EvmTransaction::Legacy(tx) => tx.signing_payload(),
+            EvmTransaction::Eip1559(tx) => tx.signing_payload(),
+        }
+    }
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&self.signing_payload())
+    }
+    pub fn sign(&self, key: &SigningKey) -> Result<SignedEvmTransaction, AvalancheError> {
+        let digest = Keccak256::new().chain(self.signing_payload());
+        let signature: recoverable::Signature = key
+            .try_sign_digest(digest)
+            .map_err(|_| AvalancheError::SigningError)?;
+        Ok(self.encode_signed(&signature))
+    }
+    /// Signs the Keccak-256 prehash of the transaction with the key of `address` held by `signer`,
+    /// checking the signature recovers the EVM address of that key.
+    pub async fn sign_with<S: Signer + ?Sized>(&self, signer: &S, address: &ShortId) -> Result<SignedEvmTransaction, AvalancheError> {
+        let hash = self.signing_hash();
+        let signature = signer.sign_hash(address, &hash).await?;
+        let signature = recoverable::Signature::try_from(&signature[..]).map_err(|_| AvalancheError::SigningError)?;
+        let signer_key = signature.recover_verify_key_from_digest_bytes(&hash.into()).map_err(|_| AvalancheError::SigningError)?;
+        if public_key_to_evm_address(&signer_key) != evm_address(signer, address).await? {
+            return Err(AvalancheError::SigningError);
+        }
+        Ok(self.encode_signed(&signature))
+    }
+    fn encode_signed(&self, signature: &recoverable::Signature) -> SignedEvmTransaction {
+        let raw = match self {
+            EvmTransaction::Legacy(tx) => tx.encode_signed(signature),
+            EvmTransaction::Eip1559(tx) => tx.encode_signed(signature),
+        };
+        let hash = keccak256(&raw);
+        SignedEvmTransaction { raw, hash }
+    }
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Keccak256::digest(data));
+    hash
+}
+
+pub fn public_key_to_evm_address(public_key: &VerifyingKey) -> EvmAddress {
+    let uncompressed = public_key.to_encoded_point(false);
+    let hash = keccak256(&uncompressed.as_bytes()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+pub fn parse_evm_address(address: &str) -> Result<EvmAddress, AvalancheError> {
+    let bytes = hex::decode(address.trim_start_matches("0x")).map_err(|e| AvalancheError::InvalidValue {
+        value: String::from(address),
+        reason: e.to_string(),
+    })?;
+    if bytes.len() != 20 {
+        return Err(AvalancheError::InvalidValue {
+            value: String::from(address),
+            reason: String::from("an EVM address is 20 bytes long"),
+        });
+    }
+    let mut evm_address = [0u8; 20];
+    evm_address.copy_from_slice(&bytes);
+    Ok(evm_address)
+}
+
+pub fn format_evm_address(address: &EvmAddress) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+pub fn parse_hex_quantity(quantity: &str) -> Result<u128, AvalancheError> {
+    u128::from_str_radix(quantity.trim_start_matches("0x"), 16).map_err(|e| AvalancheError::InvalidValue {
+        value: String::from(quantity),
+        reason: e.to_string(),
+    })
+}
+
+/// Parses a quantity which must fit in a u64, such as a chain ID, nonce or gas amount.
+pub fn parse_hex_u64(quantity: &str) -> Result<u64, AvalancheError> {
+    u64::try_from(parse_hex_quantity(quantity)?).map_err(|_| AvalancheError::InvalidValue {
+        value: String::from(quantity),
+        reason: String::from("quantity does not fit in 64 bits"),
+    })
+}
+
+pub fn format_hex_quantity(quantity: u128) -> String {
+    format!("{:#x}", quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::keychain::StandardKeyChain;
+    use crate::common::secp256k1::Secp256k1KeyChain;
+
+    fn eip155_key() -> SigningKey {
+        SigningKey::from_bytes(&[0x46; 32]).unwrap()
+    }
+
+    fn eip155_transaction() -> LegacyTransaction {
+        LegacyTransaction {
+            chain_id: 1,
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21000,
+            to: Some([0x35; 20]),
+            value: 1_000_000_000_000_000_000,
+            data: vec![],
+        }
+    }
+
+    // Example from the EIP-155 specification
+    #[test]
+    fn legacy_signing_hash_matches_eip155() {
+        let tx = EvmTransaction::Legacy(eip155_transaction());
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+    }
+
+    fn recover_signer(tx: &EvmTransaction, fields: &rlp::Rlp, v: u8) -> VerifyingKey {
+        let mut signature_bytes = [0u8; 65];
+        let count = fields.item_count().unwrap();
+        let r: Vec<u8> = fields.val_at(count - 2).unwrap();
+        let s: Vec<u8> = fields.val_at(count - 1).unwrap();
+        signature_bytes[32 - r.len()..32].copy_from_slice(&r);
+        signature_bytes[64 - s.len()..64].copy_from_slice(&s);
+        signature_bytes[64] = v;
+        let signature = recoverable::Signature::try_from(&signature_bytes[..]).unwrap();
+        signature
+            .recover_verify_key_from_digest_bytes(&tx.signing_hash().into())
+            .unwrap()
+    }
+
+    // k256 derives the RFC6979 nonce with the message digest (Keccak256), so the
+    // signature differs from the EIP-155 example while signing the same hash.
+    #[test]
+    fn legacy_signed_transaction_follows_eip155() {
+        let key = eip155_key();
+        let tx = EvmTransaction::Legacy(eip155_transaction());
+        let signed = tx.sign(&key).unwrap();
+        assert!(signed.raw_hex().starts_with(
+            "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080"
+        ));
+        let fields = rlp::Rlp::new(&signed.raw);
+        assert_eq!(fields.item_count().unwrap(), 9);
+        let v: u64 = fields.val_at(6).unwrap();
+        assert!(v == 37 || v == 38);
+        assert_eq!(recover_signer(&tx, &fields, (v - 37) as u8), key.verifying_key());
+    }
+
+    #[test]
+    fn eip1559_signature_recovers_sender() {
+        let key = eip155_key();
+        let tx = EvmTransaction::Eip1559(Eip1559Transaction {
+            chain_id: 43114,
+            nonce: 0,
+            max_priority_fee_per_gas: 1_500_000_000,
+            max_fee_per_gas: 51_500_000_000,
+            gas_limit: 21000,
+            to: Some([0x35; 20]),
+            value: 1,
+            data: vec![],
+            access_list: vec![],
+        });
+        let signed = tx.sign(&key).unwrap();
+        assert_eq!(signed.raw[0], EIP_1559_TX_TYPE);
+        let fields = rlp::Rlp::new(&signed.raw[1..]);
+        assert_eq!(fields.item_count().unwrap(), 12);
+        let y_parity: u8 = fields.val_at(9).unwrap();
+        assert_eq!(recover_signer(&tx, &fields, y_parity), key.verifying_key());
+    }
+
+    #[tokio::test]
+    async fn signers_sign_the_keccak_prehash() {
+        let mut keychain = Secp256k1KeyChain::new("C", "local");
+        let address = keychain.import_key(&[0x46; 32]).unwrap().get_short_id();
+        let tx = EvmTransaction::Legacy(eip155_transaction());
+        let signed = tx.sign_with(&keychain, &address).await.unwrap();
+        let fields = rlp::Rlp::new(&signed.raw);
+        let v: u64 = fields.val_at(6).unwrap();
+        assert_eq!(recover_signer(&tx, &fields, (v - 37) as u8), eip155_key().verifying_key());
+        assert!(tx.sign_with(&keychain, &[0; 20]).await.is_err());
+    }
+
+    #[test]
+    fn evm_address_from_public_key() {
+        let key = SigningKey::from_bytes(
+            &hex::decode(crate::utils::constants::DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY.trim_start_matches("0x")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            public_key_to_evm_address(&key.verifying_key()),
+            parse_evm_address(crate::utils::constants::DEFAULT_EVM_LOCAL_GENESIS_ADDRESS).unwrap()
+        );
+    }
+
+    #[test]
+    fn hex_quantities() {
+        assert_eq!(parse_hex_quantity("0x5d21dba00").unwrap(), 25_000_000_000);
+        assert_eq!(format_hex_quantity(25_000_000_000), "0x5d21dba00");
+        assert_eq!(format_hex_quantity(0), "0x0");
+        assert!(parse_hex_quantity("0xzz").is_err());
+        assert_eq!(parse_hex_u64("0xffffffffffffffff").unwrap(), u64::MAX);
+        assert!(parse_hex_u64("0x10000000000000000").is_err());
+    }
+}
@@ -1,22 +1,21 @@
 use crate::common::api_base::ApiBase;
+use crate::common::cache::ResponseCache;
 use crate::avalanche_core::AvalancheCore;
-use clru::CLruCache;
-use std::num::NonZeroUsize;
-use std::collections::HashMap;
+use std::sync::Arc;
 use crate::errors::AvalancheError;
 use serde::{Serialize, Deserialize};
-use crate::common::json_rpc_api::{JsonRpcApi, JsonRpcResponse, JsonRpcParams, decode_json_rpc_body};
+use crate::common::json_rpc_api::JsonRpcApi;
 
 pub struct HealthAPI {
-    core: Box<dyn AvalancheCore>,
-    cache: CLruCache<String, String>
+    core: Arc<dyn AvalancheCore>,
+    cache: ResponseCache
 }
 
 impl ApiBase for HealthAPI {
     fn get_api_base_url(&self) -> &str {
         "/ext/health"
     }
-    fn get_cache(&self) -> &CLruCache<String, String> {
+    fn get_cache(&self) -> &ResponseCache {
         &self.cache
     }
     fn get_core(&self) -> Box<&dyn AvalancheCore> {
@@ -28,10 +27,6 @@ impl JsonRpcApi for HealthAPI {
     fn get_json_rpc_version(&self) -> String {
         String::from("2.0")
     }
-
-    fn get_json_rpc_id(&self) -> u32 {
-        1
-    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -65,19 +60,19 @@ pub struct ResponseHealth {
     pub healthy: bool
 }
 
+#[derive(Serialize)]
+struct ParamsHealth<'a> {
+    alias: &'a str
+}
+
 impl HealthAPI {
-    pub fn new(core: Box<dyn AvalancheCore>) -> HealthAPI {
+    pub fn new(core: Arc<dyn AvalancheCore>) -> HealthAPI {
         HealthAPI {
             core,
-            cache: CLruCache::new(NonZeroUsize::new(2).unwrap())
+            cache: ResponseCache::default()
         }
     }
     pub async fn health(&self, alias: &'static str) -> Result<ResponseHealth, AvalancheError> {
-        let mut params = HashMap::new();
-        params.insert(String::from("alias"), JsonRpcParams::Str(alias));
-        let response = self.call_method("health.health", Some(params), None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseHealth> = decode_json_rpc_body("health.health", body)?;
-        Ok(response_formatted.result)
+        self.call_method("health.health", ParamsHealth { alias }, None, None).await
     }
 }
\ No newline at end of file
@@ -1,24 +1,22 @@
 use crate::common::api_base::ApiBase;
+use crate::common::cache::ResponseCache;
 use crate::avalanche_core::AvalancheCore;
-use clru::CLruCache;
-use std::num::NonZeroUsize;
-use std::collections::HashMap;
+use std::sync::Arc;
 use crate::errors::AvalancheError;
 use serde::{Serialize, Deserialize};
-use crate::common::json_rpc_api::{JsonRpcApi, JsonRpcResponse, JsonRpcParams, decode_json_rpc_body};
-use num_bigint::BigInt;
-use std::str::FromStr;
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::utils::amount::Avax;
 
 pub struct InfoAPI {
-    core: Box<dyn AvalancheCore>,
-    cache: CLruCache<String, String>
+    core: Arc<dyn AvalancheCore>,
+    cache: ResponseCache
 }
 
 impl ApiBase for InfoAPI {
     fn get_api_base_url(&self) -> &str {
         "/ext/info"
     }
-    fn get_cache(&self) -> &CLruCache<String, String> {
+    fn get_cache(&self) -> &ResponseCache {
         &self.cache
     }
     fn get_core(&self) -> Box<&dyn AvalancheCore> {
@@ -55,18 +53,12 @@ struct ResponseJRPCGetNodeVersion {
     pub version: String
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ResponseJRPCGetTxFee {
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ResponseGetTxFee {
     #[serde(alias = "txFee")]
-    pub tx_fee: String,
+    pub tx_fee: Avax,
     #[serde(alias = "creationTxFee")]
-    pub creation_tx_fee: String
-}
-
-#[derive(Debug, PartialEq)]
-pub struct ResponseGetTxFee {
-    pub tx_fee: BigInt,
-    pub creation_tx_fee: BigInt
+    pub creation_tx_fee: Avax
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -104,88 +96,73 @@ pub struct ResponseUptime {
     pub weighted_average_percentage: String
 }
 
+#[derive(Serialize)]
+struct ParamsGetBlockchainID<'a> {
+    alias: &'a str
+}
+
+#[derive(Serialize)]
+struct ParamsIsBootstrapped<'a> {
+    chain: &'a str
+}
+
+#[derive(Serialize)]
+struct ParamsPeers {
+    #[serde(rename = "nodeIDs")]
+    node_ids: Vec<String>
+}
+
 impl JsonRpcApi for InfoAPI {
     fn get_json_rpc_version(&self) -> String {
         String::from("2.0")
     }
-
-    fn get_json_rpc_id(&self) -> u32 {
-        1
-    }
 }
 
 //TODO: Better error management
 impl InfoAPI {
-    pub fn new(core: Box<dyn AvalancheCore>) -> InfoAPI {
+    pub fn new(core: Arc<dyn AvalancheCore>) -> InfoAPI {
         InfoAPI {
             core,
-            cache: CLruCache::new(NonZeroUsize::new(2).unwrap())
+            cache: ResponseCache::default()
         }
     }
-    pub async fn get_blockchain_id<'a>(&self, alias: &'a str) -> Result<String, AvalancheError> {
-        let mut params = HashMap::new();
-        params.insert(String::from("alias"), JsonRpcParams::Str(alias));
-        let response = self.call_method("info.getBlockchainID", Some(params), None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCGetBlockchainID> = decode_json_rpc_body("info.getBlockchainID", body)?;
-        Ok(response_formatted.result.blockchain_id)
+    pub async fn get_blockchain_id(&self, alias: &str) -> Result<String, AvalancheError> {
+        let response: ResponseJRPCGetBlockchainID = self.call_method("info.getBlockchainID", ParamsGetBlockchainID { alias }, None, None).await?;
+        Ok(response.blockchain_id)
     }
     pub async fn get_network_id(&self) -> Result<i32, AvalancheError> {
-        let response = self.call_method("info.getNetworkID", None, None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCGetNetworkID> = decode_json_rpc_body("info.getNetworkID", body)?;
-        Ok(response_formatted.result.network_id.parse::<i32>().unwrap())
+        let response: ResponseJRPCGetNetworkID = self.call_method("info.getNetworkID", (), None, None).await?;
+        response.network_id.parse::<i32>().map_err(|e| AvalancheError::InvalidValue {
+            value: response.network_id.clone(),
+            reason: e.to_string()
+        })
     }
     pub async fn get_network_name(&self) -> Result<String, AvalancheError> {
-        let response = self.call_method("info.getNetworkName", None, None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCGetNetworkName> = decode_json_rpc_body("info.getNetworkName", body)?;
-        Ok(response_formatted.result.network_name)
+        let response: ResponseJRPCGetNetworkName = self.call_method("info.getNetworkName", (), None, None).await?;
+        Ok(response.network_name)
     }
     pub async fn get_node_id(&self) -> Result<String, AvalancheError> {
-        let response = self.call_method("info.getNodeID", None, None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCGetNodeID> = decode_json_rpc_body("info.getNodeID", body)?;
-        Ok(response_formatted.result.node_id)
+        let response: ResponseJRPCGetNodeID = self.call_method("info.getNodeID", (), None, None).await?;
+        Ok(response.node_id)
     }
     pub async fn get_node_version(&self) -> Result<String, AvalancheError> {
-        let response = self.call_method("info.getNodeVersion", None, None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCGetNodeVersion> = decode_json_rpc_body("info.getNodeVersion", body)?;
-        Ok(response_formatted.result.version)
+        let response: ResponseJRPCGetNodeVersion = self.call_method("info.getNodeVersion", (), None, None).await?;
+        Ok(response.version)
     }
     pub async fn get_tx_fee(&self) -> Result<ResponseGetTxFee, AvalancheError> {
-        let response = self.call_method("info.getTxFee", None, None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCGetTxFee> = decode_json_rpc_body("info.getTxFee", body)?;
-        Ok(
-            ResponseGetTxFee {
-                tx_fee: BigInt::from_str(&response_formatted.result.tx_fee).unwrap(),
-                creation_tx_fee: BigInt::from_str(&response_formatted.result.creation_tx_fee).unwrap()
-            }
-        )
+        self.call_method("info.getTxFee", (), None, None).await
     }
-    pub async fn is_bootstrapped<'a>(&self, chain: &'a str) -> Result<bool, AvalancheError> {
-        let mut params = HashMap::new();
-        params.insert(String::from("chain"), JsonRpcParams::Str(chain));
-        let response = self.call_method("info.isBootstrapped", Some(params), None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCIsBootstrapped> = decode_json_rpc_body("info.isBootstrapped", body)?;
-        Ok(response_formatted.result.is_bootstrapped)
+    pub async fn is_bootstrapped(&self, chain: &str) -> Result<bool, AvalancheError> {
+        let response: ResponseJRPCIsBootstrapped = self.call_method("info.isBootstrapped", ParamsIsBootstrapped { chain }, None, None).await?;
+        Ok(response.is_bootstrapped)
     }
     pub async fn peers(&self, node_ids: Option<Vec<String>>) -> Result<Vec<ResponsePeers>, AvalancheError> {
-        let mut params = HashMap::new();
-        params.insert(String::from("chain"), JsonRpcParams::VecString(node_ids.unwrap_or_default()));
-        let response = self.call_method("info.peers", Some(params), None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseJRPCPeers> = decode_json_rpc_body("info.peers", body)?;
-        Ok(response_formatted.result.peers)
+        let params = ParamsPeers { node_ids: node_ids.unwrap_or_default() };
+        let response: ResponseJRPCPeers = self.call_method("info.peers", params, None, None).await?;
+        Ok(response.peers)
     }
     pub async fn uptime(&self) -> Result<ResponseUptime, AvalancheError> {
-        let response = self.call_method("info.uptime", None, None, None).await?;
-        let body = &hyper::body::to_bytes(response.into_body()).await?;
-        let response_formatted: JsonRpcResponse<ResponseUptime> = decode_json_rpc_body("info.uptime", body)?;
-        Ok(response_formatted.result)
+        self.call_method("info.uptime", (), None, None).await
     }
 }
 
@@ -197,60 +174,60 @@ mod tests {
     #[tokio::test]
     async fn get_blockchain_id_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         assert_eq!(info_api.get_blockchain_id("X").await.unwrap(), "2oYMBNV4eNHyqk2fjjV5nVQLDbtmNJzq5s3qs3Lo6ftnC6FByM");
     }
 
     #[tokio::test]
     async fn get_network_id_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         assert_eq!(info_api.get_network_id().await.unwrap(), 1);
     }
 
     #[tokio::test]
     async fn get_network_name_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         assert_eq!(info_api.get_network_name().await.unwrap(), "mainnet");
     }
 
     #[tokio::test]
     async fn get_node_version_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         assert_eq!(info_api.get_node_version().await.unwrap(), "avalanche/1.7.3");
     }
 
     #[tokio::test]
     async fn get_tx_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         assert_eq!(info_api.get_tx_fee().await.unwrap(), ResponseGetTxFee {
-            tx_fee: BigInt::from_str("1000000").unwrap(),
-            creation_tx_fee: BigInt::from_str("10000000").unwrap()
+            tx_fee: Avax::from_navax(1000000),
+            creation_tx_fee: Avax::from_navax(10000000)
         });
     }
 
     #[tokio::test]
     async fn is_bootstrapped_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
-        assert_eq!(info_api.is_bootstrapped("X").await.unwrap(), true);
+        let info_api: &InfoAPI = avalanche.info().unwrap();
+        assert!(info_api.is_bootstrapped("X").await.unwrap());
     }
 
     //TODO: Real test but at least it test if it panics
     #[tokio::test]
     async fn peers_works() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         info_api.peers(None).await.unwrap();
     }
 
     #[tokio::test]
     async fn uptime_works_with_error() {
         let avalanche = Avalanche::new(crate::utils::constants::MAINNET_API, 443, Some("https"), None, None, None, None, false).unwrap();
-        let info_api: InfoAPI = InfoAPI::new(Box::new(avalanche));
+        let info_api: &InfoAPI = avalanche.info().unwrap();
         assert_eq!(info_api.uptime().await, Err(AvalancheError::ErrorJsonRpcCall{
             call: String::from("info.uptime"),
             code: String::from("-32601"),
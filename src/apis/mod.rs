@@ -1,3 +1,5 @@
+pub mod avm;
 pub mod evm;
 pub mod info;
-pub mod health;
\ No newline at end of file
+pub mod health;
+pub mod platformvm;
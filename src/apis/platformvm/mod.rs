@@ -0,0 +1,120 @@
+use crate::common::api_base::ApiBase;
+use crate::common::cache::ResponseCache;
+use crate::avalanche_core::AvalancheCore;
+use std::sync::Arc;
+use crate::errors::AvalancheError;
+use serde::{Serialize, Deserialize};
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::common::tx_status::{ResponseJRPCGetTxStatus, TxStatus};
+use crate::common::tx::{ParamsIssueTx, ResponseIssueTx, Tx};
+use crate::common::utxo::{self, UtxoIndex, UtxoPage, UtxoSet};
+use crate::utils::amount::Avax;
+
+pub struct PlatformVMAPI {
+    core: Arc<dyn AvalancheCore>,
+    cache: ResponseCache
+}
+
+impl ApiBase for PlatformVMAPI {
+    fn get_api_base_url(&self) -> &str {
+        "/ext/bc/P"
+    }
+    fn get_cache(&self) -> &ResponseCache {
+        &self.cache
+    }
+    fn get_core(&self) -> Box<&dyn AvalancheCore> {
+        Box::new(&(*self.core))
+    }
+}
+
+impl JsonRpcApi for PlatformVMAPI {
+    fn get_json_rpc_version(&self) -> String {
+        String::from("2.0")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ResponseJRPCGetHeight {
+    pub height: String
+}
+
+/// AVAX held on the P-Chain, `balance` being the sum of the other amounts.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ResponseGetBalance {
+    pub balance: Avax,
+    #[serde(default)]
+    pub unlocked: Avax,
+    #[serde(alias = "lockedStakeable", default)]
+    pub locked_stakeable: Avax,
+    #[serde(alias = "lockedNotStakeable", default)]
+    pub locked_not_stakeable: Avax,
+    #[serde(alias = "utxoIDs", default)]
+    pub utxo_ids: Vec<UtxoId>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UtxoId {
+    #[serde(alias = "txID")]
+    pub tx_id: String,
+    #[serde(alias = "outputIndex")]
+    pub output_index: u32
+}
+
+/// AVAX staked by validators and delegators rewarding the addresses.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ResponseGetStake {
+    pub staked: Avax,
+    #[serde(alias = "stakedOutputs", default)]
+    pub staked_outputs: Vec<String>
+}
+
+#[derive(Serialize)]
+pub(crate) struct ParamsGetBalance<'a> {
+    pub addresses: &'a [String]
+}
+
+#[derive(Serialize)]
+struct ParamsGetTxStatus<'a> {
+    #[serde(rename = "txID")]
+    tx_id: &'a str
+}
+
+impl PlatformVMAPI {
+    pub fn new(core: Arc<dyn AvalancheCore>) -> PlatformVMAPI {
+        PlatformVMAPI {
+            core,
+            cache: ResponseCache::default()
+        }
+    }
+    pub async fn get_height(&self) -> Result<u64, AvalancheError> {
+        let response: ResponseJRPCGetHeight = self.call_method("platform.getHeight", (), None, None).await?;
+        response.height.parse::<u64>().map_err(|e| AvalancheError::InvalidValue {
+            value: response.height.clone(),
+            reason: e.to_string()
+        })
+    }
+    pub async fn get_balance(&self, addresses: &[String]) -> Result<ResponseGetBalance, AvalancheError> {
+        self.call_method("platform.getBalance", ParamsGetBalance { addresses }, None, None).await
+    }
+    pub async fn get_stake(&self, addresses: &[String]) -> Result<ResponseGetStake, AvalancheError> {
+        self.call_method("platform.getStake", ParamsGetBalance { addresses }, None, None).await
+    }
+    /// Page of at most `limit` UTXOs of `addresses`, the UTXOs exported to the P-Chain from
+    /// `source_chain` when given.
+    pub async fn get_utxos(&self, addresses: &[String], source_chain: Option<&str>, limit: u32, start_index: Option<&UtxoIndex>) -> Result<UtxoPage, AvalancheError> {
+        utxo::get_utxos_page(self, "platform.getUTXOs", addresses, source_chain, limit, start_index).await
+    }
+    /// Every UTXO of `addresses`, fetched page after page.
+    pub async fn get_utxo_set(&self, addresses: &[String], source_chain: Option<&str>) -> Result<UtxoSet, AvalancheError> {
+        utxo::get_utxo_set(self, "platform.getUTXOs", addresses, source_chain).await
+    }
+    /// Issues the signed `tx`, returning its ID.
+    pub async fn issue_tx(&self, tx: &Tx) -> Result<String, AvalancheError> {
+        let response: ResponseIssueTx = self.call_method("platform.issueTx", ParamsIssueTx::new(tx), None, None).await?;
+        Ok(response.tx_id)
+    }
+    pub async fn get_tx_status(&self, tx_id: &str) -> Result<TxStatus, AvalancheError> {
+        let response: ResponseJRPCGetTxStatus = self.call_method("platform.getTxStatus", ParamsGetTxStatus { tx_id }, None, None).await?;
+        TxStatus::try_from(response)
+    }
+}
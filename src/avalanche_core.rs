@@ -1,29 +1,71 @@
+use crate::common::rate_limit::RateLimiter;
+use crate::common::retry::RequestOptions;
 use crate::errors::{AvalancheError};
+use crate::utils::amount::Avax;
+use crate::utils::network_registry::NetworkDefinition;
 use std::collections::HashMap;
 use hyper::{Body};
 use hyper::client::{ResponseFuture};
-use crate::common::api_base::ApiBase;
 
-pub trait AvalancheCore {
-    fn set_address(&mut self, host: &'static str, port: u32, protocol: Option<&'static str>) -> Result<(), AvalancheError>;
+/// Parameters of the network a core is connected to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetworkParameters {
+    pub network_id: u16,
+    pub hrp: String,
+    pub x_chain_id: Option<String>,
+    pub p_chain_id: Option<String>,
+    pub c_chain_id: Option<String>,
+    pub evm_chain_id: Option<u64>,
+    pub avax_asset_id: Option<String>,
+    pub tx_fee: Option<Avax>,
+    pub creation_tx_fee: Option<Avax>
+}
+
+impl NetworkParameters {
+    pub fn from_definition(network: &NetworkDefinition) -> NetworkParameters {
+        NetworkParameters {
+            network_id: network.network_id,
+            hrp: network.hrp.clone(),
+            x_chain_id: network.x.blockchain_id.clone(),
+            p_chain_id: network.p.blockchain_id.clone(),
+            c_chain_id: network.c.blockchain_id.clone(),
+            evm_chain_id: network.c.evm_chain_id,
+            avax_asset_id: network.avax_asset_id.clone(),
+            tx_fee: network.x.tx_fee,
+            creation_tx_fee: network.x.creation_tx_fee
+        }
+    }
+}
+
+/// Connection to a node shared by every API of a client, setters use interior
+/// mutability so that a change is seen by all the APIs holding the core.
+pub trait AvalancheCore: Send + Sync {
     fn get_protocol(&self) -> &str;
     fn get_host(&self) -> &str;
     fn get_ip(&self) -> &str;
     fn get_port(&self) -> u32;
     fn get_url(&self) -> &str;
-    fn get_headers(&self) -> &HashMap<String, String>;
+    fn get_headers(&self) -> HashMap<String, String>;
+    fn get_network(&self) -> NetworkParameters;
+    fn set_network(&self, network: NetworkParameters);
     fn get_network_id(&self) -> u16;
-    fn set_network_id(&mut self, network_id: u16);
-    fn get_hrp(&self) -> &str;
-    fn set_hrp(&mut self, hrp: &'static str);
-    fn set_header(&mut self, key: &str, value: &str);
-    fn remove_header(&mut self, key: &str);
-    fn remove_all_headers(&mut self);
-    fn set_auth_token(&mut self, token: &str);
+    fn set_network_id(&self, network_id: u16) -> Result<(), AvalancheError>;
+    fn get_hrp(&self) -> String;
+    fn set_hrp(&self, hrp: &str);
+    fn set_header(&self, key: &str, value: &str);
+    fn remove_header(&self, key: &str);
+    fn remove_all_headers(&self);
+    fn set_auth_token(&self, token: &str);
+    /// Next JSON-RPC request id, unique among the requests sent through this core.
+    fn next_request_id(&self) -> u64;
+    /// Timeout and retries applied to the calls of every API sharing the core.
+    fn get_request_options(&self) -> RequestOptions;
+    fn set_request_options(&self, options: RequestOptions);
+    /// Budget shared by the JSON-RPC calls of every API using the core.
+    fn get_rate_limiter(&self) -> &RateLimiter;
     fn get(&self, url: &str, get_data: HashMap<&str, &str>, headers: HashMap<&str, &str>) -> ResponseFuture;
     fn delete(&self, url: &str, get_data: HashMap<&str, &str>, headers: HashMap<&str, &str>) -> ResponseFuture;
     fn post(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture;
     fn put(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture;
     fn patch(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture;
-    fn add_api(&mut self, api_name: &'static str, api: Box<dyn ApiBase>);
 }
@@ -0,0 +1,456 @@
+use avalanche_rs::apis::evm::transaction::{format_evm_address, parse_evm_address};
+use avalanche_rs::apis::evm::{BlockId, TransactionRequest};
+use avalanche_rs::avalanche_core::AvalancheCore;
+use avalanche_rs::common::keychain::{StandardKeyChain, StandardKeyPair};
+use avalanche_rs::common::secp256k1::{Secp256k1KeyChain, Secp256k1KeyPair};
+use avalanche_rs::errors::AvalancheError;
+use avalanche_rs::utils::address::{format_address, parse_address, parse_private_key, ShortId};
+use avalanche_rs::utils::amount::Wei;
+use avalanche_rs::utils::constants::{C_CHAIN_ALIAS, P_CHAIN_ALIAS, X_CHAIN_ALIAS};
+use avalanche_rs::utils::network_registry::NetworkRegistry;
+use avalanche_rs::Avalanche;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:9650";
+const DEFAULT_CONFIG_FILE: &str = ".avax-cli.toml";
+
+#[derive(Parser)]
+#[command(name = "avax-cli", version, about = "Command-line client of an Avalanche node")]
+struct Cli {
+    /// URL of the node [default: http://127.0.0.1:9650]
+    #[arg(long, env = "AVAX_ENDPOINT", global = true)]
+    endpoint: Option<String>,
+    /// Bearer token sent to the node
+    #[arg(long, env = "AVAX_AUTH_TOKEN", global = true, hide_env_values = true)]
+    auth_token: Option<String>,
+    /// Network of the addresses [default: network of the endpoint]
+    #[arg(long, env = "AVAX_NETWORK_ID", global = true)]
+    network_id: Option<u16>,
+    /// Output format [default: table]
+    #[arg(long, short, value_enum, env = "AVAX_OUTPUT", global = true)]
+    output: Option<OutputFormat>,
+    /// TOML file read for the options not given as flags or environment variables [default: ~/.avax-cli.toml]
+    #[arg(long, env = "AVAX_CLI_CONFIG", global = true)]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Node information
+    Info {
+        #[command(subcommand)]
+        command: InfoCommand,
+    },
+    /// Node health
+    Health {
+        #[command(subcommand)]
+        command: HealthCommand,
+    },
+    /// C-Chain EVM
+    Evm {
+        #[command(subcommand)]
+        command: EvmCommand,
+    },
+    /// Key generation and inspection
+    Key {
+        #[command(subcommand)]
+        command: KeyCommand,
+    },
+    /// Address conversion
+    Address {
+        #[command(subcommand)]
+        command: AddressCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum InfoCommand {
+    NodeId,
+    Version,
+    /// Network ID and name
+    Network,
+    Peers,
+    TxFee,
+}
+
+#[derive(Subcommand)]
+enum HealthCommand {
+    /// Checks the health once, exiting with an error if the node is unhealthy
+    Check,
+    /// Checks the health periodically
+    Watch {
+        /// Seconds between two checks
+        #[arg(long, default_value_t = 10)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum EvmCommand {
+    BaseFee,
+    /// Balance of an EVM address in AVAX
+    Balance { address: String },
+    /// Sends AVAX, or calls a contract with --data, and waits for the receipt
+    Send {
+        #[arg(long)]
+        to: String,
+        /// Amount in AVAX
+        #[arg(long, default_value = "0")]
+        value: Wei,
+        /// Hex call data
+        #[arg(long)]
+        data: Option<String>,
+        /// PrivateKey-<cb58> or hex private key
+        #[arg(long, env = "AVAX_PRIVATE_KEY", hide_env_values = true)]
+        private_key: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeyCommand {
+    /// Generates a private key
+    Generate,
+    /// Addresses of a private key
+    Show {
+        /// PrivateKey-<cb58> or hex private key
+        #[arg(long, env = "AVAX_PRIVATE_KEY", hide_env_values = true)]
+        private_key: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AddressCommand {
+    /// Re-encodes an address for another chain or HRP
+    Convert {
+        address: String,
+        #[arg(long)]
+        chain: Option<String>,
+        #[arg(long)]
+        hrp: Option<String>,
+    },
+    /// Hex of the 20 bytes of an address
+    Decode { address: String },
+    /// Address of 20 hex bytes
+    Encode {
+        hex: String,
+        #[arg(long, default_value = X_CHAIN_ALIAS)]
+        chain: String,
+        /// HRP [default: HRP of the network]
+        #[arg(long)]
+        hrp: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    Json,
+    Table,
+}
+
+/// Options of the configuration file, overridden by the flags and environment variables.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+struct Config {
+    endpoint: Option<String>,
+    auth_token: Option<String>,
+    network_id: Option<u16>,
+    output: Option<OutputFormat>,
+}
+
+impl Config {
+    fn load(path: Option<&PathBuf>) -> Result<Config, String> {
+        let (path, required) = match path {
+            Some(path) => (path.clone(), true),
+            None => match std::env::var_os("HOME") {
+                Some(home) => (PathBuf::from(home).join(DEFAULT_CONFIG_FILE), false),
+                None => return Ok(Config::default()),
+            },
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(_) if !required => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+    fn merge(self, cli: &Cli) -> Config {
+        Config {
+            endpoint: cli.endpoint.clone().or(self.endpoint),
+            auth_token: cli.auth_token.clone().or(self.auth_token),
+            network_id: cli.network_id.or(self.network_id),
+            output: cli.output.or(self.output),
+        }
+    }
+}
+
+/// Splits `endpoint` into the protocol, host and port expected by [`Avalanche::new`].
+fn parse_endpoint(endpoint: &str) -> Result<(String, String, u32), String> {
+    let url = url::Url::parse(endpoint).map_err(|e| format!("{}: {}", endpoint, e))?;
+    let host = url.host_str().ok_or_else(|| format!("{}: missing host", endpoint))?;
+    let port = url.port_or_known_default().ok_or_else(|| format!("{}: missing port", endpoint))?;
+    Ok((String::from(url.scheme()), String::from(host), port as u32))
+}
+
+fn connect(config: &Config) -> Result<Avalanche, String> {
+    let (protocol, host, port) = parse_endpoint(config.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT))?;
+    // The client keeps 'static strings, the process lives as long as it anyway
+    let avalanche = Avalanche::new(
+        Box::leak(host.into_boxed_str()),
+        port,
+        Some(Box::leak(protocol.into_boxed_str())),
+        config.network_id,
+        None,
+        None,
+        None,
+        false,
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(token) = &config.auth_token {
+        avalanche.set_auth_token(token);
+    }
+    Ok(avalanche)
+}
+
+/// HRP registered for `--network-id`, or for the network of the endpoint when none is given.
+async fn hrp(config: &Config) -> Result<String, String> {
+    let network_id = match config.network_id {
+        Some(network_id) => network_id,
+        None => {
+            let avalanche = connect(config)?;
+            let network_id = avalanche.info().map_err(|e| e.to_string())?.get_network_id().await.map_err(|e| format!("{} (give --network-id to work offline)", e))?;
+            u16::try_from(network_id).map_err(|_| format!("network ID {} out of range", network_id))?
+        }
+    };
+    let registry = NetworkRegistry::global().read().unwrap();
+    Ok(registry.get(network_id).map_err(|e| e.to_string())?.hrp.clone())
+}
+
+fn key_addresses(key_pair: &Secp256k1KeyPair, hrp: &str) -> Result<Value, AvalancheError> {
+    Ok(json!({
+        "x_address": key_pair.with_chain(X_CHAIN_ALIAS, hrp).get_address_string(),
+        "p_address": key_pair.with_chain(P_CHAIN_ALIAS, hrp).get_address_string(),
+        "c_address": format_address(C_CHAIN_ALIAS, hrp, &key_pair.get_short_id())?,
+        "evm_address": format_evm_address(&key_pair.get_evm_address()?),
+    }))
+}
+
+async fn run(command: Command, config: &Config) -> Result<Value, String> {
+    let to_string = |e: AvalancheError| e.to_string();
+    match command {
+        Command::Info { command } => {
+            let avalanche = connect(config)?;
+            let info = avalanche.info().map_err(to_string)?;
+            match command {
+                InfoCommand::NodeId => Ok(json!({"node_id": info.get_node_id().await.map_err(to_string)?})),
+                InfoCommand::Version => Ok(json!({"version": info.get_node_version().await.map_err(to_string)?})),
+                InfoCommand::Network => {
+                    let (network_id, network_name) =
+                        tokio::try_join!(info.get_network_id(), info.get_network_name()).map_err(to_string)?;
+                    Ok(json!({"network_id": network_id, "network_name": network_name}))
+                }
+                InfoCommand::Peers => serde_json::to_value(info.peers(None).await.map_err(to_string)?).map_err(|e| e.to_string()),
+                InfoCommand::TxFee => {
+                    let fee = info.get_tx_fee().await.map_err(to_string)?;
+                    Ok(json!({"tx_fee": fee.tx_fee.to_string(), "creation_tx_fee": fee.creation_tx_fee.to_string()}))
+                }
+            }
+        }
+        Command::Health { command: HealthCommand::Check } => {
+            let avalanche = connect(config)?;
+            let health = avalanche.health().map_err(to_string)?.health("").await.map_err(to_string)?;
+            if !health.healthy {
+                return Err(format!("node is unhealthy: {}", serde_json::to_string(&health.checks).unwrap_or_default()));
+            }
+            serde_json::to_value(health).map_err(|e| e.to_string())
+        }
+        Command::Health { command: HealthCommand::Watch { interval } } => {
+            let avalanche = connect(config)?;
+            let health = avalanche.health().map_err(to_string)?;
+            loop {
+                let status = match health.health("").await {
+                    Ok(response) => json!({"healthy": response.healthy}),
+                    Err(error) => json!({"healthy": false, "error": error.to_string()}),
+                };
+                print(&status, config.output.unwrap_or(OutputFormat::Table));
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        }
+        Command::Evm { command } => {
+            let avalanche = connect(config)?;
+            let evm = avalanche.evm().map_err(to_string)?;
+            match command {
+                EvmCommand::BaseFee => {
+                    let base_fee = Wei::from_hex(&evm.get_base_fee().await.map_err(to_string)?).map_err(to_string)?;
+                    Ok(json!({"base_fee_wei": base_fee.wei().to_string()}))
+                }
+                EvmCommand::Balance { address } => {
+                    let address = parse_evm_address(&address).map_err(to_string)?;
+                    let balance = Wei::from_wei(evm.get_balance(&address, BlockId::Latest).await.map_err(to_string)?);
+                    Ok(json!({"address": format_evm_address(&address), "balance": balance.to_string()}))
+                }
+                EvmCommand::Send { to, value, data, private_key } => {
+                    let mut keychain = Secp256k1KeyChain::new(C_CHAIN_ALIAS, &avalanche.get_hrp());
+                    let short_id = keychain.import_key(&parse_private_key(&private_key).map_err(to_string)?).map_err(to_string)?.get_short_id();
+                    let data = match data {
+                        Some(data) => hex::decode(data.trim_start_matches("0x")).map_err(|e| format!("--data: {}", e))?,
+                        None => Vec::new(),
+                    };
+                    let request = TransactionRequest {
+                        to: Some(parse_evm_address(&to).map_err(to_string)?),
+                        value: value.wei(),
+                        data,
+                        ..Default::default()
+                    };
+                    let receipt = evm.send_transaction(&keychain, &short_id, &request).await.map_err(to_string)?;
+                    serde_json::to_value(receipt).map_err(|e| e.to_string())
+                }
+            }
+        }
+        Command::Key { command } => {
+            let hrp = hrp(config).await?;
+            let key_pair = match command {
+                KeyCommand::Generate => Secp256k1KeyPair::generate(X_CHAIN_ALIAS, &hrp),
+                KeyCommand::Show { private_key } => {
+                    Secp256k1KeyPair::from_private_key(X_CHAIN_ALIAS, &hrp, &parse_private_key(&private_key).map_err(to_string)?)
+                }
+            }
+            .map_err(to_string)?;
+            let mut output = key_addresses(&key_pair, &hrp).map_err(to_string)?;
+            output["private_key"] = json!(key_pair.get_private_key_string());
+            output["private_key_hex"] = json!(format!("0x{}", hex::encode(key_pair.get_private_key())));
+            Ok(output)
+        }
+        Command::Address { command } => match command {
+            AddressCommand::Convert { address, chain, hrp } => {
+                let (address_chain, address_hrp, short_id) = parse_address(&address).map_err(to_string)?;
+                let chain = chain.or(address_chain).unwrap_or_else(|| String::from(X_CHAIN_ALIAS));
+                let address = format_address(&chain, &hrp.unwrap_or(address_hrp), &short_id).map_err(to_string)?;
+                Ok(json!({"address": address}))
+            }
+            AddressCommand::Decode { address } => {
+                let (chain, hrp, short_id) = parse_address(&address).map_err(to_string)?;
+                Ok(json!({"chain": chain, "hrp": hrp, "hex": format!("0x{}", hex::encode(short_id))}))
+            }
+            AddressCommand::Encode { hex, chain, hrp } => {
+                let short_id: ShortId = hex::decode(hex.trim_start_matches("0x"))
+                    .map_err(|e| e.to_string())?
+                    .try_into()
+                    .map_err(|_| String::from("an address is 20 bytes long"))?;
+                let hrp = match hrp {
+                    Some(hrp) => hrp,
+                    None => self::hrp(config).await?,
+                };
+                Ok(json!({"address": format_address(&chain, &hrp, &short_id).map_err(to_string)?}))
+            }
+        },
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Renders objects as key/value rows and arrays of objects as one row per object.
+fn render_table(value: &Value) -> String {
+    let rows: Vec<Vec<String>> = match value {
+        Value::Object(object) => object.iter().map(|(key, value)| vec![key.clone(), cell(value)]).collect(),
+        Value::Array(items) => {
+            let headers: Vec<String> = match items.first() {
+                Some(Value::Object(object)) => object.keys().cloned().collect(),
+                _ => return items.iter().map(cell).collect::<Vec<_>>().join("\n"),
+            };
+            let mut rows = vec![headers.clone()];
+            rows.extend(items.iter().map(|item| headers.iter().map(|header| cell(&item[header])).collect()));
+            rows
+        }
+        value => return cell(value),
+    };
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| rows.iter().filter_map(|row| row.get(column)).map(|cell| cell.chars().count()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let line: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+            line.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn print(value: &Value, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value).unwrap_or_default()),
+        OutputFormat::Table => println!("{}", render_table(value)),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config = match Config::load(cli.config.as_ref()) {
+        Ok(config) => config.merge(&cli),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(cli.command, &config).await {
+        Ok(value) => {
+            print(&value, config.output.unwrap_or(OutputFormat::Table));
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_override_the_config_file() {
+        let cli = Cli::parse_from(["avax-cli", "--endpoint", "https://api.avax.network", "info", "node-id"]);
+        let config: Config = toml::from_str("endpoint = \"http://127.0.0.1:9650\"\nnetwork_id = 5\noutput = \"json\"").unwrap();
+        assert_eq!(config.merge(&cli), Config {
+            endpoint: Some(String::from("https://api.avax.network")),
+            auth_token: None,
+            network_id: Some(5),
+            output: Some(OutputFormat::Json),
+        });
+        assert_eq!(
+            parse_endpoint("https://api.avax.network").unwrap(),
+            (String::from("https"), String::from("api.avax.network"), 443)
+        );
+    }
+
+    #[tokio::test]
+    async fn hrps_come_from_the_network_registry() {
+        let config = |network_id| Config { network_id: Some(network_id), ..Default::default() };
+        assert_eq!(hrp(&config(5)).await.unwrap(), "fuji");
+        assert_eq!(hrp(&config(12345)).await.unwrap(), "local");
+        assert!(hrp(&config(4242)).await.unwrap_err().contains("4242"));
+    }
+
+    #[test]
+    fn tables_align_columns() {
+        assert_eq!(render_table(&json!({"node_id": "NodeID-1", "version": "avalanche/1.7.3"})), "node_id  NodeID-1\nversion  avalanche/1.7.3");
+        assert_eq!(
+            render_table(&json!([{"ip": "1.2.3.4", "nodeID": "NodeID-1"}, {"ip": "10.0.0.1", "nodeID": "NodeID-22"}])),
+            "ip        nodeID\n1.2.3.4   NodeID-1\n10.0.0.1  NodeID-22"
+        );
+    }
+}
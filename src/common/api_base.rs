@@ -1,11 +1,10 @@
-use clru::CLruCache;
-use crate::AvalancheCore;
+use crate::avalanche_core::AvalancheCore;
+use crate::common::cache::ResponseCache;
 use core::fmt::Debug;
 
-// TODO: Add namespace to the cache
 pub trait ApiBase {
     fn get_api_base_url(&self) -> &str;
-    fn get_cache(&self) -> &CLruCache<String, String>;
+    fn get_cache(&self) -> &ResponseCache;
     fn get_core(&self) -> Box<&dyn AvalancheCore>;
 }
 
@@ -13,4 +12,4 @@ impl Debug for dyn ApiBase {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "WIP")
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,247 @@
+use crate::apis::avm::{AssetBalance, ParamsGetAllBalances, ResponseGetAllBalances};
+use crate::apis::evm::transaction::{format_evm_address, parse_evm_address, EvmAddress};
+use crate::apis::evm::{BlockId, RPC_ENDPOINT};
+use crate::avalanche_core::AvalancheCore;
+use crate::common::batch::BatchCall;
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::errors::AvalancheError;
+use crate::utils::address::{format_address, parse_address};
+use crate::utils::amount::{Avax, Wei};
+use crate::utils::constants::{C_CHAIN_ALIAS, PRIMARY_ASSET_ALIAS, P_CHAIN_ALIAS, X_CHAIN_ALIAS};
+use crate::Avalanche;
+
+/// AVAX of the addresses on the P-Chain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PChainBalance {
+    pub unlocked: Avax,
+    /// Locked until a time but usable to stake, such as vesting outputs.
+    pub locked_stakeable: Avax,
+    /// Locked until a time and not usable to stake, such as outputs of rewards.
+    pub locked_not_stakeable: Avax,
+    /// Staked by validators and delegators, returned with the rewards when the staking ends.
+    pub staked: Avax,
+}
+
+impl PChainBalance {
+    pub fn total(&self) -> Option<Avax> {
+        self.unlocked
+            .checked_add(self.locked_stakeable)?
+            .checked_add(self.locked_not_stakeable)?
+            .checked_add(self.staked)
+    }
+}
+
+/// AVAX of the EVM addresses on the C-Chain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CChainBalance {
+    pub wei: Wei,
+    /// `wei` in nAVAX, without the part below 1 nAVAX.
+    pub avax: Avax,
+}
+
+/// AVAX of addresses on the X, P and C chains, in nAVAX.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TotalBalance {
+    pub x: Avax,
+    /// Other assets of the X-Chain addresses, when requested.
+    pub x_assets: Vec<AssetBalance>,
+    pub p: PChainBalance,
+    pub c: CChainBalance,
+    /// Sum of the AVAX of the three chains.
+    pub total: Avax,
+}
+
+/// Addresses sorted by the chain they are queried on.
+#[derive(Debug, Default, PartialEq)]
+struct ChainAddresses {
+    x: Vec<String>,
+    p: Vec<String>,
+    c: Vec<EvmAddress>,
+}
+
+fn push_unique<T: PartialEq>(addresses: &mut Vec<T>, address: T) {
+    if !addresses.contains(&address) {
+        addresses.push(address);
+    }
+}
+
+/// Sorts `0x` addresses to the C-Chain and bech32 addresses to their chain, addresses without
+/// chain alias being queried on both the X and P chains. An address given twice, in any form,
+/// is queried once.
+fn sort_addresses(addresses: &[&str], hrp: &str) -> Result<ChainAddresses, AvalancheError> {
+    let mut sorted = ChainAddresses::default();
+    for address in addresses {
+        if address.starts_with("0x") {
+            push_unique(&mut sorted.c, parse_evm_address(address)?);
+            continue;
+        }
+        let (chain_alias, address_hrp, short_id) = parse_address(address)?;
+        if address_hrp != hrp {
+            return Err(AvalancheError::InvalidValue {
+                value: String::from(*address),
+                reason: format!("address is not of the {} network", hrp),
+            });
+        }
+        match chain_alias.as_deref() {
+            Some(X_CHAIN_ALIAS) => push_unique(&mut sorted.x, format_address(X_CHAIN_ALIAS, hrp, &short_id)?),
+            Some(P_CHAIN_ALIAS) => push_unique(&mut sorted.p, format_address(P_CHAIN_ALIAS, hrp, &short_id)?),
+            None => {
+                push_unique(&mut sorted.x, format_address(X_CHAIN_ALIAS, hrp, &short_id)?);
+                push_unique(&mut sorted.p, format_address(P_CHAIN_ALIAS, hrp, &short_id)?);
+            }
+            Some(chain_alias) => {
+                return Err(AvalancheError::InvalidValue {
+                    value: String::from(*address),
+                    reason: format!("balances of the {} chain are read from EVM addresses", chain_alias),
+                })
+            }
+        }
+    }
+    Ok(sorted)
+}
+
+fn overflow(chain: &str) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: String::from(chain),
+        reason: String::from("balance overflows"),
+    }
+}
+
+/// Sums the AVAX of `addresses` on the X, P and C chains, with the other X-Chain assets when
+/// `include_assets` is set.
+pub async fn get_total_balance(avalanche: &Avalanche, addresses: &[&str], include_assets: bool) -> Result<TotalBalance, AvalancheError> {
+    let sorted = sort_addresses(addresses, &avalanche.get_hrp())?;
+    let avax_asset_id = avalanche.get_network().avax_asset_id;
+    let (x_assets, p, c) = tokio::try_join!(
+        query_x_chain(avalanche, &sorted.x),
+        query_p_chain(avalanche, &sorted.p),
+        query_c_chain(avalanche, &sorted.c),
+    )?;
+    let mut balance = TotalBalance { p, c, ..Default::default() };
+    for asset in x_assets {
+        if asset.asset == PRIMARY_ASSET_ALIAS || avax_asset_id.as_deref() == Some(asset.asset.as_str()) {
+            balance.x = balance.x.checked_add(Avax::from_navax(asset.balance)).ok_or_else(|| overflow(X_CHAIN_ALIAS))?;
+        } else if include_assets {
+            balance.x_assets.push(asset);
+        }
+    }
+    balance.total = balance
+        .p
+        .total()
+        .and_then(|p| p.checked_add(balance.x))
+        .and_then(|total| total.checked_add(balance.c.avax))
+        .ok_or_else(|| overflow("total"))?;
+    Ok(balance)
+}
+
+/// Balances of every asset summed over `addresses`, in the order the assets are first seen.
+async fn query_x_chain(avalanche: &Avalanche, addresses: &[String]) -> Result<Vec<AssetBalance>, AvalancheError> {
+    if addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+    let x = avalanche.x()?;
+    let mut batch = x.batch();
+    let calls: Vec<BatchCall<ResponseGetAllBalances>> = addresses
+        .iter()
+        .map(|address| batch.add("avm.getAllBalances", ParamsGetAllBalances { address }))
+        .collect();
+    let response = batch.send().await;
+    let mut assets: Vec<AssetBalance> = Vec::new();
+    for call in calls {
+        for balance in response.get(&call)?.balances {
+            match assets.iter_mut().find(|asset| asset.asset == balance.asset) {
+                Some(asset) => asset.balance = asset.balance.checked_add(balance.balance).ok_or_else(|| overflow(X_CHAIN_ALIAS))?,
+                None => assets.push(balance),
+            }
+        }
+    }
+    Ok(assets)
+}
+
+async fn query_p_chain(avalanche: &Avalanche, addresses: &[String]) -> Result<PChainBalance, AvalancheError> {
+    if addresses.is_empty() {
+        return Ok(PChainBalance::default());
+    }
+    let p = avalanche.p()?;
+    let (balance, stake) = tokio::try_join!(p.get_balance(addresses), p.get_stake(addresses))?;
+    Ok(PChainBalance {
+        unlocked: balance.unlocked,
+        locked_stakeable: balance.locked_stakeable,
+        locked_not_stakeable: balance.locked_not_stakeable,
+        staked: stake.staked,
+    })
+}
+
+async fn query_c_chain(avalanche: &Avalanche, addresses: &[EvmAddress]) -> Result<CChainBalance, AvalancheError> {
+    if addresses.is_empty() {
+        return Ok(CChainBalance::default());
+    }
+    let evm = avalanche.evm()?;
+    let mut batch = evm.batch().with_base_api_url(RPC_ENDPOINT);
+    let calls: Vec<BatchCall<String>> = addresses
+        .iter()
+        .map(|address| batch.add("eth_getBalance", (format_evm_address(address), BlockId::Latest)))
+        .collect();
+    let response = batch.send().await;
+    let mut wei = Wei::ZERO;
+    for call in calls {
+        let balance = Wei::from_hex(&response.get(&call)?)?;
+        wei = wei.checked_add(balance).ok_or_else(|| overflow(C_CHAIN_ALIAS))?;
+    }
+    Ok(CChainBalance { wei, avax: wei.to_avax_truncated()? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::utils::constants::DEFAULT_EVM_LOCAL_GENESIS_ADDRESS;
+    use serde_json::json;
+
+    const X_ADDRESS: &str = "X-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u";
+    const BARE_ADDRESS: &str = "local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u";
+
+    #[tokio::test]
+    async fn total_balances_sum_the_three_chains() {
+        let node = test_utils::mock_json_rpc_node(|method, params| match method {
+            "avm.getAllBalances" => Ok(json!({"balances": [
+                {"asset": "AVAX", "balance": "100"},
+                {"asset": "2RMRtr6nHSPDrzSMcuEcFDa2SvDrZpP4pQFMVLLbPf3ZpdUDpL", "balance": "3"}
+            ]})),
+            "platform.getBalance" => {
+                assert_eq!(params["addresses"], json!(["P-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"]));
+                Ok(json!({"balance": "70", "unlocked": "10", "lockedStakeable": "20", "lockedNotStakeable": "40", "utxoIDs": []}))
+            }
+            "platform.getStake" => Ok(json!({"staked": "2000", "stakedOutputs": []})),
+            // 1.5 AVAX and 3 wei
+            "eth_getBalance" => Ok(json!("0x14d1120d7b160003")),
+            _ => Err((-32601, String::from("method not found"))),
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), Some(12345), None, None, None, false).unwrap();
+        let evm_address = DEFAULT_EVM_LOCAL_GENESIS_ADDRESS.to_lowercase();
+        let addresses = [X_ADDRESS, BARE_ADDRESS, DEFAULT_EVM_LOCAL_GENESIS_ADDRESS, &evm_address];
+        let balance = avalanche.get_total_balance(&addresses, true).await.unwrap();
+        assert_eq!(balance.x, Avax::from_navax(100));
+        assert_eq!(balance.x_assets, vec![AssetBalance {
+            asset: String::from("2RMRtr6nHSPDrzSMcuEcFDa2SvDrZpP4pQFMVLLbPf3ZpdUDpL"),
+            balance: 3,
+        }]);
+        assert_eq!(balance.p.total(), Some(Avax::from_navax(2070)));
+        assert_eq!(balance.c, CChainBalance { wei: Wei::from_wei(1_500_000_000_000_000_003), avax: Avax::from_navax(1_500_000_000) });
+        assert_eq!(balance.total, Avax::from_navax(1_500_002_170));
+        let without_assets = avalanche.get_total_balance(&addresses, false).await.unwrap();
+        assert!(without_assets.x_assets.is_empty());
+        assert_eq!(without_assets.total, balance.total);
+    }
+
+    #[test]
+    fn addresses_are_sorted_by_chain() {
+        let addresses = [BARE_ADDRESS, X_ADDRESS, DEFAULT_EVM_LOCAL_GENESIS_ADDRESS, &DEFAULT_EVM_LOCAL_GENESIS_ADDRESS.to_lowercase()];
+        let sorted = sort_addresses(&addresses, "local").unwrap();
+        assert_eq!(sorted.x, vec![X_ADDRESS]);
+        assert_eq!(sorted.p, vec!["P-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"]);
+        assert_eq!(sorted.c, vec![parse_evm_address(DEFAULT_EVM_LOCAL_GENESIS_ADDRESS).unwrap()]);
+        assert!(sort_addresses(&[X_ADDRESS], "avax").is_err());
+        assert!(sort_addresses(&["C-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"], "local").is_err());
+    }
+}
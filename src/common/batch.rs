@@ -0,0 +1,271 @@
+use crate::common::json_rpc_api::{send_json_rpc, JsonRpcApi, JsonRpcId};
+use crate::common::retry::{with_retries, Idempotency, RequestOptions};
+use crate::errors::AvalancheError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Source of the IDs tying the calls of a batch to its response.
+static NEXT_BATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Handle of a call queued in a [`BatchRequest`], reading its result from the [`BatchResponse`].
+pub struct BatchCall<T> {
+    batch_id: u64,
+    index: usize,
+    result: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for BatchCall<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for BatchCall<T> {}
+
+/// Calls queued to be sent as JSON-RPC 2.0 arrays, split in chunks of at most
+/// `max_batch_size` calls. Batched calls bypass the response cache.
+pub struct BatchRequest<'a, A: JsonRpcApi + ?Sized> {
+    id: u64,
+    api: &'a A,
+    base_api_url: Option<&'a str>,
+    max_batch_size: usize,
+    options: Option<RequestOptions>,
+    calls: Vec<(&'static str, Result<Option<Value>, AvalancheError>)>,
+}
+
+impl<'a, A: JsonRpcApi + ?Sized> BatchRequest<'a, A> {
+    pub fn new(api: &'a A) -> BatchRequest<'a, A> {
+        BatchRequest {
+            id: NEXT_BATCH_ID.fetch_add(1, Ordering::Relaxed),
+            api,
+            base_api_url: None,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            options: None,
+            calls: Vec::new(),
+        }
+    }
+    /// Sends the batch to `base_api_url` instead of the endpoint of the API.
+    pub fn with_base_api_url(mut self, base_api_url: &'a str) -> Self {
+        self.base_api_url = Some(base_api_url);
+        self
+    }
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+    /// Replaces the request options of the client, a chunk is retried only when all its calls are retryable.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+    /// Queues a call whose result is decoded as `T`, `params` being sent as in [`JsonRpcApi::call_method`].
+    pub fn add<T: DeserializeOwned, P: Serialize>(&mut self, method: &'static str, params: P) -> BatchCall<T> {
+        let params = match serde_json::to_value(&params) {
+            Ok(Value::Null) => Ok(None),
+            Ok(params) => Ok(Some(params)),
+            Err(error) => Err(AvalancheError::InvalidValue {
+                value: String::from(method),
+                reason: format!("params do not serialize: {}", error),
+            }),
+        };
+        self.calls.push((method, params));
+        BatchCall {
+            batch_id: self.id,
+            index: self.calls.len() - 1,
+            result: PhantomData,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+    /// Sends the chunks one after the other, a chunk failing as a whole failing every call in it.
+    pub async fn send(self) -> BatchResponse {
+        let mut results = Vec::with_capacity(self.calls.len());
+        for calls in self.calls.chunks(self.max_batch_size) {
+            results.extend(self.send_chunk(calls).await);
+        }
+        BatchResponse {
+            batch_id: self.id,
+            methods: self.calls.iter().map(|(method, _)| *method).collect(),
+            results,
+        }
+    }
+    async fn send_chunk(&self, calls: &[(&'static str, Result<Option<Value>, AvalancheError>)]) -> Vec<Result<Value, AvalancheError>> {
+        let version = self.api.get_json_rpc_version();
+        let ids: Vec<JsonRpcId> = calls.iter().map(|_| self.api.get_json_rpc_id()).collect();
+        let body: Vec<Value> = calls
+            .iter()
+            .zip(&ids)
+            .filter_map(|((method, params), id)| {
+                let mut call = json!({"jsonrpc": version, "id": id, "method": method});
+                if let Some(params) = params.as_ref().ok()? {
+                    call["params"] = params.clone();
+                }
+                Some(call)
+            })
+            .collect();
+        if body.is_empty() {
+            return calls.iter().map(|(_, params)| Err(params.clone().unwrap_err())).collect();
+        }
+        let retryable = calls.iter().all(|(method, _)| Idempotency::of(method).is_retryable());
+        let response = match self.post(serde_json::to_vec(&body).unwrap(), retryable).await {
+            Ok(response) => response,
+            Err(error) => return vec![Err(error); calls.len()],
+        };
+        let mut responses: HashMap<String, Value> = match response {
+            Value::Array(responses) => responses
+                .into_iter()
+                .filter_map(|response| {
+                    let id = serde_json::from_value::<JsonRpcId>(response["id"].clone()).ok()?;
+                    Some((id.to_string(), response))
+                })
+                .collect(),
+            response => {
+                // The node rejected the whole batch
+                return calls.iter().map(|(method, _)| Err(call_error(method, &response))).collect();
+            }
+        };
+        calls
+            .iter()
+            .zip(&ids)
+            .map(|((method, params), id)| match responses.remove(&id.to_string()) {
+                _ if params.is_err() => Err(params.clone().unwrap_err()),
+                Some(response) if response.get("error").is_some() => Err(call_error(method, &response)),
+                Some(mut response) => Ok(response["result"].take()),
+                None => Err(AvalancheError::MissingResponse { call: String::from(*method) }),
+            })
+            .collect()
+    }
+    async fn post(&self, body: Vec<u8>, retryable: bool) -> Result<Value, AvalancheError> {
+        let core = *self.api.get_core();
+        let options = self.options.unwrap_or_else(|| core.get_request_options());
+        let url = self.api.get_json_rpc_url(self.base_api_url);
+        let headers = HashMap::new();
+        let (_, body) = with_retries(&options, retryable, "batch", || {
+            send_json_rpc(core, &url, body.clone(), &headers)
+        }).await?;
+        serde_json::from_slice(&body).map_err(|error| AvalancheError::InvalidValue {
+            value: String::from_utf8_lossy(&body).into_owned(),
+            reason: error.to_string(),
+        })
+    }
+}
+
+fn call_error(method: &str, response: &Value) -> AvalancheError {
+    AvalancheError::ErrorJsonRpcCall {
+        call: String::from(method),
+        code: response["error"]["code"].to_string(),
+        message: response["error"]["message"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// Results of a sent [`BatchRequest`], in the order the calls were queued.
+pub struct BatchResponse {
+    batch_id: u64,
+    methods: Vec<&'static str>,
+    results: Vec<Result<Value, AvalancheError>>,
+}
+
+impl BatchResponse {
+    /// Result of `call`, [`AvalancheError::MissingResponse`] for a call queued in another batch.
+    pub fn get<T: DeserializeOwned>(&self, call: &BatchCall<T>) -> Result<T, AvalancheError> {
+        match (self.methods.get(call.index), self.results.get(call.index)) {
+            (Some(method), Some(result)) if call.batch_id == self.batch_id => decode_result(method, result.clone()),
+            _ => Err(AvalancheError::MissingResponse {
+                call: format!("call {} of batch {}", call.index, call.batch_id),
+            }),
+        }
+    }
+    /// Decodes every result as `T`, for batches of calls to the same method.
+    pub fn into_results<T: DeserializeOwned>(self) -> Vec<Result<T, AvalancheError>> {
+        self.methods
+            .into_iter()
+            .zip(self.results)
+            .map(|(method, result)| decode_result(method, result))
+            .collect()
+    }
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+fn decode_result<T: DeserializeOwned>(method: &str, result: Result<Value, AvalancheError>) -> Result<T, AvalancheError> {
+    let result = result?;
+    serde_json::from_value(result.clone()).map_err(|error| AvalancheError::InvalidValue {
+        value: result.to_string(),
+        reason: format!("{}: {}", method, error),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::Avalanche;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct BlockchainID {
+        #[serde(alias = "blockchainID")]
+        blockchain_id: String,
+    }
+
+    #[tokio::test]
+    async fn results_are_matched_by_id_and_chunked() {
+        let node = test_utils::mock_json_rpc_node(|method, params| match method {
+            "info.getBlockchainID" => Ok(serde_json::json!({"blockchainID": params["alias"]})),
+            "info.getNodeID" => Ok(serde_json::json!({"nodeID": "NodeID-1"})),
+            _ => Err((-32601, String::from("method not found"))),
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let info = avalanche.info().unwrap();
+        let mut batch = info.batch().with_max_batch_size(2);
+        let x = batch.add::<BlockchainID, _>("info.getBlockchainID", json!({"alias": "X"}));
+        let unknown = batch.add::<Value, _>("info.unknown", ());
+        let p = batch.add::<BlockchainID, _>("info.getBlockchainID", json!({"alias": "P"}));
+        let node_id = batch.add::<BlockchainID, _>("info.getNodeID", ());
+        assert_eq!(batch.len(), 4);
+        let response = batch.send().await;
+        assert_eq!(node.request_count(), 2);
+        assert_eq!(response.get(&x).unwrap().blockchain_id, "X");
+        assert_eq!(response.get(&p).unwrap().blockchain_id, "P");
+        assert_eq!(response.get(&unknown).unwrap_err(), AvalancheError::ErrorJsonRpcCall {
+            call: String::from("info.unknown"),
+            code: String::from("-32601"),
+            message: String::from("method not found"),
+        });
+        assert!(matches!(response.get(&node_id), Err(AvalancheError::InvalidValue { .. })));
+    }
+
+    #[tokio::test]
+    async fn homogeneous_batches_decode_every_result() {
+        let node = test_utils::mock_json_rpc_node(|_, params| Ok(params["alias"].clone())).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let info = avalanche.info().unwrap();
+        let mut batch = info.batch();
+        for alias in ["X", "P", "C"] {
+            batch.add::<String, _>("info.getBlockchainID", json!({"alias": alias}));
+        }
+        let mut other_batch = info.batch();
+        let foreign: Vec<BatchCall<String>> = (0..4).map(|_| other_batch.add("info.getBlockchainID", json!({"alias": "X"}))).collect();
+        let response = batch.send().await;
+        for call in &foreign {
+            assert!(matches!(response.get(call), Err(AvalancheError::MissingResponse { .. })));
+        }
+        let results = response.into_results::<String>();
+        assert_eq!(results, vec![Ok(String::from("X")), Ok(String::from("P")), Ok(String::from("C"))]);
+        assert_eq!(node.request_count(), 1);
+    }
+}
@@ -0,0 +1,187 @@
+use clru::CLruCache;
+use hyper::body::Bytes;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_CACHE_CAPACITY: usize = 256;
+pub const SHORT_LIVED_TTL: Duration = Duration::from_secs(2);
+
+/// How long a successful response of a method stays valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheTtl {
+    /// The answer never changes, e.g. a blockchain ID or a transaction fetched by ID.
+    Immutable,
+    Expires(Duration),
+}
+
+/// Default TTL of each method, `None` for the methods which are never cached.
+pub fn default_ttl(method: &str) -> Option<CacheTtl> {
+    match method {
+        "info.getBlockchainID"
+        | "info.getNetworkID"
+        | "info.getNetworkName"
+        | "eth_chainId"
+        | "avm.getAssetDescription"
+        | "avm.getTx"
+        | "platform.getTx"
+        | "avax.getAtomicTx" => Some(CacheTtl::Immutable),
+        "info.getTxFee"
+        | "eth_baseFee"
+        | "eth_maxPriorityFeePerGas"
+        | "eth_blockNumber"
+        | "platform.getHeight" => Some(CacheTtl::Expires(SHORT_LIVED_TTL)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl CacheStats {
+    pub fn merge(self, other: CacheStats) -> CacheStats {
+        CacheStats {
+            hits: self.hits + other.hits,
+            misses: self.misses + other.misses,
+            entries: self.entries + other.entries,
+        }
+    }
+}
+
+struct CacheEntry {
+    body: Bytes,
+    expires_at: Option<Instant>,
+}
+
+/// Opt-in cache of JSON-RPC responses keyed by (endpoint, method, params).
+/// Only successful responses of methods having a TTL are stored.
+pub struct ResponseCache {
+    enabled: AtomicBool,
+    entries: Mutex<CLruCache<String, CacheEntry>>,
+    ttls: RwLock<HashMap<String, Option<CacheTtl>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        ResponseCache::new(NonZeroUsize::new(DEFAULT_CACHE_CAPACITY).unwrap())
+    }
+}
+
+impl ResponseCache {
+    pub fn new(capacity: NonZeroUsize) -> ResponseCache {
+        ResponseCache {
+            enabled: AtomicBool::new(false),
+            entries: Mutex::new(CLruCache::new(capacity)),
+            ttls: RwLock::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.clear();
+        }
+    }
+    /// Overrides the TTL of `method`, `None` disables caching of it.
+    pub fn set_ttl(&self, method: &str, ttl: Option<CacheTtl>) {
+        self.ttls.write().unwrap().insert(String::from(method), ttl);
+    }
+    /// TTL to apply to `method`, `None` when the cache is disabled or the method is not cacheable.
+    pub fn ttl(&self, method: &str) -> Option<CacheTtl> {
+        if !self.is_enabled() {
+            return None;
+        }
+        match self.ttls.read().unwrap().get(method) {
+            Some(ttl) => *ttl,
+            None => default_ttl(method),
+        }
+    }
+    pub fn key(endpoint: &str, method: &str, params: &str) -> String {
+        format!("{} {} {}", endpoint, method, params)
+    }
+    pub fn get(&self, key: &str) -> Option<Bytes> {
+        let mut entries = self.entries.lock().unwrap();
+        let body = match entries.get(key) {
+            Some(entry) if entry.expires_at.is_none_or(|expires_at| Instant::now() < expires_at) => {
+                Some(entry.body.clone())
+            }
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        };
+        match body {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        body
+    }
+    pub fn insert(&self, key: String, body: Bytes, ttl: CacheTtl) {
+        let expires_at = match ttl {
+            CacheTtl::Immutable => None,
+            CacheTtl::Expires(duration) => Some(Instant::now() + duration),
+        };
+        self.entries.lock().unwrap().put(key, CacheEntry { body, expires_at });
+    }
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_by_default() {
+        let cache = ResponseCache::default();
+        assert_eq!(cache.ttl("info.getBlockchainID"), None);
+        cache.set_enabled(true);
+        assert_eq!(cache.ttl("info.getBlockchainID"), Some(CacheTtl::Immutable));
+        assert_eq!(cache.ttl("info.peers"), None);
+    }
+
+    #[test]
+    fn overridden_ttls() {
+        let cache = ResponseCache::default();
+        cache.set_enabled(true);
+        cache.set_ttl("info.peers", Some(CacheTtl::Expires(Duration::from_secs(10))));
+        cache.set_ttl("info.getBlockchainID", None);
+        assert_eq!(cache.ttl("info.peers"), Some(CacheTtl::Expires(Duration::from_secs(10))));
+        assert_eq!(cache.ttl("info.getBlockchainID"), None);
+    }
+
+    #[test]
+    fn entries_expire() {
+        let cache = ResponseCache::default();
+        cache.set_enabled(true);
+        cache.insert(String::from("a"), Bytes::from("1"), CacheTtl::Immutable);
+        cache.insert(String::from("b"), Bytes::from("2"), CacheTtl::Expires(Duration::ZERO));
+        assert_eq!(cache.get("a"), Some(Bytes::from("1")));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), None);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2, entries: 1 });
+        cache.set_enabled(false);
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
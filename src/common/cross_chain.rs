@@ -0,0 +1,636 @@
+use crate::apis::evm::BlockId;
+use crate::avalanche_core::AvalancheCore;
+use crate::common::retry::{is_transient, RetryPolicy};
+use crate::common::signer::{evm_address, Signer};
+use crate::common::tx::{sign_tx_with, signed_len, sort_outputs, spend_utxos, BaseTx, EvmInput, EvmOutput, TransferableOutput, Tx, UnsignedTx, Vm};
+use crate::common::tx_status::{poll_until, FinalTxStatus, TxChain, TxStatus};
+use crate::common::utxo::{Output, OutputOwners, Utxo};
+use crate::errors::AvalancheError;
+use crate::utils::address::{format_address, ShortId};
+use crate::utils::amount::{Avax, Denomination, Wei, WEI_PER_NAVAX};
+use crate::utils::codec::{format_id, parse_id, Id};
+use crate::utils::constants::{C_CHAIN_ALIAS, P_CHAIN_ALIAS, X_CHAIN_ALIAS};
+use crate::Avalanche;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time waited for the acceptance of each transaction and for the exported UTXOs.
+pub const CROSS_CHAIN_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Gas of the atomic transactions of the C-Chain since Apricot Phase 5
+const ATOMIC_TX_FIXED_GAS: u64 = 10_000;
+const ATOMIC_TX_GAS_PER_BYTE: u64 = 1;
+const ATOMIC_TX_GAS_PER_SIGNATURE: u64 = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Chain {
+    X,
+    P,
+    C,
+}
+
+impl Chain {
+    pub fn alias(self) -> &'static str {
+        match self {
+            Chain::X => X_CHAIN_ALIAS,
+            Chain::P => P_CHAIN_ALIAS,
+            Chain::C => C_CHAIN_ALIAS,
+        }
+    }
+    fn tx_chain(self) -> TxChain {
+        match self {
+            Chain::X => TxChain::X,
+            Chain::P => TxChain::P,
+            Chain::C => TxChain::CAtomic,
+        }
+    }
+}
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-Chain", self.alias())
+    }
+}
+
+/// Last step of a cross-chain transfer that succeeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferStep {
+    Started,
+    /// The export is signed and its ID recorded, it may or may not have reached the node.
+    ExportIssued,
+    ExportAccepted,
+    /// The import is signed and its ID recorded, it may or may not have reached the node.
+    ImportIssued,
+    Completed,
+}
+
+/// Progress of a transfer of AVAX between two chains. Saving it after a failure lets
+/// [`Avalanche::resume_cross_chain`] carry on without issuing the transactions again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrossChainTransfer {
+    pub from: Chain,
+    pub to: Chain,
+    /// AVAX exported, the fee of the import being paid out of it.
+    pub amount: Avax,
+    pub step: TransferStep,
+    pub export_tx_id: Option<String>,
+    /// Signed export in hex, issued again on resume if its chain does not know it.
+    pub export_tx: Option<String>,
+    pub import_tx_id: Option<String>,
+    /// Signed import in hex, issued again on resume if its chain does not know it.
+    pub import_tx: Option<String>,
+}
+
+impl CrossChainTransfer {
+    pub fn new(from: Chain, to: Chain, amount: Avax) -> Result<CrossChainTransfer, AvalancheError> {
+        if from == to {
+            return Err(AvalancheError::InvalidValue {
+                value: to.to_string(),
+                reason: String::from("transfers go to another chain"),
+            });
+        }
+        if amount == Avax::ZERO {
+            return Err(AvalancheError::InvalidValue {
+                value: amount.to_string(),
+                reason: String::from("nothing to transfer"),
+            });
+        }
+        Ok(CrossChainTransfer {
+            from,
+            to,
+            amount,
+            step: TransferStep::Started,
+            export_tx_id: None,
+            export_tx: None,
+            import_tx_id: None,
+            import_tx: None,
+        })
+    }
+    fn export_issued(&mut self, tx: &Tx) {
+        self.step = TransferStep::ExportIssued;
+        self.export_tx_id = Some(tx.id_string());
+        self.export_tx = Some(tx.to_hex());
+    }
+    fn export_failed(&mut self) {
+        self.step = TransferStep::Started;
+        self.export_tx_id = None;
+        self.export_tx = None;
+    }
+    fn import_issued(&mut self, tx: &Tx) {
+        self.step = TransferStep::ImportIssued;
+        self.import_tx_id = Some(tx.id_string());
+        self.import_tx = Some(tx.to_hex());
+    }
+    fn import_failed(&mut self) {
+        self.step = TransferStep::ExportAccepted;
+        self.import_tx_id = None;
+        self.import_tx = None;
+    }
+}
+
+/// Parameters of the network the transactions are built for.
+struct NetworkContext {
+    network_id: u32,
+    hrp: String,
+    avax_asset_id: Id,
+    x_chain_id: Id,
+    p_chain_id: Id,
+    c_chain_id: Id,
+    tx_fee: u64,
+}
+
+impl NetworkContext {
+    fn load(avalanche: &Avalanche) -> Result<NetworkContext, AvalancheError> {
+        let network = avalanche.get_network();
+        let id = |id: Option<String>, name: &str| {
+            id.ok_or_else(|| AvalancheError::InvalidValue {
+                value: String::from(name),
+                reason: format!("unknown on network {}", network.network_id),
+            })
+            .and_then(|id| parse_id(&id))
+        };
+        Ok(NetworkContext {
+            network_id: u32::from(network.network_id),
+            hrp: network.hrp.clone(),
+            avax_asset_id: id(network.avax_asset_id.clone(), "AVAX asset ID")?,
+            x_chain_id: id(network.x_chain_id.clone(), "X-Chain ID")?,
+            p_chain_id: id(network.p_chain_id.clone(), "P-Chain ID")?,
+            c_chain_id: id(network.c_chain_id.clone(), "C-Chain ID")?,
+            tx_fee: network.tx_fee.unwrap_or(Avax::from_navax(Denomination::MilliAvax.navax())).navax(),
+        })
+    }
+    fn chain_id(&self, chain: Chain) -> Id {
+        match chain {
+            Chain::X => self.x_chain_id,
+            Chain::P => self.p_chain_id,
+            Chain::C => self.c_chain_id,
+        }
+    }
+    fn addresses(&self, chain: Chain, short_ids: &[ShortId]) -> Result<Vec<String>, AvalancheError> {
+        short_ids.iter().map(|short_id| format_address(chain.alias(), &self.hrp, short_id)).collect()
+    }
+    fn transfer_output(&self, amount: u64, owner: ShortId) -> TransferableOutput {
+        let owners = OutputOwners { locktime: 0, threshold: 1, addresses: vec![owner] };
+        TransferableOutput { asset_id: self.avax_asset_id, output: Output::Transfer { amount, owners } }
+    }
+}
+
+fn vm(chain: Chain) -> Vm {
+    match chain {
+        Chain::X => Vm::Avm,
+        Chain::P => Vm::Platform,
+        Chain::C => Vm::Evm,
+    }
+}
+
+/// Fee in nAVAX of the atomic transaction `unsigned` of the C-Chain, once signed by `signers`, at
+/// `base_fee` per gas.
+fn atomic_tx_fee(unsigned: &UnsignedTx, signers: &[Vec<ShortId>], base_fee: Wei) -> Result<u64, AvalancheError> {
+    let signatures: usize = signers.iter().map(Vec::len).sum();
+    let gas = ATOMIC_TX_FIXED_GAS + signed_len(unsigned, signers) as u64 * ATOMIC_TX_GAS_PER_BYTE + signatures as u64 * ATOMIC_TX_GAS_PER_SIGNATURE;
+    let fee = (gas as u128).saturating_mul(base_fee.wei()).div_ceil(WEI_PER_NAVAX);
+    u64::try_from(fee).map_err(|_| AvalancheError::InvalidValue {
+        value: base_fee.to_string(),
+        reason: String::from("fee overflows"),
+    })
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn overflow(amount: u64) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: amount.to_string(),
+        reason: String::from("amount and fee overflow"),
+    }
+}
+
+fn insufficient(needed: u64, available: u64) -> AvalancheError {
+    AvalancheError::InsufficientFunds { asset: String::from("AVAX"), needed, available }
+}
+
+/// Carries `transfer` on from its last step until the import is accepted.
+pub async fn run_transfer(avalanche: &Avalanche, mut transfer: CrossChainTransfer, signer: &dyn Signer) -> Result<CrossChainTransfer, AvalancheError> {
+    match advance(avalanche, &mut transfer, signer).await {
+        Ok(()) => Ok(transfer),
+        Err(error) => Err(AvalancheError::TransferInterrupted { transfer: Box::new(transfer), error: Box::new(error) }),
+    }
+}
+
+async fn advance(avalanche: &Avalanche, transfer: &mut CrossChainTransfer, signer: &dyn Signer) -> Result<(), AvalancheError> {
+    let context = NetworkContext::load(avalanche)?;
+    let short_ids = signer.addresses().await?;
+    let key = *short_ids.first().ok_or_else(|| AvalancheError::InvalidValue {
+        value: String::from("signer"),
+        reason: String::from("signer without keys"),
+    })?;
+    // Whether the transaction of the current step was issued by this run rather than by an earlier one
+    let mut issued = false;
+    loop {
+        match transfer.step {
+            TransferStep::Started => {
+                let tx = build_export(avalanche, &context, transfer, signer, key, &short_ids).await?;
+                // The ID is recorded first so that a lost answer does not lead to a second export
+                transfer.export_issued(&tx);
+                if let Err(error) = issue_tx(avalanche, transfer.from, &tx).await {
+                    if !is_transient(&error) {
+                        transfer.export_failed();
+                    }
+                    return Err(error);
+                }
+                issued = true;
+            }
+            TransferStep::ExportIssued => {
+                let tx_id = transfer.export_tx_id.clone().unwrap_or_default();
+                if !issued {
+                    reissue_if_unknown(avalanche, transfer.from, &tx_id, transfer.export_tx.as_deref()).await?;
+                }
+                match avalanche.wait_for_tx(&tx_id, transfer.from.tx_chain(), CROSS_CHAIN_TIMEOUT).await? {
+                    FinalTxStatus::Accepted => transfer.step = TransferStep::ExportAccepted,
+                    status => {
+                        transfer.export_failed();
+                        return Err(AvalancheError::TxNotAccepted { tx_id, status: format!("{:?}", status) });
+                    }
+                }
+            }
+            TransferStep::ExportAccepted => {
+                let utxos = wait_for_exported_utxos(avalanche, &context, transfer, &short_ids).await?;
+                let tx = build_import(avalanche, &context, transfer, signer, key, &short_ids, &utxos).await?;
+                transfer.import_issued(&tx);
+                if let Err(error) = issue_tx(avalanche, transfer.to, &tx).await {
+                    if !is_transient(&error) {
+                        transfer.import_failed();
+                    }
+                    return Err(error);
+                }
+                issued = true;
+            }
+            TransferStep::ImportIssued => {
+                let tx_id = transfer.import_tx_id.clone().unwrap_or_default();
+                if !issued {
+                    reissue_if_unknown(avalanche, transfer.to, &tx_id, transfer.import_tx.as_deref()).await?;
+                }
+                match avalanche.wait_for_tx(&tx_id, transfer.to.tx_chain(), CROSS_CHAIN_TIMEOUT).await? {
+                    FinalTxStatus::Accepted => transfer.step = TransferStep::Completed,
+                    status => {
+                        transfer.import_failed();
+                        return Err(AvalancheError::TxNotAccepted { tx_id, status: format!("{:?}", status) });
+                    }
+                }
+            }
+            TransferStep::Completed => return Ok(()),
+        }
+    }
+}
+
+async fn issue_tx(avalanche: &Avalanche, chain: Chain, tx: &Tx) -> Result<String, AvalancheError> {
+    match chain {
+        Chain::X => avalanche.x()?.issue_tx(tx).await,
+        Chain::P => avalanche.p()?.issue_tx(tx).await,
+        Chain::C => avalanche.evm()?.issue_tx(tx).await,
+    }
+}
+
+/// Issues the signed `tx` of a resumed transfer again if `chain` does not know `tx_id`, the
+/// issuance that was interrupted having possibly not reached the node. The same bytes carry the
+/// same ID, so a node that already has them does not execute them twice.
+async fn reissue_if_unknown(avalanche: &Avalanche, chain: Chain, tx_id: &str, tx: Option<&str>) -> Result<(), AvalancheError> {
+    let tx = match tx {
+        Some(tx) => Tx::from_hex(vm(chain), tx)?,
+        None => return Ok(()),
+    };
+    if tx.id_string() != tx_id {
+        return Err(AvalancheError::InvalidValue {
+            value: String::from(tx_id),
+            reason: String::from("the signed transaction of the transfer has another ID"),
+        });
+    }
+    if avalanche.get_tx_status(tx_id, chain.tx_chain()).await? == TxStatus::Unknown {
+        issue_tx(avalanche, chain, &tx).await?;
+    }
+    Ok(())
+}
+
+async fn base_fee(avalanche: &Avalanche) -> Result<Wei, AvalancheError> {
+    Wei::from_hex(&avalanche.evm()?.get_base_fee().await?)
+}
+
+async fn build_export(
+    avalanche: &Avalanche,
+    context: &NetworkContext,
+    transfer: &CrossChainTransfer,
+    signer: &dyn Signer,
+    key: ShortId,
+    short_ids: &[ShortId],
+) -> Result<Tx, AvalancheError> {
+    let amount = transfer.amount.navax();
+    let exported_outputs = vec![context.transfer_output(amount, key)];
+    let destination_chain = context.chain_id(transfer.to);
+    if transfer.from == Chain::C {
+        let address = evm_address(signer, &key).await?;
+        let nonce = avalanche.evm()?.get_transaction_count(&address, BlockId::Pending).await?;
+        let base_fee = base_fee(avalanche).await?;
+        let signers = [vec![key]];
+        let build = |fee: u64| -> Result<UnsignedTx, AvalancheError> {
+            let input = EvmInput { address, amount: amount.checked_add(fee).ok_or_else(|| overflow(amount))?, asset_id: context.avax_asset_id, nonce };
+            Ok(UnsignedTx::EvmExport {
+                network_id: context.network_id,
+                blockchain_id: context.c_chain_id,
+                destination_chain,
+                inputs: vec![input],
+                exported_outputs: exported_outputs.clone(),
+            })
+        };
+        let unsigned = build(atomic_tx_fee(&build(0)?, &signers, base_fee)?)?;
+        return sign_tx_with(unsigned, &signers, signer).await;
+    }
+    let addresses = context.addresses(transfer.from, short_ids)?;
+    let utxos = match transfer.from {
+        Chain::X => avalanche.x()?.get_utxo_set(&addresses, None).await?,
+        _ => avalanche.p()?.get_utxo_set(&addresses, None).await?,
+    };
+    let needed = amount.checked_add(context.tx_fee).ok_or_else(|| overflow(amount))?;
+    let mut selected = Vec::new();
+    let mut total: u64 = 0;
+    for utxo in utxos.spendable(short_ids, &context.avax_asset_id, unix_time()) {
+        if total >= needed {
+            break;
+        }
+        total = total.saturating_add(utxo.output.amount());
+        selected.push(utxo);
+    }
+    if total < needed {
+        return Err(insufficient(needed, total));
+    }
+    let spent = spend_utxos(selected, short_ids)?;
+    let mut outputs = Vec::new();
+    if spent.total > needed {
+        outputs.push(context.transfer_output(spent.total - needed, key));
+    }
+    sort_outputs(&mut outputs);
+    let base = BaseTx {
+        network_id: context.network_id,
+        blockchain_id: context.chain_id(transfer.from),
+        outputs,
+        inputs: spent.inputs,
+        memo: Vec::new(),
+    };
+    let unsigned = UnsignedTx::Export { vm: vm(transfer.from), base, destination_chain, exported_outputs };
+    sign_tx_with(unsigned, &spent.signers, signer).await
+}
+
+/// UTXOs of the export waiting in the atomic memory of the destination chain.
+async fn wait_for_exported_utxos(
+    avalanche: &Avalanche,
+    context: &NetworkContext,
+    transfer: &CrossChainTransfer,
+    short_ids: &[ShortId],
+) -> Result<Vec<Utxo>, AvalancheError> {
+    let export_tx_id = transfer.export_tx_id.clone().unwrap_or_default();
+    let export_id = parse_id(&export_tx_id)?;
+    let addresses = &context.addresses(transfer.to, short_ids)?;
+    let source_chain = &format_id(&context.chain_id(transfer.from));
+    let to = transfer.to;
+    let operation = format!("UTXOs exported by {} to the {}", export_tx_id, to);
+    poll_until(operation, CROSS_CHAIN_TIMEOUT, &RetryPolicy::default(), || async move {
+        let utxos = match to {
+            Chain::X => avalanche.x()?.get_utxo_set(addresses, Some(source_chain)).await?,
+            Chain::P => avalanche.p()?.get_utxo_set(addresses, Some(source_chain)).await?,
+            Chain::C => avalanche.evm()?.get_utxo_set(addresses, source_chain).await?,
+        };
+        let exported: Vec<Utxo> = utxos.by_tx(&export_id).into_iter().cloned().collect();
+        Ok((!exported.is_empty()).then_some(exported))
+    })
+    .await
+}
+
+async fn build_import(
+    avalanche: &Avalanche,
+    context: &NetworkContext,
+    transfer: &CrossChainTransfer,
+    signer: &dyn Signer,
+    key: ShortId,
+    short_ids: &[ShortId],
+    utxos: &[Utxo],
+) -> Result<Tx, AvalancheError> {
+    let spent = spend_utxos(utxos, short_ids)?;
+    let source_chain = context.chain_id(transfer.from);
+    if transfer.to == Chain::C {
+        let address = evm_address(signer, &key).await?;
+        let base_fee = base_fee(avalanche).await?;
+        let build = |fee: u64| -> Result<UnsignedTx, AvalancheError> {
+            let amount = spent.total.checked_sub(fee).filter(|amount| *amount > 0).ok_or_else(|| insufficient(fee, spent.total))?;
+            Ok(UnsignedTx::EvmImport {
+                network_id: context.network_id,
+                blockchain_id: context.c_chain_id,
+                source_chain,
+                imported_inputs: spent.inputs.clone(),
+                outputs: vec![EvmOutput { address, amount, asset_id: context.avax_asset_id }],
+            })
+        };
+        let unsigned = build(atomic_tx_fee(&build(0)?, &spent.signers, base_fee)?)?;
+        return sign_tx_with(unsigned, &spent.signers, signer).await;
+    }
+    let amount = spent
+        .total
+        .checked_sub(context.tx_fee)
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| insufficient(context.tx_fee, spent.total))?;
+    let base = BaseTx {
+        network_id: context.network_id,
+        blockchain_id: context.chain_id(transfer.to),
+        outputs: vec![context.transfer_output(amount, key)],
+        inputs: Vec::new(),
+        memo: Vec::new(),
+    };
+    let unsigned = UnsignedTx::Import { vm: vm(transfer.to), base, source_chain, imported_inputs: spent.inputs };
+    sign_tx_with(unsigned, &spent.signers, signer).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::evm::transaction::parse_evm_address;
+    use crate::common::keychain::StandardKeyChain;
+    use crate::common::retry::RequestOptions;
+    use crate::common::secp256k1::Secp256k1KeyChain;
+    use crate::test_utils;
+    use hyper::{Body, Response, StatusCode};
+    use crate::utils::address::cb58_decode;
+    use crate::utils::codec::decode_hex_with_checksum;
+    use crate::utils::constants::{DEFAULT_EVM_LOCAL_GENESIS_ADDRESS, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY};
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+    use std::sync::{Arc, Mutex};
+
+    const LOCAL_AVAX: &str = "2fombhL7aGPwj3KH4bfrmJwW6PVnMobf9Y2fn9GwxiAAJyFDbe";
+
+    #[derive(Default)]
+    struct MockChains {
+        issued: Vec<(String, Vec<u8>)>,
+        atomic_polls: usize,
+        import_failures: usize,
+    }
+
+    fn utxo_page(utxos: Vec<Utxo>) -> Value {
+        let utxos: Vec<String> = utxos.iter().map(Utxo::to_hex).collect();
+        json!({"numFetched": utxos.len().to_string(), "utxos": utxos, "endIndex": {"address": "", "utxo": ""}, "encoding": "hex"})
+    }
+
+    fn owned_by(tx_id: Id, output_index: u32, amount: u64, owner: ShortId) -> Utxo {
+        let owners = OutputOwners { locktime: 0, threshold: 1, addresses: vec![owner] };
+        Utxo { tx_id, output_index, asset_id: parse_id(LOCAL_AVAX).unwrap(), output: Output::Transfer { amount, owners } }
+    }
+
+    fn contains(bytes: &[u8], part: &[u8]) -> bool {
+        bytes.windows(part.len()).any(|window| window == part)
+    }
+
+    #[tokio::test]
+    async fn transfers_resume_after_a_failed_import() {
+        let mut keys = Secp256k1KeyChain::new("X", "local");
+        let owner = keys.import_key(&cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap().get_short_id();
+        let chains = Arc::new(Mutex::new(MockChains { import_failures: 1, ..Default::default() }));
+        let state = chains.clone();
+        let node = test_utils::mock_json_rpc_node(move |method, params| {
+            let mut chains = state.lock().unwrap();
+            match method {
+                "avm.getUTXOs" => Ok(utxo_page(vec![owned_by([7; 32], 0, 10_000_000_000, owner)])),
+                "avax.getUTXOs" => {
+                    assert_eq!(params["sourceChain"], "2eNy1mUFdmaxXNj1eQHUe7Np4gju9sJsEtWQ4MX3ToiNKuADed");
+                    assert!(params["addresses"][0].as_str().unwrap().starts_with("C-local1"));
+                    chains.atomic_polls += 1;
+                    if chains.atomic_polls == 1 {
+                        return Ok(utxo_page(Vec::new()));
+                    }
+                    let mut export_id = [0u8; 32];
+                    export_id.copy_from_slice(&Sha256::digest(&chains.issued[0].1));
+                    Ok(utxo_page(vec![owned_by(export_id, 1, 1_000_000_000, owner)]))
+                }
+                "avm.issueTx" | "avax.issueTx" => {
+                    if method == "avax.issueTx" && chains.import_failures > 0 {
+                        chains.import_failures -= 1;
+                        return Err((-32000, String::from("insufficient funds")));
+                    }
+                    let bytes = decode_hex_with_checksum(params["tx"].as_str().unwrap()).unwrap();
+                    let mut tx_id = [0u8; 32];
+                    tx_id.copy_from_slice(&Sha256::digest(&bytes));
+                    chains.issued.push((String::from(method), bytes));
+                    Ok(json!({"txID": format_id(&tx_id)}))
+                }
+                "avm.getTxStatus" | "avax.getAtomicTxStatus" => Ok(json!({"status": "Accepted"})),
+                // 25 gwei
+                "eth_baseFee" => Ok(json!("0x5d21dba00")),
+                _ => Err((-32601, String::from("method not found"))),
+            }
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), Some(12345), None, None, None, false).unwrap();
+
+        let interrupted = match avalanche.transfer_cross_chain(Chain::X, Chain::C, Avax::ONE, &keys).await {
+            Err(AvalancheError::TransferInterrupted { transfer, error }) => {
+                assert!(matches!(*error, AvalancheError::ErrorJsonRpcCall { .. }));
+                *transfer
+            }
+            result => panic!("transfer not interrupted: {:?}", result),
+        };
+        assert_eq!(interrupted.step, TransferStep::ExportAccepted);
+        let completed = avalanche.resume_cross_chain(interrupted.clone(), &keys).await.unwrap();
+        assert_eq!(completed.step, TransferStep::Completed);
+        assert_eq!(completed.export_tx_id, interrupted.export_tx_id);
+
+        let chains = chains.lock().unwrap();
+        let methods: Vec<&str> = chains.issued.iter().map(|(method, _)| method.as_str()).collect();
+        assert_eq!(methods, vec!["avm.issueTx", "avax.issueTx"]);
+        let (export, import) = (&chains.issued[0].1, &chains.issued[1].1);
+        // 10 AVAX spent, 1 AVAX exported and 1 mAVAX of fee
+        assert!(contains(export, &8_999_000_000u64.to_be_bytes()));
+        let fee = (ATOMIC_TX_FIXED_GAS + import.len() as u64 + ATOMIC_TX_GAS_PER_SIGNATURE) * 25;
+        let mut output = parse_evm_address(DEFAULT_EVM_LOCAL_GENESIS_ADDRESS).unwrap().to_vec();
+        output.extend_from_slice(&(1_000_000_000 - fee).to_be_bytes());
+        assert!(contains(import, &output));
+    }
+
+    #[tokio::test]
+    async fn resumed_transfers_do_not_issue_twice_after_lost_answers() {
+        let mut keys = Secp256k1KeyChain::new("X", "local");
+        let owner = keys.import_key(&cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap().get_short_id();
+        let chains = Arc::new(Mutex::new(MockChains::default()));
+        let state = chains.clone();
+        let node = test_utils::mock_raw_http_server(move |call| {
+            let mut chains = state.lock().unwrap();
+            let (method, params) = (call["method"].as_str().unwrap(), &call["params"]);
+            let result = match method {
+                "avm.getUTXOs" => utxo_page(vec![owned_by([7; 32], 0, 10_000_000_000, owner)]),
+                "avax.getUTXOs" => {
+                    chains.atomic_polls += 1;
+                    let mut export_id = [0u8; 32];
+                    export_id.copy_from_slice(&Sha256::digest(&chains.issued[0].1));
+                    utxo_page(vec![owned_by(export_id, 1, 1_000_000_000, owner)])
+                }
+                "avm.issueTx" | "avax.issueTx" => {
+                    let bytes = decode_hex_with_checksum(params["tx"].as_str().unwrap()).unwrap();
+                    let first_import = method == "avax.issueTx" && chains.import_failures == 0;
+                    if first_import {
+                        chains.import_failures += 1;
+                    } else {
+                        chains.issued.push((String::from(method), bytes));
+                    }
+                    // The export reaches the node and the first import does not, both answers are lost
+                    if method == "avm.issueTx" || first_import {
+                        return Response::builder().status(StatusCode::GATEWAY_TIMEOUT).body(Body::empty()).unwrap();
+                    }
+                    json!({"txID": format_id(&Sha256::digest(&chains.issued.last().unwrap().1).into())})
+                }
+                "avm.getTxStatus" | "avax.getAtomicTxStatus" => {
+                    let known = chains.issued.iter().any(|(_, bytes)| format_id(&Sha256::digest(bytes).into()) == params["txID"]);
+                    json!({"status": if known { "Accepted" } else { "Unknown" }})
+                }
+                // 25 gwei
+                "eth_baseFee" => json!("0x5d21dba00"),
+                _ => panic!("unexpected call {}", method),
+            };
+            Response::new(Body::from(json!({"jsonrpc": "2.0", "id": call["id"], "result": result}).to_string()))
+        })
+        .await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), Some(12345), None, None, None, false).unwrap();
+        avalanche.set_request_options(RequestOptions::default().with_retry(RetryPolicy::none()));
+        let interrupted = |result: Result<CrossChainTransfer, AvalancheError>| match result {
+            Err(AvalancheError::TransferInterrupted { transfer, error }) => {
+                assert!(matches!(*error, AvalancheError::HttpStatus { status: 504, .. }));
+                *transfer
+            }
+            result => panic!("transfer not interrupted: {:?}", result),
+        };
+
+        let transfer = interrupted(avalanche.transfer_cross_chain(Chain::X, Chain::C, Avax::ONE, &keys).await);
+        assert_eq!(transfer.step, TransferStep::ExportIssued);
+        let export_tx_id = transfer.export_tx_id.clone().unwrap();
+        assert_eq!(export_tx_id, format_id(&Sha256::digest(&chains.lock().unwrap().issued[0].1).into()));
+
+        // The export is known: the transfer goes on to the import without a second export
+        let transfer = interrupted(avalanche.resume_cross_chain(transfer, &keys).await);
+        assert_eq!(transfer.step, TransferStep::ImportIssued);
+        assert_eq!(transfer.export_tx_id.as_deref(), Some(export_tx_id.as_str()));
+        let atomic_polls = chains.lock().unwrap().atomic_polls;
+
+        // The import is unknown: the same signed bytes are issued again
+        let completed = avalanche.resume_cross_chain(transfer.clone(), &keys).await.unwrap();
+        assert_eq!(completed.step, TransferStep::Completed);
+        assert_eq!(completed.import_tx_id, transfer.import_tx_id);
+        let chains = chains.lock().unwrap();
+        assert_eq!(chains.atomic_polls, atomic_polls);
+        let methods: Vec<&str> = chains.issued.iter().map(|(method, _)| method.as_str()).collect();
+        assert_eq!(methods, vec!["avm.issueTx", "avax.issueTx"]);
+        assert_eq!(chains.issued[1].1, decode_hex_with_checksum(transfer.import_tx.as_deref().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn transfers_need_two_chains_and_an_amount() {
+        assert!(CrossChainTransfer::new(Chain::P, Chain::P, Avax::ONE).is_err());
+        assert!(CrossChainTransfer::new(Chain::C, Chain::P, Avax::ZERO).is_err());
+        let transfer = CrossChainTransfer::new(Chain::C, Chain::P, Avax::ONE).unwrap();
+        let saved = serde_json::to_string(&transfer).unwrap();
+        assert_eq!(serde_json::from_str::<CrossChainTransfer>(&saved).unwrap(), transfer);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::common::keychain::StandardKeyChain;
+use crate::common::secp256k1::{Secp256k1KeyChain, Secp256k1KeyPair};
+use crate::errors::AvalancheError;
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac, NewMac};
+use k256::ecdsa::SigningKey;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::elliptic_curve::PrimeField;
+use k256::{FieldBytes, NonZeroScalar, Scalar};
+use rand::RngCore;
+use sha2::Sha512;
+
+/// Account of the X and P chains, addresses being derived at `<path>/<change>/<index>`.
+pub const AVAX_ACCOUNT_PATH: &str = "m/44'/9000'/0'";
+/// Account of the C-Chain EVM, shared with Ethereum wallets.
+pub const EVM_ACCOUNT_PATH: &str = "m/44'/60'/0'";
+pub const HARDENED_OFFSET: u32 = 0x8000_0000;
+pub const EXTERNAL_CHAIN: u32 = 0;
+pub const CHANGE_CHAIN: u32 = 1;
+
+const BIP32_SEED_KEY: &[u8] = b"Bitcoin seed";
+
+fn invalid_mnemonic(error: bip39::Error) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: String::from("<mnemonic>"),
+        reason: error.to_string(),
+    }
+}
+
+/// Generates an English BIP39 mnemonic of `word_count` words (12, 15, 18, 21 or 24).
+pub fn generate_mnemonic(word_count: usize) -> Result<String, AvalancheError> {
+    if !word_count.is_multiple_of(3) || !(12..=24).contains(&word_count) {
+        return Err(AvalancheError::InvalidValue {
+            value: word_count.to_string(),
+            reason: String::from("a mnemonic has 12, 15, 18, 21 or 24 words"),
+        });
+    }
+    let mut entropy = vec![0u8; word_count / 3 * 4];
+    rand::rngs::OsRng.fill_bytes(&mut entropy);
+    Ok(Mnemonic::from_entropy(&entropy).map_err(invalid_mnemonic)?.to_string())
+}
+
+/// BIP39 seed of `mnemonic`, whose checksum is verified.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64], AvalancheError> {
+    Ok(Mnemonic::parse(mnemonic).map_err(invalid_mnemonic)?.to_seed(passphrase))
+}
+
+/// Parses a path such as `m/44'/9000'/0'/0/0` into child indexes, hardened ones having [`HARDENED_OFFSET`].
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>, AvalancheError> {
+    let invalid = |reason: &str| AvalancheError::InvalidValue {
+        value: String::from(path),
+        reason: String::from(reason),
+    };
+    let mut components = path.split('/');
+    if components.next() != Some("m") {
+        return Err(invalid("a derivation path starts with m"));
+    }
+    components
+        .map(|component| {
+            let (index, hardened) = match component.strip_suffix('\'').or_else(|| component.strip_suffix('h')) {
+                Some(index) => (index, true),
+                None => (component, false),
+            };
+            let index: u32 = index.parse().map_err(|_| invalid("invalid child index"))?;
+            if index >= HARDENED_OFFSET {
+                return Err(invalid("child index out of range"));
+            }
+            Ok(if hardened { index + HARDENED_OFFSET } else { index })
+        })
+        .collect()
+}
+
+/// BIP32 extended private key on secp256k1.
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    signing_key: SigningKey,
+    chain_code: [u8; 32],
+    depth: u8,
+}
+
+impl ExtendedPrivateKey {
+    pub fn from_seed(seed: &[u8]) -> Result<ExtendedPrivateKey, AvalancheError> {
+        let (key, chain_code) = hmac_sha512(BIP32_SEED_KEY, &[seed]);
+        Ok(ExtendedPrivateKey {
+            signing_key: SigningKey::from_bytes(&key).map_err(|_| invalid_derivation())?,
+            chain_code,
+            depth: 0,
+        })
+    }
+    pub fn derive_child(&self, index: u32) -> Result<ExtendedPrivateKey, AvalancheError> {
+        let (tweak, chain_code) = if index >= HARDENED_OFFSET {
+            hmac_sha512(&self.chain_code, &[&[0], &self.signing_key.to_bytes(), &index.to_be_bytes()])
+        } else {
+            hmac_sha512(&self.chain_code, &[&self.public_key(), &index.to_be_bytes()])
+        };
+        let tweak: Option<Scalar> = Scalar::from_repr(FieldBytes::clone_from_slice(&tweak)).into();
+        let parent: Option<Scalar> = Scalar::from_repr(self.signing_key.to_bytes()).into();
+        let child: Option<NonZeroScalar> = NonZeroScalar::new(tweak.ok_or_else(invalid_derivation)? + parent.ok_or_else(invalid_derivation)?).into();
+        Ok(ExtendedPrivateKey {
+            signing_key: SigningKey::from(child.ok_or_else(invalid_derivation)?),
+            chain_code,
+            depth: self.depth.checked_add(1).ok_or_else(invalid_derivation)?,
+        })
+    }
+    pub fn derive_path(&self, path: &str) -> Result<ExtendedPrivateKey, AvalancheError> {
+        parse_derivation_path(path)?
+            .into_iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(index))
+    }
+    pub fn private_key(&self) -> [u8; 32] {
+        self.signing_key.to_bytes().into()
+    }
+    /// Compressed public key.
+    pub fn public_key(&self) -> Vec<u8> {
+        self.signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec()
+    }
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+    pub fn to_key_pair(&self, chain_alias: &str, hrp: &str) -> Result<Secp256k1KeyPair, AvalancheError> {
+        Secp256k1KeyPair::from_private_key(chain_alias, hrp, &self.private_key())
+    }
+}
+
+fn invalid_derivation() -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: String::from("<extended key>"),
+        reason: String::from("derived key is invalid, use the next index"),
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for data in data {
+        mac.update(data);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+/// Keys of a mnemonic along the Avalanche BIP44 paths.
+#[derive(Clone)]
+pub struct HdWallet {
+    avax_account: ExtendedPrivateKey,
+    evm_account: ExtendedPrivateKey,
+}
+
+impl HdWallet {
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str) -> Result<HdWallet, AvalancheError> {
+        HdWallet::from_seed(&mnemonic_to_seed(mnemonic, passphrase)?)
+    }
+    pub fn from_seed(seed: &[u8]) -> Result<HdWallet, AvalancheError> {
+        let master = ExtendedPrivateKey::from_seed(seed)?;
+        Ok(HdWallet {
+            avax_account: master.derive_path(AVAX_ACCOUNT_PATH)?,
+            evm_account: master.derive_path(EVM_ACCOUNT_PATH)?,
+        })
+    }
+    /// Key of the X and P chains at `m/44'/9000'/0'/<change>/<index>`.
+    pub fn derive_avax_key(&self, change: u32, index: u32, chain_alias: &str, hrp: &str) -> Result<Secp256k1KeyPair, AvalancheError> {
+        self.avax_account.derive_child(change)?.derive_child(index)?.to_key_pair(chain_alias, hrp)
+    }
+    /// Key of the C-Chain EVM at `m/44'/60'/0'/0/<index>`.
+    pub fn derive_evm_key(&self, index: u32, hrp: &str) -> Result<Secp256k1KeyPair, AvalancheError> {
+        self.evm_account.derive_child(EXTERNAL_CHAIN)?.derive_child(index)?.to_key_pair("C", hrp)
+    }
+    /// Adds the keys `indexes` of the `change` chain to `keychain`, with its chain alias and HRP.
+    pub fn fill_keychain(
+        &self,
+        keychain: &mut Secp256k1KeyChain,
+        change: u32,
+        indexes: std::ops::Range<u32>,
+    ) -> Result<(), AvalancheError> {
+        for index in indexes {
+            let key = self.derive_avax_key(change, index, keychain.get_chain_alias(), keychain.get_hrp())?;
+            keychain.add_key(key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::evm::transaction::format_evm_address;
+    use crate::common::keychain::StandardKeyPair;
+    use crate::utils::constants::MNEMONIC;
+
+    #[test]
+    fn bip39_seeds_match_the_reference_vector() {
+        let seed = mnemonic_to_seed("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about", "TREZOR").unwrap();
+        assert_eq!(
+            hex::encode(seed),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+        assert!(mnemonic_to_seed("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon", "").is_err());
+        let mnemonic = generate_mnemonic(24).unwrap();
+        assert_eq!(mnemonic.split(' ').count(), 24);
+        assert!(mnemonic_to_seed(&mnemonic, "").is_ok());
+    }
+
+    #[test]
+    fn bip32_derivation_matches_the_reference_vector() {
+        let master = ExtendedPrivateKey::from_seed(&hex::decode("000102030405060708090a0b0c0d0e0f").unwrap()).unwrap();
+        assert_eq!(hex::encode(master.private_key()), "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35");
+        assert_eq!(hex::encode(master.chain_code()), "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508");
+        let child = master.derive_path("m/0'/1").unwrap();
+        assert_eq!(hex::encode(child.private_key()), "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368");
+        assert_eq!(hex::encode(child.chain_code()), "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19");
+        let child = child.derive_path("m/2'").unwrap();
+        assert_eq!(hex::encode(child.private_key()), "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca");
+        assert_eq!(child.depth(), 3);
+        assert!(parse_derivation_path("44'/9000'").is_err());
+        assert_eq!(parse_derivation_path("m/44'/1/2147483647h").unwrap(), vec![HARDENED_OFFSET + 44, 1, u32::MAX]);
+    }
+
+    #[test]
+    fn mnemonic_feeds_the_keychain() {
+        let wallet = HdWallet::from_mnemonic(MNEMONIC, "").unwrap();
+        let mut keychain = Secp256k1KeyChain::new("X", "avax");
+        wallet.fill_keychain(&mut keychain, EXTERNAL_CHAIN, 0..3).unwrap();
+        wallet.fill_keychain(&mut keychain, EXTERNAL_CHAIN, 0..1).unwrap();
+        assert_eq!(keychain.get_addresses_strings(), vec![
+            "X-avax1egnwafzkta3nfhww2f7wwpaafhu6uk652054xv",
+            "X-avax132azz4xt2yccmggvkzqj3jgqu8jry5msw7rvqz",
+            "X-avax1wf6w3nae7masypqdxpdjvwy5hdu46e8hd4nywt",
+        ]);
+        let evm_key = wallet.derive_evm_key(0, "avax").unwrap();
+        assert_eq!(format_evm_address(&evm_key.get_evm_address().unwrap()), "0x497d587e195f26183ff9e59e2c141837fc48d314");
+        assert_eq!(
+            wallet.derive_avax_key(CHANGE_CHAIN, 0, "P", "avax").unwrap().get_address_string(),
+            "P-avax198m26dk6cxqnlxpl7drv9fgashkm0xgah0m2k4"
+        );
+    }
+}
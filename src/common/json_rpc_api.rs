@@ -1,33 +1,63 @@
 use crate::common::api_base::ApiBase;
 use crate::AvalancheError;
 use std::collections::HashMap;
-use hyper::client::{ResponseFuture};
-use serde::{Serialize, Serializer, Deserialize};
-
-#[derive(Deserialize, Clone, Debug)]
-pub enum JsonRpcParams<'a> {
-    Str(&'a str),
-    HashMap(HashMap<String, JsonRpcParams<'a>>),
-    VecString(Vec<String>)
+use crate::common::batch::BatchRequest;
+use crate::common::cache::ResponseCache;
+use crate::avalanche_core::AvalancheCore;
+use crate::common::retry::{with_retries, Idempotency, RequestOptions};
+use crate::common::rate_limit::parse_retry_after;
+use hyper::header::RETRY_AFTER;
+use hyper::StatusCode;
+use url::Url;
+use std::future::Future;
+use std::pin::Pin;
+use hyper::body::Bytes;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+
+/// Id of a JSON-RPC request, nodes may echo it as a number or as a string.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum JsonRpcId {
+    Number(u64),
+    String(String)
 }
 
-impl<'a> Serialize for JsonRpcParams<'a> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
+impl JsonRpcId {
+    /// Whether `other` identifies the same request, a node stringifying a numeric id still matching.
+    pub fn matches(&self, other: &JsonRpcId) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl std::fmt::Display for JsonRpcId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            JsonRpcParams::Str(s) => s.serialize(serializer),
-            JsonRpcParams::HashMap(h) => h.serialize(serializer),
-            JsonRpcParams::VecString(vs) => vs.serialize(serializer),
+            JsonRpcId::Number(id) => write!(f, "{}", id),
+            JsonRpcId::String(id) => write!(f, "{}", id),
         }
     }
 }
 
+impl From<u64> for JsonRpcId {
+    fn from(id: u64) -> Self {
+        JsonRpcId::Number(id)
+    }
+}
+
+impl From<&str> for JsonRpcId {
+    fn from(id: &str) -> Self {
+        JsonRpcId::String(String::from(id))
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct JsonRpcError {
     pub jsonrpc: String,
-    pub id: String,
+    /// `None` when the node could not read the id of the request.
+    #[serde(default)]
+    pub id: Option<JsonRpcId>,
     pub error: JsonRpcSubError
 }
 
@@ -40,51 +70,286 @@ pub struct JsonRpcSubError {
 #[derive(Serialize, Deserialize)]
 pub struct JsonRpcResponse<T> {
     pub jsonrpc: String,
-    pub id: String,
+    pub id: JsonRpcId,
     pub result: T
 }
 
+/// Future of a JSON-RPC call, resolved from the API cache when possible.
+pub type CallFuture<'a, R> = Pin<Box<dyn Future<Output = Result<R, AvalancheError>> + Send + 'a>>;
+
 pub trait JsonRpcApi: ApiBase {
     fn get_json_rpc_version(&self) -> String;
-    fn get_json_rpc_id(&self) -> u32;
-    fn call_method(&self, method: &'static str, params: Option<HashMap<String, JsonRpcParams>>, base_api_url: Option<&str>, headers: Option<HashMap<&str, &str>>) -> ResponseFuture {
+    /// Id of the next request, drawn from the client core so that it is unique across its APIs.
+    fn get_json_rpc_id(&self) -> JsonRpcId {
+        JsonRpcId::Number(self.get_core().next_request_id())
+    }
+    /// URL of `base_api_url` on the node, the endpoint of the API by default.
+    fn get_json_rpc_url(&self, base_api_url: Option<&str>) -> String {
         let ep = base_api_url.unwrap_or_else(|| self.get_api_base_url());
-        let mut params_call: HashMap<&str, JsonRpcParams> = HashMap::new();
-        let id = &self.get_json_rpc_id().to_string();
-        let version = &self.get_json_rpc_version().to_string();
-        params_call.insert("id", JsonRpcParams::Str(id));
-        params_call.insert("method", JsonRpcParams::Str(method));
+        format!("{}://{}:{}{}", self.get_core().get_protocol(), self.get_core().get_host(), self.get_core().get_port(), ep)
+    }
+    /// Starts a batch of calls sent together to the endpoint of the API.
+    fn batch(&self) -> BatchRequest<'_, Self> where Self: Sized {
+        BatchRequest::new(self)
+    }
+    /// Calls `method` and decodes its result as `R`. `params` serializing to an array are sent
+    /// as positional params, to an object as named params, and to null (e.g. `()`) are omitted.
+    fn call_method<'a, P, R>(&'a self, method: &'static str, params: P, base_api_url: Option<&str>, headers: Option<HashMap<&str, &str>>) -> CallFuture<'a, R>
+    where
+        P: Serialize,
+        R: DeserializeOwned + Send + 'a
+    {
+        self.call_method_with_options(method, params, base_api_url, headers, None)
+    }
+    /// [`JsonRpcApi::call_method`] with `options` replacing the request options of the client.
+    /// Transient failures are retried unless `method` is [`Idempotency::NonIdempotent`].
+    fn call_method_with_options<'a, P, R>(&'a self, method: &'static str, params: P, base_api_url: Option<&str>, headers: Option<HashMap<&str, &str>>, options: Option<RequestOptions>) -> CallFuture<'a, R>
+    where
+        P: Serialize,
+        R: DeserializeOwned + Send + 'a
+    {
+        let params = match serde_json::to_value(&params) {
+            Ok(Value::Null) => None,
+            Ok(params) => Some(params),
+            Err(error) => {
+                let error = AvalancheError::InvalidValue {
+                    value: String::from(method),
+                    reason: format!("params do not serialize: {}", error)
+                };
+                return Box::pin(async move { Err(error) });
+            }
+        };
+        let ep = base_api_url.unwrap_or_else(|| self.get_api_base_url());
+        let cache = self.get_cache();
+        let ttl = cache.ttl(method);
+        // Params are keyed through a `Value` so that maps serialize in a stable order
+        let cache_key = ttl.map(|_| {
+            let params_key = params.as_ref().map(Value::to_string).unwrap_or_default();
+            ResponseCache::key(ep, method, &params_key)
+        });
+        if let Some(ref key) = cache_key {
+            if let Some(body) = cache.get(key) {
+                return Box::pin(async move { decode_json_rpc_body(method, &body) });
+            }
+        }
+        let id = self.get_json_rpc_id();
+        let version = self.get_json_rpc_version();
+        let mut call = json!({"id": id, "method": method});
         if let Some(p) = params {
-            params_call.insert("params", JsonRpcParams::HashMap(p));
+            call["params"] = p;
         }
-        if self.get_json_rpc_version() != "1.0" {
-            params_call.insert("jsonrpc", JsonRpcParams::Str(version));
+        if version != "1.0" {
+            call["jsonrpc"] = Value::String(version);
         }
-        let mut headers_call = match headers {
-            Some(h) => h,
-            None => HashMap::new()
-        };
-        headers_call.insert("Content-Type", "application/json;charset=UTF-8");
-        let base_url = format!("{}://{}:{}{}", self.get_core().get_protocol(), self.get_core().get_host(), self.get_core().get_port(), ep);
-        let body_string = serde_json::to_vec(&params_call).unwrap();
-        self.get_core().post(&base_url, body_string.into(), headers_call)
-        //TODO: Add error handling
+        let core: &'a dyn AvalancheCore = *self.get_core();
+        let options = options.unwrap_or_else(|| core.get_request_options());
+        let url = self.get_json_rpc_url(Some(ep));
+        let headers = owned_headers(headers);
+        let body = serde_json::to_vec(&call).unwrap();
+        let retryable = Idempotency::of(method).is_retryable();
+        Box::pin(async move {
+            let (status, body) = with_retries(&options, retryable, method, || {
+                send_json_rpc(core, &url, body.clone(), &headers)
+            }).await?;
+            let decoded = serde_json::from_slice::<Value>(&body).ok();
+            if let Some(ref decoded) = decoded {
+                verify_response_id(method, &id, decoded)?;
+            }
+            let result = decode_json_rpc_body(method, &body);
+            if let (Some(ttl), Some(key), Ok(_)) = (ttl, cache_key, &result) {
+                if status.is_success() {
+                    cache.insert(key, body, ttl);
+                }
+            }
+            result
+        })
     }
 }
 
-pub fn decode_json_rpc_body<T>(call_name: &str, body: &hyper::body::Bytes) -> Result<JsonRpcResponse<T>, AvalancheError>
-where 
-    T: serde::de::DeserializeOwned
-    {
-    match serde_json::from_slice::<JsonRpcResponse<T>>(body) {
-        Ok(response) => Ok(response),
-        Err(_) => {
-            let response = serde_json::from_slice::<JsonRpcError>(body).unwrap();
-            Err(AvalancheError::ErrorJsonRpcCall{
-                call: String::from(call_name),
-                code: response.error.code.to_string(),
-                message: response.error.message
-            })
+pub(crate) fn owned_headers(headers: Option<HashMap<&str, &str>>) -> HashMap<String, String> {
+    headers
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (String::from(key), String::from(value)))
+        .collect()
+}
+
+/// Posts an encoded JSON-RPC call or batch to `url` once the rate limiter of the core allows it.
+/// A 429 is returned as [`AvalancheError::RateLimited`] after pausing the limiter for its
+/// `Retry-After`, and a failed response whose body is not JSON, such as the error page of a
+/// proxy, as [`AvalancheError::HttpStatus`].
+pub async fn send_json_rpc(core: &dyn AvalancheCore, url: &str, body: Vec<u8>, headers: &HashMap<String, String>) -> Result<(StatusCode, Bytes), AvalancheError> {
+    let limiter = core.get_rate_limiter();
+    let path = Url::parse(url).map(|url| String::from(url.path())).unwrap_or_default();
+    limiter.acquire(&path).await;
+    let mut headers_call: HashMap<&str, &str> = headers.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+    headers_call.insert("Content-Type", "application/json;charset=UTF-8");
+    let response = core.post(url, body.into(), headers_call).await?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        if let Some(retry_after) = retry_after {
+            limiter.pause_for(retry_after);
         }
+        return Err(AvalancheError::RateLimited { retry_after });
+    }
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    if !status.is_success() && serde_json::from_slice::<Value>(&body).is_err() {
+        return Err(AvalancheError::HttpStatus {
+            status: status.as_u16(),
+            body: String::from_utf8_lossy(&body).into_owned()
+        });
+    }
+    Ok((status, body))
+}
+
+/// Checks that `response` answers the request `id`, an error without id being accepted as the node
+/// could not read it.
+pub fn verify_response_id(call_name: &str, id: &JsonRpcId, response: &Value) -> Result<(), AvalancheError> {
+    let received = &response["id"];
+    if received.is_null() && response.get("error").is_some() {
+        return Ok(());
+    }
+    match serde_json::from_value::<JsonRpcId>(received.clone()) {
+        Ok(ref received) if id.matches(received) => Ok(()),
+        _ => Err(AvalancheError::ResponseIdMismatch {
+            call: String::from(call_name),
+            expected: id.to_string(),
+            received: received.to_string()
+        })
+    }
+}
+
+/// Decodes the result of a JSON-RPC response body, turning a JSON-RPC error into
+/// [`AvalancheError::ErrorJsonRpcCall`].
+pub fn decode_json_rpc_body<T>(call_name: &str, body: &Bytes) -> Result<T, AvalancheError>
+where
+    T: DeserializeOwned
+{
+    let invalid = |reason: String| AvalancheError::InvalidValue {
+        value: String::from_utf8_lossy(body).into_owned(),
+        reason: format!("{}: {}", call_name, reason)
+    };
+    let mut response = serde_json::from_slice::<Value>(body).map_err(|error| invalid(error.to_string()))?;
+    if response.get("error").is_some() {
+        let response = serde_json::from_value::<JsonRpcError>(response).map_err(|error| invalid(error.to_string()))?;
+        return Err(AvalancheError::ErrorJsonRpcCall {
+            call: String::from(call_name),
+            code: response.error.code.to_string(),
+            message: response.error.message
+        });
+    }
+    serde_json::from_value(response["result"].take()).map_err(|error| invalid(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::Avalanche;
+    use crate::common::retry::RetryPolicy;
+    use hyper::{Body, Response};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn ids_are_numbers_or_strings() {
+        let response: JsonRpcResponse<String> = serde_json::from_str(r#"{"jsonrpc":"2.0","id":7,"result":"ok"}"#).unwrap();
+        assert_eq!(response.id, JsonRpcId::Number(7));
+        let response: JsonRpcResponse<String> = serde_json::from_str(r#"{"jsonrpc":"2.0","id":"7","result":"ok"}"#).unwrap();
+        assert_eq!(response.id, JsonRpcId::from("7"));
+        assert!(response.id.matches(&JsonRpcId::Number(7)));
+        let error: JsonRpcError = serde_json::from_str(r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"parse error"}}"#).unwrap();
+        assert_eq!(error.id, None);
+    }
+
+    #[tokio::test]
+    async fn ids_increase_across_the_apis_of_a_client() {
+        let ids = Arc::new(Mutex::new(Vec::new()));
+        let seen = ids.clone();
+        let node = test_utils::mock_http_server(move |call| {
+            seen.lock().unwrap().push(call["id"].clone());
+            json!({"jsonrpc": "2.0", "id": call["id"].to_string(), "result": {"nodeID": "NodeID-1", "height": "10"}})
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        avalanche.info().unwrap().get_node_id().await.unwrap();
+        avalanche.p().unwrap().get_height().await.unwrap();
+        avalanche.info().unwrap().get_node_id().await.unwrap();
+        assert_eq!(*ids.lock().unwrap(), vec![json!(1), json!(2), json!(3)]);
+    }
+
+    #[tokio::test]
+    async fn mismatched_ids_are_rejected() {
+        let node = test_utils::mock_http_server(|_| json!({"jsonrpc": "2.0", "id": 999, "result": {"nodeID": "NodeID-1"}})).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        assert_eq!(avalanche.info().unwrap().get_node_id().await, Err(AvalancheError::ResponseIdMismatch {
+            call: String::from("info.getNodeID"),
+            expected: String::from("1"),
+            received: String::from("999")
+        }));
+    }
+
+    #[tokio::test]
+    async fn params_are_positional_named_or_omitted() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let seen = calls.clone();
+        let node = test_utils::mock_http_server(move |call| {
+            seen.lock().unwrap().push(call.get("params").cloned());
+            json!({"jsonrpc": "2.0", "id": call["id"], "result": [1, 2]})
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let info = avalanche.info().unwrap();
+        let result: Vec<u8> = info.call_method("test.positional", ("a", 1, true), None, None).await.unwrap();
+        assert_eq!(result, vec![1, 2]);
+        let _: Value = info.call_method("test.named", json!({"nested": [{"a": 1}]}), None, None).await.unwrap();
+        let _: Value = info.call_method("test.none", (), None, None).await.unwrap();
+        let result: Result<String, AvalancheError> = info.call_method("test.mistyped", (), None, None).await;
+        assert!(matches!(result, Err(AvalancheError::InvalidValue { .. })));
+        assert_eq!(calls.lock().unwrap()[..3], [Some(json!(["a", 1, true])), Some(json!({"nested": [{"a": 1}]})), None]);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn transient_failures_of_read_only_calls_are_retried() {
+        let first = std::sync::atomic::AtomicBool::new(true);
+        let node = test_utils::mock_raw_http_server(move |call| {
+            if first.swap(false, std::sync::atomic::Ordering::SeqCst) || call["method"] == "avm.send" {
+                return Response::builder().status(502).body(Body::from("<html>Bad Gateway</html>")).unwrap();
+            }
+            Response::new(Body::from(json!({"jsonrpc": "2.0", "id": call["id"], "result": {"nodeID": "NodeID-1"}}).to_string()))
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        avalanche.set_request_options(RequestOptions::default().with_retry(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        }));
+        let info = avalanche.info().unwrap();
+        assert_eq!(info.get_node_id().await.unwrap(), "NodeID-1");
+        assert_eq!(node.request_count(), 2);
+
+        let result: Result<Value, AvalancheError> = info.call_method("avm.send", (), None, None).await;
+        assert_eq!(result, Err(AvalancheError::HttpStatus { status: 502, body: String::from("<html>Bad Gateway</html>") }));
+        assert_eq!(node.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn throttled_calls_wait_for_retry_after() {
+        let first = std::sync::atomic::AtomicBool::new(true);
+        let node = test_utils::mock_raw_http_server(move |call| {
+            if first.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                return Response::builder().status(429).header("Retry-After", "1").body(Body::empty()).unwrap();
+            }
+            Response::new(Body::from(json!({"jsonrpc": "2.0", "id": call["id"], "result": {"nodeID": "NodeID-1"}}).to_string()))
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let start = std::time::Instant::now();
+        assert_eq!(avalanche.info().unwrap().get_node_id().await.unwrap(), "NodeID-1");
+        assert!(start.elapsed() >= Duration::from_millis(900));
+        assert_eq!(node.request_count(), 2);
+        let metrics = avalanche.get_rate_limiter().metrics();
+        assert_eq!((metrics.requests, metrics.delayed_requests), (2, 1));
+    }
+}
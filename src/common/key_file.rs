@@ -0,0 +1,228 @@
+use crate::apis::evm::transaction::EvmAddress;
+use crate::common::keychain::{StandardKeyChain, StandardKeyPair};
+use crate::common::keystore::{invalid, KeystoreCipher, KeystoreCrypto, ScryptCost};
+use crate::common::secp256k1::{public_key_to_evm, Secp256k1KeyChain, Secp256k1KeyPair};
+use crate::errors::AvalancheError;
+use crate::utils::address::{format_address, parse_private_key, public_key_to_short_id, ShortId};
+use crate::utils::constants::{C_CHAIN_ALIAS, FALLBACK_HRP};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version of the layout of key files, checked when they are read.
+pub const KEY_FILE_VERSION: u32 = 1;
+
+/// Key of a key file, its public key in the clear and its private key encrypted with AES-128-GCM.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyFileEntry {
+    /// Compressed public key in hex, listing the addresses without the password.
+    pub public_key: String,
+    pub crypto: KeystoreCrypto,
+}
+
+impl KeyFileEntry {
+    fn public_key(&self) -> Result<Vec<u8>, AvalancheError> {
+        let public_key = hex::decode(&self.public_key).map_err(|_| invalid(&self.public_key, "not hex"))?;
+        if public_key.len() != 33 {
+            return Err(invalid(&self.public_key, "compressed public keys are 33 bytes long"));
+        }
+        Ok(public_key)
+    }
+    fn short_id(&self) -> Result<ShortId, AvalancheError> {
+        Ok(public_key_to_short_id(&self.public_key()?))
+    }
+    /// Key pair of the entry, checking the decrypted key matches the public key.
+    fn key_pair(&self, password: &str, chain_alias: &str, hrp: &str) -> Result<Secp256k1KeyPair, AvalancheError> {
+        let key_pair = Secp256k1KeyPair::from_private_key(chain_alias, hrp, &self.crypto.decrypt(password)?)?;
+        if hex::encode(key_pair.get_public_key()) != self.public_key {
+            return Err(invalid(&self.public_key, "the private key does not match the public key"));
+        }
+        Ok(key_pair)
+    }
+}
+
+/// Avalanche keys encrypted with one password, saved as JSON.
+///
+/// Keys are imported as `PrivateKey-<cb58>` or EVM hex strings and decrypted only into a
+/// [`Secp256k1KeyChain`]; their addresses are listed from the public keys, without the password.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub keys: Vec<KeyFileEntry>,
+    /// Cost of scrypt for the keys imported, that of the first key of a file read.
+    #[serde(skip)]
+    cost: ScryptCost,
+}
+
+impl Default for KeyFile {
+    fn default() -> Self {
+        KeyFile::new(ScryptCost::default())
+    }
+}
+
+impl KeyFile {
+    pub fn new(cost: ScryptCost) -> KeyFile {
+        KeyFile { version: KEY_FILE_VERSION, keys: Vec::new(), cost }
+    }
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    /// Imports a `PrivateKey-<cb58>` or hex private key, returning the short ID of its address.
+    pub fn import_key(&mut self, private_key: &str, password: &str) -> Result<ShortId, AvalancheError> {
+        self.import_private_key(&parse_private_key(private_key)?, password)
+    }
+    /// Encrypts `private_key` with `password`, which must be the password of the keys of the file.
+    pub fn import_private_key(&mut self, private_key: &[u8], password: &str) -> Result<ShortId, AvalancheError> {
+        let key_pair = Secp256k1KeyPair::from_private_key(C_CHAIN_ALIAS, FALLBACK_HRP, private_key)?;
+        let short_id = key_pair.get_short_id();
+        if self.short_ids()?.contains(&short_id) {
+            return Err(invalid(&hex::encode(short_id), "the key file already has this key"));
+        }
+        if let Some(entry) = self.keys.first() {
+            entry.key_pair(password, C_CHAIN_ALIAS, FALLBACK_HRP)?;
+        }
+        self.keys.push(KeyFileEntry {
+            public_key: hex::encode(key_pair.get_public_key()),
+            crypto: KeystoreCrypto::encrypt(private_key, password, KeystoreCipher::Aes128Gcm, self.cost)?,
+        });
+        Ok(short_id)
+    }
+    /// Removes the key of `address`, returning whether the file had it.
+    pub fn remove_key(&mut self, address: &ShortId) -> bool {
+        let length = self.keys.len();
+        self.keys.retain(|entry| entry.short_id().ok().as_ref() != Some(address));
+        self.keys.len() != length
+    }
+    /// Short IDs of the addresses of the keys, in the order they were imported.
+    pub fn short_ids(&self) -> Result<Vec<ShortId>, AvalancheError> {
+        self.keys.iter().map(KeyFileEntry::short_id).collect()
+    }
+    /// Addresses of the keys on `chain_alias`, such as `X-avax1...`.
+    pub fn addresses(&self, chain_alias: &str, hrp: &str) -> Result<Vec<String>, AvalancheError> {
+        self.short_ids()?.iter().map(|short_id| format_address(chain_alias, hrp, short_id)).collect()
+    }
+    /// Addresses of the keys on the C-Chain EVM.
+    pub fn evm_addresses(&self) -> Result<Vec<EvmAddress>, AvalancheError> {
+        self.keys.iter().map(|entry| public_key_to_evm(&entry.public_key()?)).collect()
+    }
+    /// Encrypts every key with `new_password`, leaving the file unchanged if one does not decrypt.
+    pub fn change_password(&mut self, old_password: &str, new_password: &str) -> Result<(), AvalancheError> {
+        self.keys = self
+            .keys
+            .iter()
+            .map(|entry| {
+                let key_pair = entry.key_pair(old_password, C_CHAIN_ALIAS, FALLBACK_HRP)?;
+                let crypto = KeystoreCrypto::encrypt(key_pair.get_private_key(), new_password, KeystoreCipher::Aes128Gcm, entry.crypto.cost())?;
+                Ok(KeyFileEntry { public_key: entry.public_key.clone(), crypto })
+            })
+            .collect::<Result<Vec<KeyFileEntry>, AvalancheError>>()?;
+        Ok(())
+    }
+    /// Decrypts the keys into a keychain of `chain_alias` and `hrp`.
+    pub fn keychain(&self, password: &str, chain_alias: &str, hrp: &str) -> Result<Secp256k1KeyChain, AvalancheError> {
+        let mut keychain = Secp256k1KeyChain::new(chain_alias, hrp);
+        for entry in &self.keys {
+            keychain.add_key(entry.key_pair(password, chain_alias, hrp)?);
+        }
+        Ok(keychain)
+    }
+    pub fn to_json(&self) -> Result<String, AvalancheError> {
+        serde_json::to_string_pretty(self).map_err(|error| invalid("key file", &error.to_string()))
+    }
+    /// Reads a key file, checking its version and public keys.
+    pub fn from_json(json: &str) -> Result<KeyFile, AvalancheError> {
+        let mut file: KeyFile = serde_json::from_str(json).map_err(|error| invalid("key file", &error.to_string()))?;
+        if file.version != KEY_FILE_VERSION {
+            return Err(invalid(&file.version.to_string(), "key files of version 1 are read"));
+        }
+        file.short_ids()?;
+        if let Some(entry) = file.keys.first() {
+            file.cost = entry.crypto.cost();
+        }
+        Ok(file)
+    }
+    pub fn load(path: impl AsRef<Path>) -> Result<KeyFile, AvalancheError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|error| invalid(&path.display().to_string(), &error.to_string()))?;
+        KeyFile::from_json(&json)
+    }
+    /// Writes the file next to `path` then renames it over, readable by its owner only on Unix.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AvalancheError> {
+        let path = path.as_ref();
+        let io_error = |error: std::io::Error| invalid(&path.display().to_string(), &error.to_string());
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        std::io::Write::write_all(&mut options.open(&temporary).map_err(io_error)?, self.to_json()?.as_bytes()).map_err(io_error)?;
+        std::fs::rename(&temporary, path).map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::evm::transaction::format_evm_address;
+    use crate::utils::constants::{
+        DEFAULT_EVM_LOCAL_GENESIS_ADDRESS, DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY, PRIVATE_KEY_PREFIX,
+    };
+
+    const TEST_COST: ScryptCost = ScryptCost { log_n: 10, r: 8, p: 1 };
+
+    #[test]
+    fn addresses_are_listed_without_the_password() {
+        let mut file = KeyFile::new(TEST_COST);
+        let short_id = file.import_key(&format!("{}{}", PRIVATE_KEY_PREFIX, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY), "password").unwrap();
+        let mut key_pair = Secp256k1KeyPair::new("X", "local");
+        key_pair.generate_key(None).unwrap();
+        assert!(file.import_key(&hex::encode(key_pair.get_private_key()), "other password").is_err());
+        file.import_key(&hex::encode(key_pair.get_private_key()), "password").unwrap();
+        assert!(file.import_key(DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY, "password").is_err());
+
+        let file = KeyFile::from_json(&file.to_json().unwrap()).unwrap();
+        assert_eq!(file.short_ids().unwrap(), vec![short_id, key_pair.get_short_id()]);
+        assert_eq!(file.addresses("X", "local").unwrap()[1], key_pair.get_address_string());
+        let evm_addresses = file.evm_addresses().unwrap();
+        assert_eq!(format_evm_address(&evm_addresses[0]).to_lowercase(), DEFAULT_EVM_LOCAL_GENESIS_ADDRESS.to_lowercase());
+        let keychain = file.keychain("password", "X", "local").unwrap();
+        assert_eq!(keychain.get_addresses_strings(), file.addresses("X", "local").unwrap());
+        assert!(file.keychain("other password", "X", "local").is_err());
+    }
+
+    #[test]
+    fn passwords_change_for_every_key_or_none() {
+        let mut file = KeyFile::new(TEST_COST);
+        let short_id = file.import_key(DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY, "old password").unwrap();
+        let unchanged = file.clone();
+        assert!(file.change_password("wrong password", "new password").is_err());
+        assert_eq!(file, unchanged);
+        file.change_password("old password", "new password").unwrap();
+        assert!(file.keychain("old password", "C", "local").is_err());
+        assert!(file.keychain("new password", "C", "local").unwrap().has_key(&short_id));
+        assert!(file.remove_key(&short_id));
+        assert!(file.is_empty());
+    }
+
+    #[test]
+    fn key_files_save_and_load() {
+        let mut file = KeyFile::new(TEST_COST);
+        file.import_key(DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY, "password").unwrap();
+        let path = std::env::temp_dir().join(format!("avalanche-rs-key-file-{}.json", std::process::id()));
+        file.save(&path).unwrap();
+        let loaded = KeyFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, file);
+
+        let mut json: serde_json::Value = serde_json::from_str(&file.to_json().unwrap()).unwrap();
+        json["version"] = serde_json::json!(2);
+        assert!(KeyFile::from_json(&json.to_string()).is_err());
+        json["version"] = serde_json::json!(1);
+        json["keys"][0]["publicKey"] = serde_json::json!("00");
+        assert!(KeyFile::from_json(&json.to_string()).is_err());
+    }
+}
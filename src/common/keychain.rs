@@ -1,10 +1,16 @@
-pub trait StandardKeyPair {
+use crate::errors::AvalancheError;
+
+/// Key pair bound to a chain alias and HRP, used to format its address.
+pub trait StandardKeyPair: Sized {
     fn get_public_key(&self) -> &[u8];
     fn get_private_key(&self) -> &[u8];
-    fn generate_key(&self, entropy: Option<&[u8]>);
-    fn import_key(&self, priv_key: &[u8]);
-    fn sign(&self, message: &[u8]) -> &[u8];
-    fn recover(&self, message: &[u8], signature: &[u8]) -> Result<&[u8], ()>;
+    /// Generates a new key, from `entropy` (32 bytes) when given.
+    fn generate_key(&mut self, entropy: Option<&[u8]>) -> Result<(), AvalancheError>;
+    fn import_key(&mut self, priv_key: &[u8]) -> Result<(), AvalancheError>;
+    /// Recoverable signature of the SHA-256 of `message`.
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, AvalancheError>;
+    /// Public key of the signer of `message`.
+    fn recover(&self, message: &[u8], signature: &[u8]) -> Result<Vec<u8>, AvalancheError>;
     fn verify(&self, message: &[u8], signature: &[u8], pubk: &[u8]) -> bool;
     fn get_public_key_string(&self) -> String;
     fn get_private_key_string(&self) -> String;
@@ -16,12 +22,13 @@ pub trait StandardKeyChain<KPClass>
 where
     KPClass: StandardKeyPair
 {
-    fn make_key(&self) -> KPClass;
-    fn import_key(&self, priv_key: &[u8]) -> KPClass;
+    /// Generates a key and adds it to the keychain.
+    fn make_key(&mut self) -> Result<&KPClass, AvalancheError>;
+    fn import_key(&mut self, priv_key: &[u8]) -> Result<&KPClass, AvalancheError>;
     fn get_addresses(&self) -> Vec<&[u8]>;
     fn get_addresses_strings(&self) -> Vec<String>;
     fn add_key(&mut self, key: KPClass);
-    fn remove_key(&mut self, key: KPClass);
+    fn remove_key(&mut self, key: &KPClass) -> bool;
     fn has_key(&self, address: &[u8]) -> bool;
     fn get_key(&self, address: &[u8]) -> Option<&KPClass>;
-}
\ No newline at end of file
+}
@@ -0,0 +1,428 @@
+use crate::apis::evm::transaction::{format_evm_address, keccak256};
+use crate::common::keychain::StandardKeyPair;
+use crate::common::secp256k1::Secp256k1KeyPair;
+use crate::common::signer::{unknown_address, Signer, SignerFuture};
+use crate::common::tx::Signature;
+use crate::errors::AvalancheError;
+use crate::utils::address::ShortId;
+use crate::utils::constants::{C_CHAIN_ALIAS, FALLBACK_HRP};
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes128Gcm, Key, Nonce};
+use ctr::cipher::{NewCipher, StreamCipher};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Version of the Web3 Secret Storage layout, the keystore v3 of Ethereum.
+pub const KEYSTORE_VERSION: u32 = 3;
+
+const SCRYPT: &str = "scrypt";
+const DERIVED_KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 32;
+const GCM_NONCE_LENGTH: usize = 12;
+const CTR_IV_LENGTH: usize = 16;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// Cipher of the private key, both keyed with the first 16 bytes derived by scrypt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeystoreCipher {
+    /// Cipher of the Ethereum keystores, authenticated by the MAC of the file only.
+    Aes128Ctr,
+    /// Authenticated cipher, the tag following the ciphertext. Ethereum tools do not read it.
+    Aes128Gcm,
+}
+
+impl KeystoreCipher {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeystoreCipher::Aes128Ctr => "aes-128-ctr",
+            KeystoreCipher::Aes128Gcm => "aes-128-gcm",
+        }
+    }
+    fn parse(cipher: &str) -> Result<KeystoreCipher, AvalancheError> {
+        match cipher {
+            "aes-128-ctr" => Ok(KeystoreCipher::Aes128Ctr),
+            "aes-128-gcm" => Ok(KeystoreCipher::Aes128Gcm),
+            _ => Err(AvalancheError::InvalidValue {
+                value: String::from(cipher),
+                reason: String::from("unsupported keystore cipher"),
+            }),
+        }
+    }
+}
+
+/// Cost of scrypt, `2^log_n` iterations over blocks of `r` and `p` lanes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScryptCost {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl ScryptCost {
+    /// Cost of the keystores of geth, about a second and 256 MB per decryption.
+    pub const STANDARD: ScryptCost = ScryptCost { log_n: 18, r: 8, p: 1 };
+    /// Cost of the light keystores of geth, for devices with little memory.
+    pub const LIGHT: ScryptCost = ScryptCost { log_n: 12, r: 8, p: 6 };
+}
+
+impl ScryptCost {
+    /// Fails above the memory and work of [`ScryptCost::STANDARD`], so that a crafted file cannot
+    /// make scrypt allocate terabytes.
+    fn check(self) -> Result<(), AvalancheError> {
+        let limit = ScryptCost::STANDARD;
+        let blocks = |cost: ScryptCost, lanes: u128| (u128::from(cost.r) * lanes) << cost.log_n;
+        if self.log_n > limit.log_n
+            || blocks(self, 1) > blocks(limit, 1)
+            || blocks(self, u128::from(self.p)) > blocks(limit, u128::from(limit.p))
+        {
+            return Err(invalid(
+                &format!("n = 2^{}, r = {}, p = {}", self.log_n, self.r, self.p),
+                "scrypt costs above those of geth's standard keystores are refused",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ScryptCost {
+    fn default() -> Self {
+        ScryptCost::STANDARD
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub dklen: u32,
+    pub n: u64,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    /// Keccak-256 of the second half of the derived key and the ciphertext, checking the password.
+    pub mac: String,
+}
+
+/// Private key encrypted with a password, in the layout of the Ethereum keystore v3 files.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreFile {
+    pub version: u32,
+    pub id: String,
+    /// EVM address of the key, in hex without `0x`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(alias = "Crypto")]
+    pub crypto: KeystoreCrypto,
+}
+
+pub(crate) fn invalid(value: &str, reason: &str) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: String::from(value),
+        reason: String::from(reason),
+    }
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Random UUID of version 4, the ID of keystore files.
+fn random_uuid() -> String {
+    let mut bytes: [u8; 16] = random_bytes();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn derive_key(password: &str, salt: &[u8], cost: ScryptCost) -> Result<Zeroizing<[u8; DERIVED_KEY_LENGTH]>, AvalancheError> {
+    cost.check()?;
+    let params = scrypt::Params::new(cost.log_n, cost.r, cost.p).map_err(|_| invalid(SCRYPT, "invalid scrypt parameters"))?;
+    let mut derived_key = Zeroizing::new([0u8; DERIVED_KEY_LENGTH]);
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut derived_key[..]).map_err(|_| invalid(SCRYPT, "invalid derived key length"))?;
+    Ok(derived_key)
+}
+
+fn mac(derived_key: &[u8; DERIVED_KEY_LENGTH], ciphertext: &[u8]) -> [u8; 32] {
+    let mut data = derived_key[16..].to_vec();
+    data.extend_from_slice(ciphertext);
+    keccak256(&data)
+}
+
+impl KeystoreCrypto {
+    /// Encrypts `secret` with a key derived from `password` at `cost`.
+    pub fn encrypt(secret: &[u8], password: &str, cipher: KeystoreCipher, cost: ScryptCost) -> Result<KeystoreCrypto, AvalancheError> {
+        let salt: [u8; SALT_LENGTH] = random_bytes();
+        let derived_key = derive_key(password, &salt, cost)?;
+        let (iv, ciphertext) = match cipher {
+            KeystoreCipher::Aes128Ctr => {
+                let iv: [u8; CTR_IV_LENGTH] = random_bytes();
+                let mut ciphertext = secret.to_vec();
+                Aes128Ctr::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut ciphertext);
+                (iv.to_vec(), ciphertext)
+            }
+            KeystoreCipher::Aes128Gcm => {
+                let nonce: [u8; GCM_NONCE_LENGTH] = random_bytes();
+                let ciphertext = Aes128Gcm::new(Key::from_slice(&derived_key[..16]))
+                    .encrypt(Nonce::from_slice(&nonce), secret)
+                    .map_err(|_| AvalancheError::SigningError)?;
+                (nonce.to_vec(), ciphertext)
+            }
+        };
+        Ok(KeystoreCrypto {
+            cipher: String::from(cipher.as_str()),
+            cipherparams: CipherParams { iv: hex::encode(iv) },
+            mac: hex::encode(mac(&derived_key, &ciphertext)),
+            ciphertext: hex::encode(ciphertext),
+            kdf: String::from(SCRYPT),
+            kdfparams: KdfParams {
+                dklen: DERIVED_KEY_LENGTH as u32,
+                n: 1 << cost.log_n,
+                r: cost.r,
+                p: cost.p,
+                salt: hex::encode(salt),
+            },
+        })
+    }
+    /// Cost of scrypt the secret was encrypted at.
+    pub fn cost(&self) -> ScryptCost {
+        ScryptCost { log_n: self.kdfparams.n.trailing_zeros() as u8, r: self.kdfparams.r, p: self.kdfparams.p }
+    }
+    /// Secret encrypted, failing on a wrong password.
+    pub fn decrypt(&self, password: &str) -> Result<Zeroizing<Vec<u8>>, AvalancheError> {
+        if self.kdf != SCRYPT || self.kdfparams.dklen as usize != DERIVED_KEY_LENGTH || !self.kdfparams.n.is_power_of_two() {
+            return Err(invalid(&self.kdf, "keys are derived by scrypt, with a power of 2 iterations, in 32 bytes"));
+        }
+        let cipher = KeystoreCipher::parse(&self.cipher)?;
+        let decode = |value: &str| hex::decode(value).map_err(|_| invalid(value, "not hex"));
+        let derived_key = derive_key(password, &decode(&self.kdfparams.salt)?, self.cost())?;
+        let ciphertext = decode(&self.ciphertext)?;
+        if mac(&derived_key, &ciphertext)[..] != decode(&self.mac)?[..] {
+            return Err(invalid(&self.mac, "wrong password or corrupted keystore"));
+        }
+        let iv = decode(&self.cipherparams.iv)?;
+        let secret = match cipher {
+            KeystoreCipher::Aes128Ctr => {
+                let iv: [u8; CTR_IV_LENGTH] = iv.try_into().map_err(|_| invalid(&self.cipherparams.iv, "IVs are 16 bytes long"))?;
+                let mut secret = Zeroizing::new(ciphertext);
+                Aes128Ctr::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut secret);
+                secret
+            }
+            KeystoreCipher::Aes128Gcm => {
+                if iv.len() != GCM_NONCE_LENGTH {
+                    return Err(invalid(&self.cipherparams.iv, "nonces are 12 bytes long"));
+                }
+                let secret = Aes128Gcm::new(Key::from_slice(&derived_key[..16]))
+                    .decrypt(Nonce::from_slice(&iv), &ciphertext[..])
+                    .map_err(|_| invalid(&self.mac, "wrong password or corrupted keystore"))?;
+                Zeroizing::new(secret)
+            }
+        };
+        Ok(secret)
+    }
+}
+
+impl KeystoreFile {
+    /// Encrypts `private_key` with a key derived from `password` at `cost`.
+    pub fn encrypt(private_key: &[u8], password: &str, cipher: KeystoreCipher, cost: ScryptCost) -> Result<KeystoreFile, AvalancheError> {
+        let key_pair = Secp256k1KeyPair::from_private_key(C_CHAIN_ALIAS, FALLBACK_HRP, private_key)?;
+        Ok(KeystoreFile {
+            version: KEYSTORE_VERSION,
+            id: random_uuid(),
+            address: Some(format_evm_address(&key_pair.get_evm_address()?).trim_start_matches("0x").to_lowercase()),
+            crypto: KeystoreCrypto::encrypt(private_key, password, cipher, cost)?,
+        })
+    }
+    /// Private key of the file, failing on a wrong password.
+    pub fn decrypt(&self, password: &str) -> Result<Zeroizing<Vec<u8>>, AvalancheError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(invalid(&self.version.to_string(), "keystores of version 3 are read"));
+        }
+        self.crypto.decrypt(password)
+    }
+    pub fn to_json(&self) -> Result<String, AvalancheError> {
+        serde_json::to_string_pretty(self).map_err(|error| invalid(&self.id, &error.to_string()))
+    }
+    pub fn from_json(json: &str) -> Result<KeystoreFile, AvalancheError> {
+        serde_json::from_str(json).map_err(|error| invalid("keystore", &error.to_string()))
+    }
+    pub fn load(path: impl AsRef<Path>) -> Result<KeystoreFile, AvalancheError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|error| invalid(&path.display().to_string(), &error.to_string()))?;
+        KeystoreFile::from_json(&json)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AvalancheError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json()?).map_err(|error| invalid(&path.display().to_string(), &error.to_string()))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct KeystoreEntry {
+    file: KeystoreFile,
+    public_key: Vec<u8>,
+    short_id: ShortId,
+}
+
+/// Keys kept encrypted in keystore files and decrypted only while signing, off the async runtime.
+///
+/// No decrypted key is kept between two signatures, so every signature runs scrypt again: a
+/// transaction spending N inputs of a key costs N derivations, about N seconds at
+/// [`ScryptCost::STANDARD`]. Keys signing many inputs are better decrypted once into a
+/// [`crate::common::secp256k1::Secp256k1KeyChain`].
+#[derive(Clone)]
+pub struct KeystoreSigner {
+    entries: Vec<KeystoreEntry>,
+    password: Zeroizing<String>,
+}
+
+impl std::fmt::Debug for KeystoreSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeystoreSigner").field("keys", &self.entries.len()).finish()
+    }
+}
+
+impl KeystoreSigner {
+    /// Signer of the keys of `files`, checking `password` decrypts every one of them.
+    /// This runs scrypt once per file on the calling thread, async code uses [`KeystoreSigner::open`].
+    pub fn new(files: Vec<KeystoreFile>, password: &str) -> Result<KeystoreSigner, AvalancheError> {
+        let entries = files
+            .into_iter()
+            .map(|file| {
+                let key_pair = Secp256k1KeyPair::from_private_key(C_CHAIN_ALIAS, FALLBACK_HRP, &file.decrypt(password)?)?;
+                Ok(KeystoreEntry { public_key: key_pair.get_public_key().to_vec(), short_id: key_pair.get_short_id(), file })
+            })
+            .collect::<Result<Vec<KeystoreEntry>, AvalancheError>>()?;
+        Ok(KeystoreSigner { entries, password: Zeroizing::new(String::from(password)) })
+    }
+    /// [`KeystoreSigner::new`] run on the blocking threads of the runtime.
+    pub async fn open(files: Vec<KeystoreFile>, password: &str) -> Result<KeystoreSigner, AvalancheError> {
+        let password = Zeroizing::new(String::from(password));
+        tokio::task::spawn_blocking(move || KeystoreSigner::new(files, &password))
+            .await
+            .map_err(|_| AvalancheError::SigningError)?
+    }
+    fn entry(&self, address: &ShortId) -> Result<&KeystoreEntry, AvalancheError> {
+        self.entries.iter().find(|entry| entry.short_id == *address).ok_or_else(|| unknown_address(address))
+    }
+}
+
+impl Signer for KeystoreSigner {
+    fn addresses(&self) -> SignerFuture<'_, Vec<ShortId>> {
+        let addresses = self.entries.iter().map(|entry| entry.short_id).collect();
+        Box::pin(async move { Ok(addresses) })
+    }
+    fn public_key<'a>(&'a self, address: &'a ShortId) -> SignerFuture<'a, Vec<u8>> {
+        Box::pin(async move { Ok(self.entry(address)?.public_key.clone()) })
+    }
+    fn sign_hash<'a>(&'a self, address: &'a ShortId, hash: &'a [u8; 32]) -> SignerFuture<'a, Signature> {
+        Box::pin(async move {
+            let file = self.entry(address)?.file.clone();
+            let password = self.password.clone();
+            let hash = *hash;
+            tokio::task::spawn_blocking(move || {
+                let private_key = file.decrypt(&password)?;
+                Secp256k1KeyPair::from_private_key(C_CHAIN_ALIAS, FALLBACK_HRP, &private_key)?.sign_hash(&hash)
+            })
+            .await
+            .map_err(|_| AvalancheError::SigningError)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::keychain::StandardKeyChain;
+    use crate::common::secp256k1::Secp256k1KeyChain;
+    use crate::common::tx::{sign_tx, sign_tx_with, BaseTx, UnsignedTx, Vm};
+    use crate::utils::address::cb58_decode;
+    use crate::utils::constants::{DEFAULT_EVM_LOCAL_GENESIS_ADDRESS, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY};
+
+    const TEST_COST: ScryptCost = ScryptCost { log_n: 10, r: 8, p: 1 };
+
+    #[test]
+    fn ethereum_keystores_decrypt() {
+        // Keystore of geth's layout with the scrypt cost lowered, password "testpassword"
+        let file = KeystoreFile::from_json(
+            r#"{
+                "crypto": {
+                    "cipher": "aes-128-ctr",
+                    "cipherparams": {"iv": "6465666768696a6b6c6d6e6f70717273"},
+                    "ciphertext": "59b7da563f07f618ec269ff957085d00b6fa2ea94843b88e6b16cda43d4dbbeb",
+                    "kdf": "scrypt",
+                    "kdfparams": {"dklen": 32, "n": 1024, "p": 1, "r": 8, "salt": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"},
+                    "mac": "f1b9377e5b8414446a817085413aef48027644713fd05995e864ba3ff93d7187"
+                },
+                "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+                "version": 3
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(hex::encode(&*file.decrypt("testpassword").unwrap()), "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d");
+        assert!(file.decrypt("wrong password").is_err());
+    }
+
+    #[test]
+    fn costly_keystores_are_refused() {
+        let private_key = cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap();
+        assert!(KeystoreFile::encrypt(&private_key, "password", KeystoreCipher::Aes128Ctr, ScryptCost::LIGHT).is_ok());
+        let file = KeystoreFile::encrypt(&private_key, "password", KeystoreCipher::Aes128Ctr, TEST_COST).unwrap();
+        for (n, r, p) in [(1u64 << 40, 8, 1), (1 << 18, 1024, 1), (1 << 18, 8, 64), (1 << 10, u32::MAX, u32::MAX)] {
+            let mut crafted = file.clone();
+            crafted.crypto.kdfparams = KdfParams { n, r, p, ..file.crypto.kdfparams.clone() };
+            assert!(matches!(crafted.decrypt("password"), Err(AvalancheError::InvalidValue { .. })));
+        }
+    }
+
+    #[test]
+    fn encrypted_keys_round_trip_with_both_ciphers() {
+        let private_key = cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap();
+        for cipher in [KeystoreCipher::Aes128Ctr, KeystoreCipher::Aes128Gcm] {
+            let file = KeystoreFile::encrypt(&private_key, "password", cipher, TEST_COST).unwrap();
+            assert_eq!(file.address.as_deref(), Some(&DEFAULT_EVM_LOCAL_GENESIS_ADDRESS[2..].to_lowercase()[..]));
+            let file = KeystoreFile::from_json(&file.to_json().unwrap()).unwrap();
+            assert_eq!(file.crypto.cipher, cipher.as_str());
+            assert_eq!(&*file.decrypt("password").unwrap(), &private_key);
+            assert!(file.decrypt("other password").is_err());
+            let mut tampered = file.clone();
+            tampered.crypto.ciphertext.replace_range(..2, if tampered.crypto.ciphertext.starts_with("00") { "01" } else { "00" });
+            assert!(tampered.decrypt("password").is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn keystore_and_keychain_signers_sign_alike() {
+        let private_key = cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap();
+        let file = KeystoreFile::encrypt(&private_key, "password", KeystoreCipher::Aes128Gcm, TEST_COST).unwrap();
+        assert!(KeystoreSigner::new(vec![file.clone()], "other password").is_err());
+        let keystore = KeystoreSigner::open(vec![file], "password").await.unwrap();
+        let mut keychain = Secp256k1KeyChain::new("X", "local");
+        keychain.import_key(&private_key).unwrap();
+        let addresses = keystore.addresses().await.unwrap();
+        assert_eq!(addresses, keychain.addresses().await.unwrap());
+        assert_eq!(keystore.public_key(&addresses[0]).await.unwrap(), keychain.public_key(&addresses[0]).await.unwrap());
+        let unsigned = UnsignedTx::Export { vm: Vm::Avm, base: BaseTx { network_id: 12345, ..Default::default() }, destination_chain: [1; 32], exported_outputs: Vec::new() };
+        let signers = [addresses.clone()];
+        let signed = sign_tx_with(unsigned.clone(), &signers, &keystore).await.unwrap();
+        assert_eq!(signed, sign_tx(unsigned.clone(), &signers, &keychain).unwrap());
+        assert!(sign_tx_with(unsigned, &[vec![[0; 20]]], &keystore).await.is_err());
+    }
+}
@@ -1,3 +1,19 @@
 pub mod api_base;
+pub mod balance;
+pub mod batch;
+pub mod cache;
+pub mod cross_chain;
+pub mod hd_wallet;
 pub mod json_rpc_api;
-pub mod keychain;
\ No newline at end of file
+pub mod key_file;
+pub mod keystore;
+pub mod keychain;
+pub mod rate_limit;
+pub mod retry;
+pub mod secp256k1;
+pub mod signer;
+pub mod tx;
+pub mod tx_envelope;
+pub mod tx_status;
+pub mod utxo;
+pub mod wallet_scan;
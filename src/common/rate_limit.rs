@@ -0,0 +1,217 @@
+use crate::errors::AvalancheError;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Budget of a token bucket: `requests_per_second` sustained, with bursts of up to `burst` requests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    pub fn new(requests_per_second: f64, burst: u32) -> Result<RateLimit, AvalancheError> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 || burst == 0 {
+            return Err(AvalancheError::InvalidValue {
+                value: format!("{} requests per second, burst {}", requests_per_second, burst),
+                reason: String::from("the rate and the burst must be positive"),
+            });
+        }
+        Ok(RateLimit {
+            requests_per_second,
+            burst,
+        })
+    }
+    /// Limit whose burst is one second of requests.
+    pub fn per_second(requests_per_second: f64) -> Result<RateLimit, AvalancheError> {
+        RateLimit::new(requests_per_second, requests_per_second.ceil().max(1.0) as u32)
+    }
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> TokenBucket {
+        TokenBucket {
+            limit,
+            tokens: limit.burst as f64,
+            last: Instant::now(),
+        }
+    }
+    /// Takes a token and returns the wait until it is available, the tokens going negative
+    /// while requests are queued so that they are served in order.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.requests_per_second).min(self.limit.burst as f64);
+        self.last = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.limit.requests_per_second)
+        }
+    }
+}
+
+/// Queue waits of the requests which went through a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitMetrics {
+    pub requests: u64,
+    pub delayed_requests: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+impl RateLimitMetrics {
+    pub fn average_wait(&self) -> Duration {
+        match self.requests {
+            0 => Duration::ZERO,
+            requests => self.total_wait / requests as u32,
+        }
+    }
+}
+
+/// Client-side token buckets, one for the whole client and one per endpoint path, no limit
+/// being set by default. A `Retry-After` answered by the node pauses every request.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    global: Mutex<Option<TokenBucket>>,
+    endpoints: Mutex<HashMap<String, TokenBucket>>,
+    paused_until: Mutex<Option<Instant>>,
+    requests: AtomicU64,
+    delayed_requests: AtomicU64,
+    total_wait_nanos: AtomicU64,
+    max_wait_nanos: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn set_global_limit(&self, limit: Option<RateLimit>) {
+        *self.global.lock().unwrap() = limit.map(TokenBucket::new);
+    }
+    /// Limits the requests sent to `path`, e.g. `/ext/bc/X`, on top of the global limit.
+    pub fn set_endpoint_limit(&self, path: &str, limit: Option<RateLimit>) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        match limit {
+            Some(limit) => endpoints.insert(String::from(path), TokenBucket::new(limit)),
+            None => endpoints.remove(path),
+        };
+    }
+    /// Holds every request for `duration`, as asked by the `Retry-After` of a 429 response.
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut paused_until = self.paused_until.lock().unwrap();
+        if paused_until.is_none_or(|paused_until| paused_until < until) {
+            *paused_until = Some(until);
+        }
+    }
+    /// Waits until a request to `path` fits in the budget.
+    pub async fn acquire(&self, path: &str) {
+        let wait = self.reserve(path, Instant::now());
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !wait.is_zero() {
+            let nanos = wait.as_nanos().min(u64::MAX as u128) as u64;
+            self.delayed_requests.fetch_add(1, Ordering::Relaxed);
+            self.total_wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+            self.max_wait_nanos.fetch_max(nanos, Ordering::Relaxed);
+            tokio::time::sleep(wait).await;
+        }
+    }
+    fn reserve(&self, path: &str, now: Instant) -> Duration {
+        let global = self.global.lock().unwrap().as_mut().map_or(Duration::ZERO, |bucket| bucket.reserve(now));
+        let endpoint = self
+            .endpoints
+            .lock()
+            .unwrap()
+            .get_mut(path)
+            .map_or(Duration::ZERO, |bucket| bucket.reserve(now));
+        let paused = self
+            .paused_until
+            .lock()
+            .unwrap()
+            .map_or(Duration::ZERO, |paused_until| paused_until.saturating_duration_since(now));
+        global.max(endpoint).max(paused)
+    }
+    pub fn metrics(&self) -> RateLimitMetrics {
+        RateLimitMetrics {
+            requests: self.requests.load(Ordering::Relaxed),
+            delayed_requests: self.delayed_requests.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(self.total_wait_nanos.load(Ordering::Relaxed)),
+            max_wait: Duration::from_nanos(self.max_wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_refill_at_the_configured_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket {
+            limit: RateLimit::new(10.0, 2).unwrap(),
+            tokens: 2.0,
+            last: start,
+        };
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_millis(100));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(200));
+        assert_eq!(bucket.reserve(start + Duration::from_secs(1)), Duration::ZERO);
+        assert!(RateLimit::per_second(0.0).is_err());
+        assert_eq!(RateLimit::per_second(2.5).unwrap().burst(), 3);
+    }
+
+    #[tokio::test]
+    async fn requests_wait_for_the_global_and_endpoint_budgets() {
+        let limiter = RateLimiter::default();
+        limiter.set_endpoint_limit("/ext/info", Some(RateLimit::new(50.0, 1).unwrap()));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire("/ext/info").await;
+            limiter.acquire("/ext/bc/X").await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(35));
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.requests, 6);
+        assert_eq!(metrics.delayed_requests, 2);
+        assert!(metrics.max_wait > Duration::ZERO);
+
+        limiter.set_endpoint_limit("/ext/info", None);
+        limiter.set_global_limit(Some(RateLimit::new(1000.0, 1).unwrap()));
+        limiter.pause_for(Duration::from_millis(30));
+        let start = Instant::now();
+        limiter.acquire("/ext/bc/X").await;
+        assert!(start.elapsed() >= Duration::from_millis(25));
+    }
+
+    #[test]
+    fn retry_after_is_read_in_seconds_or_as_a_date() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::errors::AvalancheError;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Exponential backoff between the attempts of a retryable call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Waits a random duration between half and all of the backoff so that clients
+    /// failing together do not retry together.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+    /// Wait before the retry following the failed attempt number `attempt`, starting at 0.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+        if self.jitter && !backoff.is_zero() {
+            rand::thread_rng().gen_range(backoff / 2..=backoff)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Timeout and retries of the calls of a client, overridable per call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RequestOptions {
+    /// Limit of each attempt, `None` waiting forever.
+    pub timeout: Option<Duration>,
+    pub retry: RetryPolicy,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        RequestOptions {
+            timeout: Some(DEFAULT_TIMEOUT),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl RequestOptions {
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+/// Whether sending a call twice has the effect of sending it once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Idempotency {
+    ReadOnly,
+    /// Issues signed bytes, a second issuance carrying the same tx ID as the first.
+    IdempotentByTxId,
+    /// Builds a new transaction or changes node state at every call.
+    NonIdempotent,
+}
+
+impl Idempotency {
+    pub fn of(method: &str) -> Idempotency {
+        match method {
+            "avm.issueTx" | "platform.issueTx" | "avax.issueTx" | "eth_sendRawTransaction" => {
+                Idempotency::IdempotentByTxId
+            }
+            "avm.send"
+            | "avm.sendMultiple"
+            | "avm.sendNFT"
+            | "avm.mint"
+            | "avm.mintNFT"
+            | "avm.createAddress"
+            | "avm.createFixedCapAsset"
+            | "avm.createVariableCapAsset"
+            | "avm.createNFTAsset"
+            | "avm.export"
+            | "avm.import"
+            | "avm.exportKey"
+            | "avm.importKey"
+            | "platform.addValidator"
+            | "platform.addDelegator"
+            | "platform.addSubnetValidator"
+            | "platform.createAddress"
+            | "platform.createSubnet"
+            | "platform.createBlockchain"
+            | "platform.exportAVAX"
+            | "platform.importAVAX"
+            | "platform.exportKey"
+            | "platform.importKey"
+            | "avax.export"
+            | "avax.exportAVAX"
+            | "avax.import"
+            | "avax.importAVAX"
+            | "avax.exportKey"
+            | "avax.importKey"
+            | "eth_sendTransaction" => Idempotency::NonIdempotent,
+            _ if method.starts_with("keystore.")
+                || method.starts_with("admin.")
+                || method.starts_with("personal_") =>
+            {
+                Idempotency::NonIdempotent
+            }
+            _ => Idempotency::ReadOnly,
+        }
+    }
+    pub fn is_retryable(self) -> bool {
+        self != Idempotency::NonIdempotent
+    }
+}
+
+/// Whether `error` may not happen again on a new attempt.
+pub fn is_transient(error: &AvalancheError) -> bool {
+    match error {
+        AvalancheError::Timeout { .. }
+        | AvalancheError::Connect { .. }
+        | AvalancheError::Http { .. }
+        | AvalancheError::WebSocket { .. }
+        | AvalancheError::RateLimited { .. } => true,
+        AvalancheError::HttpStatus { status, .. } => matches!(status, 502..=504),
+        _ => false,
+    }
+}
+
+/// Whether `error` happened before the request reached the node, so that even a
+/// [`Idempotency::NonIdempotent`] call can be sent again.
+pub fn is_unsent(error: &AvalancheError) -> bool {
+    matches!(error, AvalancheError::Connect { .. })
+}
+
+/// Runs `attempt` under the timeout of `options`, retrying transient failures when `retryable`.
+pub async fn with_retries<F, Fut, T>(
+    options: &RequestOptions,
+    retryable: bool,
+    operation: &str,
+    mut attempt: F,
+) -> Result<T, AvalancheError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AvalancheError>>,
+{
+    let mut retries = 0;
+    loop {
+        let result = match options.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, attempt())
+                .await
+                .unwrap_or_else(|_| Err(AvalancheError::Timeout { operation: String::from(operation) })),
+            None => attempt().await,
+        };
+        match result {
+            Err(error) if retryable && retries < options.retry.max_retries && is_transient(&error) => {
+                tokio::time::sleep(options.retry.backoff(retries)).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+        let jittered = RetryPolicy::default().backoff(1);
+        assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn writes_are_classified() {
+        assert_eq!(Idempotency::of("info.getNodeID"), Idempotency::ReadOnly);
+        assert_eq!(Idempotency::of("eth_sendRawTransaction"), Idempotency::IdempotentByTxId);
+        assert_eq!(Idempotency::of("avm.send"), Idempotency::NonIdempotent);
+        assert_eq!(Idempotency::of("keystore.createUser"), Idempotency::NonIdempotent);
+        assert!(!Idempotency::NonIdempotent.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let options = RequestOptions::default().with_retry(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        });
+        let attempts = AtomicU32::new(0);
+        let result = with_retries(&options, true, "test", || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(AvalancheError::HttpStatus { status: 502, body: String::new() }),
+                1 => Err(AvalancheError::Http { message: String::from("connection reset") }),
+                _ => Ok(7),
+            }
+        })
+        .await;
+        assert_eq!(result, Ok(7));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        attempts.store(0, Ordering::SeqCst);
+        let result: Result<u32, _> = with_retries(&options, false, "test", || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(AvalancheError::HttpStatus { status: 503, body: String::new() })
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn attempts_time_out() {
+        let options = RequestOptions::default()
+            .with_timeout(Some(Duration::from_millis(10)))
+            .with_retry(RetryPolicy::none());
+        let result: Result<(), _> = with_retries(&options, true, "info.getNodeID", || async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        })
+        .await;
+        assert_eq!(result, Err(AvalancheError::Timeout { operation: String::from("info.getNodeID") }));
+    }
+}
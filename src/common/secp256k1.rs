@@ -0,0 +1,321 @@
+use crate::apis::evm::transaction::{public_key_to_evm_address, EvmAddress};
+use crate::common::keychain::{StandardKeyChain, StandardKeyPair};
+use crate::common::tx::Signature;
+use crate::errors::AvalancheError;
+use crate::utils::address::{cb58_encode, format_address, format_private_key, public_key_to_short_id, ShortId};
+use ecdsa::hazmat::{rfc6979_generate_k, SignPrimitive};
+use k256::ecdsa::{recoverable, SigningKey, VerifyingKey};
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{FieldBytes, Scalar, Secp256k1, SecretKey, U256};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// secp256k1 key pair of the X, P and C chains, signing the SHA-256 of messages.
+#[derive(Clone)]
+pub struct Secp256k1KeyPair {
+    chain_alias: String,
+    hrp: String,
+    signing_key: Option<SigningKey>,
+    private_key: Vec<u8>,
+    public_key: Vec<u8>,
+    address: ShortId,
+}
+
+impl std::fmt::Debug for Secp256k1KeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Secp256k1KeyPair")
+            .field("address", &self.get_address_string())
+            .finish()
+    }
+}
+
+impl Drop for Secp256k1KeyPair {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl PartialEq for Secp256k1KeyPair {
+    fn eq(&self, other: &Self) -> bool {
+        self.private_key == other.private_key && self.chain_alias == other.chain_alias && self.hrp == other.hrp
+    }
+}
+
+impl Secp256k1KeyPair {
+    /// Key pair without key, to be generated or imported.
+    pub fn new(chain_alias: &str, hrp: &str) -> Secp256k1KeyPair {
+        Secp256k1KeyPair {
+            chain_alias: String::from(chain_alias),
+            hrp: String::from(hrp),
+            signing_key: None,
+            private_key: Vec::new(),
+            public_key: Vec::new(),
+            address: [0; 20],
+        }
+    }
+    pub fn generate(chain_alias: &str, hrp: &str) -> Result<Secp256k1KeyPair, AvalancheError> {
+        let mut key_pair = Secp256k1KeyPair::new(chain_alias, hrp);
+        key_pair.generate_key(None)?;
+        Ok(key_pair)
+    }
+    pub fn from_private_key(chain_alias: &str, hrp: &str, private_key: &[u8]) -> Result<Secp256k1KeyPair, AvalancheError> {
+        let mut key_pair = Secp256k1KeyPair::new(chain_alias, hrp);
+        key_pair.import_key(private_key)?;
+        Ok(key_pair)
+    }
+    pub fn get_chain_alias(&self) -> &str {
+        &self.chain_alias
+    }
+    pub fn get_hrp(&self) -> &str {
+        &self.hrp
+    }
+    /// Same key formatting its address for `chain_alias` and `hrp`.
+    pub fn with_chain(&self, chain_alias: &str, hrp: &str) -> Secp256k1KeyPair {
+        let mut key_pair = self.clone();
+        key_pair.chain_alias = String::from(chain_alias);
+        key_pair.hrp = String::from(hrp);
+        key_pair
+    }
+    pub fn get_short_id(&self) -> ShortId {
+        self.address
+    }
+    pub fn get_signing_key(&self) -> Result<&SigningKey, AvalancheError> {
+        self.signing_key.as_ref().ok_or(AvalancheError::SigningError)
+    }
+    /// Address of the key on the C-Chain EVM.
+    pub fn get_evm_address(&self) -> Result<EvmAddress, AvalancheError> {
+        Ok(public_key_to_evm_address(&self.get_signing_key()?.verifying_key()))
+    }
+    /// Recoverable signature of `hash`, a SHA-256 or Keccak-256 computed by the caller, with the
+    /// deterministic nonce of RFC 6979.
+    pub fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature, AvalancheError> {
+        self.get_signing_key()?;
+        let secret_key = SecretKey::from_be_bytes(&self.private_key).map_err(|_| AvalancheError::SigningError)?;
+        let secret = secret_key.to_nonzero_scalar();
+        let z = <Scalar as Reduce<U256>>::from_be_bytes_reduced(FieldBytes::clone_from_slice(hash));
+        let k = rfc6979_generate_k::<Secp256k1, Sha256>(&secret, &z, &[]);
+        let (signature, recovery_id) = secret.try_sign_prehashed(**k, z).map_err(|_| AvalancheError::SigningError)?;
+        let recovery_id = recovery_id.ok_or(AvalancheError::SigningError)?.try_into().map_err(|_| AvalancheError::SigningError)?;
+        let signature = recoverable::Signature::new(&signature, recovery_id).map_err(|_| AvalancheError::SigningError)?;
+        signature.as_ref().try_into().map_err(|_| AvalancheError::SigningError)
+    }
+    fn set_signing_key(&mut self, signing_key: SigningKey) {
+        self.private_key.zeroize();
+        self.private_key = signing_key.to_bytes().to_vec();
+        self.public_key = signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+        self.address = public_key_to_short_id(&self.public_key);
+        self.signing_key = Some(signing_key);
+    }
+}
+
+fn recover_verifying_key(message: &[u8], signature: &[u8]) -> Result<VerifyingKey, AvalancheError> {
+    let invalid = |reason: String| AvalancheError::InvalidValue {
+        value: hex::encode(signature),
+        reason,
+    };
+    let signature = recoverable::Signature::try_from(signature).map_err(|e| invalid(e.to_string()))?;
+    signature
+        .recover_verify_key_from_digest(Sha256::new().chain(message))
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// EVM address of the compressed or uncompressed `public_key`.
+pub fn public_key_to_evm(public_key: &[u8]) -> Result<EvmAddress, AvalancheError> {
+    let verifying_key = VerifyingKey::from_sec1_bytes(public_key).map_err(|_| AvalancheError::InvalidValue {
+        value: hex::encode(public_key),
+        reason: String::from("not a secp256k1 public key"),
+    })?;
+    Ok(public_key_to_evm_address(&verifying_key))
+}
+
+/// Short ID of the address whose key made `signature` of `message`.
+pub fn recover_short_id(message: &[u8], signature: &[u8]) -> Result<ShortId, AvalancheError> {
+    let verifying_key = recover_verifying_key(message, signature)?;
+    Ok(public_key_to_short_id(verifying_key.to_encoded_point(true).as_bytes()))
+}
+
+impl StandardKeyPair for Secp256k1KeyPair {
+    fn get_public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+    fn get_private_key(&self) -> &[u8] {
+        &self.private_key
+    }
+    fn generate_key(&mut self, entropy: Option<&[u8]>) -> Result<(), AvalancheError> {
+        match entropy {
+            Some(entropy) => self.import_key(entropy),
+            None => {
+                self.set_signing_key(SigningKey::random(&mut rand::rngs::OsRng));
+                Ok(())
+            }
+        }
+    }
+    fn import_key(&mut self, priv_key: &[u8]) -> Result<(), AvalancheError> {
+        let signing_key = SigningKey::from_bytes(priv_key).map_err(|_| AvalancheError::InvalidValue {
+            value: String::from("<private key>"),
+            reason: String::from("not a secp256k1 private key"),
+        })?;
+        self.set_signing_key(signing_key);
+        Ok(())
+    }
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, AvalancheError> {
+        Ok(self.sign_hash(&Sha256::digest(message).into())?.to_vec())
+    }
+    fn recover(&self, message: &[u8], signature: &[u8]) -> Result<Vec<u8>, AvalancheError> {
+        let verifying_key = recover_verifying_key(message, signature)?;
+        Ok(verifying_key.to_encoded_point(true).as_bytes().to_vec())
+    }
+    fn verify(&self, message: &[u8], signature: &[u8], pubk: &[u8]) -> bool {
+        self.recover(message, signature).is_ok_and(|public_key| public_key == pubk)
+    }
+    fn get_public_key_string(&self) -> String {
+        cb58_encode(&self.public_key)
+    }
+    fn get_private_key_string(&self) -> String {
+        format_private_key(&self.private_key)
+    }
+    fn get_address(&self) -> &[u8] {
+        &self.address
+    }
+    fn get_address_string(&self) -> String {
+        format_address(&self.chain_alias, &self.hrp, &self.address).unwrap_or_default()
+    }
+}
+
+/// Keys of one chain and HRP, in the order they were added.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Secp256k1KeyChain {
+    chain_alias: String,
+    hrp: String,
+    keys: Vec<Secp256k1KeyPair>,
+}
+
+impl Secp256k1KeyChain {
+    pub fn new(chain_alias: &str, hrp: &str) -> Secp256k1KeyChain {
+        Secp256k1KeyChain {
+            chain_alias: String::from(chain_alias),
+            hrp: String::from(hrp),
+            keys: Vec::new(),
+        }
+    }
+    pub fn get_chain_alias(&self) -> &str {
+        &self.chain_alias
+    }
+    pub fn get_hrp(&self) -> &str {
+        &self.hrp
+    }
+    pub fn keys(&self) -> &[Secp256k1KeyPair] {
+        &self.keys
+    }
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    /// Adds `key` unless the keychain already has it, returning the key of the keychain.
+    fn insert(&mut self, key: Secp256k1KeyPair) -> &Secp256k1KeyPair {
+        let index = match self.keys.iter().position(|existing| existing.get_address() == key.get_address()) {
+            Some(index) => index,
+            None => {
+                self.keys.push(key.with_chain(&self.chain_alias, &self.hrp));
+                self.keys.len() - 1
+            }
+        };
+        &self.keys[index]
+    }
+}
+
+impl StandardKeyChain<Secp256k1KeyPair> for Secp256k1KeyChain {
+    fn make_key(&mut self) -> Result<&Secp256k1KeyPair, AvalancheError> {
+        let key = Secp256k1KeyPair::generate(&self.chain_alias, &self.hrp)?;
+        Ok(self.insert(key))
+    }
+    fn import_key(&mut self, priv_key: &[u8]) -> Result<&Secp256k1KeyPair, AvalancheError> {
+        let key = Secp256k1KeyPair::from_private_key(&self.chain_alias, &self.hrp, priv_key)?;
+        Ok(self.insert(key))
+    }
+    fn get_addresses(&self) -> Vec<&[u8]> {
+        self.keys.iter().map(|key| key.get_address()).collect()
+    }
+    fn get_addresses_strings(&self) -> Vec<String> {
+        self.keys.iter().map(|key| key.get_address_string()).collect()
+    }
+    /// Adds `key` with the chain alias and HRP of the keychain.
+    fn add_key(&mut self, key: Secp256k1KeyPair) {
+        self.insert(key);
+    }
+    fn remove_key(&mut self, key: &Secp256k1KeyPair) -> bool {
+        let length = self.keys.len();
+        self.keys.retain(|existing| existing.get_address() != key.get_address());
+        self.keys.len() != length
+    }
+    fn has_key(&self, address: &[u8]) -> bool {
+        self.get_key(address).is_some()
+    }
+    fn get_key(&self, address: &[u8]) -> Option<&Secp256k1KeyPair> {
+        self.keys.iter().find(|key| key.get_address() == address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::evm::transaction::parse_evm_address;
+    use crate::utils::address::cb58_decode;
+    use crate::utils::constants::{DEFAULT_EVM_LOCAL_GENESIS_ADDRESS, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY};
+
+    fn ewoq() -> Secp256k1KeyPair {
+        Secp256k1KeyPair::from_private_key("X", "local", &cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn imported_keys_have_their_addresses() {
+        let key_pair = ewoq();
+        assert_eq!(key_pair.get_address_string(), "X-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u");
+        assert_eq!(key_pair.with_chain("P", "avax").get_address_string(), "P-avax18jma8ppw3nhx5r4ap8clazz0dps7rv5ukulre5");
+        assert_eq!(key_pair.get_evm_address().unwrap(), parse_evm_address(DEFAULT_EVM_LOCAL_GENESIS_ADDRESS).unwrap());
+        assert_eq!(key_pair.get_private_key_string(), format!("PrivateKey-{}", DEFAULT_LOCAL_GENESIS_PRIVATE_KEY));
+        assert_eq!(key_pair.get_public_key().len(), 33);
+    }
+
+    #[test]
+    fn signatures_recover_the_signer() {
+        let key_pair = ewoq();
+        let other = Secp256k1KeyPair::generate("X", "local").unwrap();
+        let signature = key_pair.sign(b"unsigned tx bytes").unwrap();
+        assert_eq!(signature.len(), 65);
+        assert_eq!(key_pair.recover(b"unsigned tx bytes", &signature).unwrap(), key_pair.get_public_key());
+        assert!(key_pair.verify(b"unsigned tx bytes", &signature, key_pair.get_public_key()));
+        assert!(!key_pair.verify(b"other bytes", &signature, key_pair.get_public_key()));
+        assert!(!other.verify(b"unsigned tx bytes", &signature, other.get_public_key()));
+        assert!(Secp256k1KeyPair::new("X", "local").sign(b"message").is_err());
+    }
+
+    #[test]
+    fn hashes_sign_as_the_digest_signer_does() {
+        use k256::ecdsa::signature::DigestSigner;
+        let key_pair = ewoq();
+        let expected: recoverable::Signature = key_pair.get_signing_key().unwrap().try_sign_digest(Sha256::new().chain(b"unsigned tx bytes")).unwrap();
+        let signature = key_pair.sign_hash(&Sha256::digest(b"unsigned tx bytes").into()).unwrap();
+        assert_eq!(&signature[..], expected.as_ref());
+        assert_eq!(recover_short_id(b"unsigned tx bytes", &signature).unwrap(), key_pair.get_short_id());
+        assert_eq!(public_key_to_evm(key_pair.get_public_key()).unwrap(), key_pair.get_evm_address().unwrap());
+    }
+
+    #[test]
+    fn keychains_index_keys_by_address() {
+        let mut keychain = Secp256k1KeyChain::new("P", "local");
+        keychain.add_key(ewoq());
+        let address = ewoq().get_address().to_vec();
+        assert_eq!(keychain.get_addresses_strings(), vec!["P-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"]);
+        keychain.import_key(ewoq().get_private_key()).unwrap();
+        let generated = keychain.make_key().unwrap().clone();
+        assert_eq!(keychain.len(), 2);
+        assert!(keychain.has_key(&address));
+        assert!(keychain.remove_key(&generated));
+        assert!(!keychain.remove_key(&generated));
+        assert_eq!(keychain.get_addresses(), vec![&address[..]]);
+    }
+}
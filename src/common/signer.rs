@@ -0,0 +1,48 @@
+use crate::apis::evm::transaction::EvmAddress;
+use crate::common::keychain::{StandardKeyChain, StandardKeyPair};
+use crate::common::secp256k1::{public_key_to_evm, Secp256k1KeyChain, Secp256k1KeyPair};
+use crate::common::tx::Signature;
+use crate::errors::AvalancheError;
+use crate::utils::address::ShortId;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type SignerFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, AvalancheError>> + Send + 'a>>;
+
+/// Holder of secp256k1 keys signing for the transaction builders, whether the keys are in memory,
+/// in an encrypted keystore or behind a remote HSM or KMS.
+pub trait Signer: Send + Sync {
+    /// Short IDs of the addresses of the keys, the first receiving the funds the builders move.
+    fn addresses(&self) -> SignerFuture<'_, Vec<ShortId>>;
+    /// Compressed public key of the key of `address`.
+    fn public_key<'a>(&'a self, address: &'a ShortId) -> SignerFuture<'a, Vec<u8>>;
+    /// Recoverable signature of `hash` by the key of `address`: the SHA-256 of the bytes of an
+    /// Avalanche transaction or the Keccak-256 of those of an EVM transaction.
+    fn sign_hash<'a>(&'a self, address: &'a ShortId, hash: &'a [u8; 32]) -> SignerFuture<'a, Signature>;
+}
+
+pub(crate) fn unknown_address(address: &ShortId) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: hex::encode(address),
+        reason: String::from("no key of the signer has this address"),
+    }
+}
+
+/// Address on the C-Chain EVM of the key of `address`.
+pub async fn evm_address<S: Signer + ?Sized>(signer: &S, address: &ShortId) -> Result<EvmAddress, AvalancheError> {
+    public_key_to_evm(&signer.public_key(address).await?)
+}
+
+/// Keys held in memory, signing in place.
+impl Signer for Secp256k1KeyChain {
+    fn addresses(&self) -> SignerFuture<'_, Vec<ShortId>> {
+        let addresses = self.keys().iter().map(Secp256k1KeyPair::get_short_id).collect();
+        Box::pin(async move { Ok(addresses) })
+    }
+    fn public_key<'a>(&'a self, address: &'a ShortId) -> SignerFuture<'a, Vec<u8>> {
+        Box::pin(async move { Ok(self.get_key(address).ok_or_else(|| unknown_address(address))?.get_public_key().to_vec()) })
+    }
+    fn sign_hash<'a>(&'a self, address: &'a ShortId, hash: &'a [u8; 32]) -> SignerFuture<'a, Signature> {
+        Box::pin(async move { self.get_key(address).ok_or_else(|| unknown_address(address))?.sign_hash(hash) })
+    }
+}
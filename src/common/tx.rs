@@ -0,0 +1,621 @@
+use crate::apis::evm::transaction::EvmAddress;
+use crate::common::keychain::{StandardKeyChain, StandardKeyPair};
+use crate::common::signer::Signer;
+use crate::common::utxo::{Output, Utxo};
+use crate::errors::AvalancheError;
+use crate::utils::address::ShortId;
+use crate::utils::codec::{decode_hex_with_checksum, encode_hex_with_checksum, format_id, Id, Packer, Unpacker, CODEC_VERSION};
+use crate::utils::constants::{C_CHAIN_ALIAS, P_CHAIN_ALIAS, X_CHAIN_ALIAS};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const SECP256K1_TRANSFER_INPUT_ID: u32 = 5;
+pub const SECP256K1_CREDENTIAL_ID: u32 = 9;
+pub const STAKEABLE_LOCK_INPUT_ID: u32 = 21;
+
+/// Length of a recoverable secp256k1 signature.
+pub const SIGNATURE_LENGTH: usize = 65;
+
+pub type Signature = [u8; SIGNATURE_LENGTH];
+
+/// Virtual machine of a transaction, defining the type IDs of its codec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vm {
+    /// X-Chain.
+    Avm,
+    /// P-Chain.
+    Platform,
+    /// Atomic transactions of the C-Chain.
+    Evm,
+}
+
+impl Vm {
+    /// Alias of the chain the transactions of the VM are issued to.
+    pub fn chain_alias(self) -> &'static str {
+        match self {
+            Vm::Avm => X_CHAIN_ALIAS,
+            Vm::Platform => P_CHAIN_ALIAS,
+            Vm::Evm => C_CHAIN_ALIAS,
+        }
+    }
+    fn import_tx_id(self) -> u32 {
+        match self {
+            Vm::Avm => 3,
+            Vm::Platform => 17,
+            Vm::Evm => 0,
+        }
+    }
+    fn export_tx_id(self) -> u32 {
+        match self {
+            Vm::Avm => 4,
+            Vm::Platform => 18,
+            Vm::Evm => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Input {
+    Transfer { amount: u64, sig_indices: Vec<u32> },
+    /// Input of the P-Chain spending a stakeable lock output.
+    StakeableLock { locktime: u64, amount: u64, sig_indices: Vec<u32> },
+}
+
+impl Input {
+    pub fn amount(&self) -> u64 {
+        match self {
+            Input::Transfer { amount, .. } | Input::StakeableLock { amount, .. } => *amount,
+        }
+    }
+    /// Indices in the owners of the spent output of the addresses signing the input.
+    pub fn sig_indices(&self) -> &[u32] {
+        match self {
+            Input::Transfer { sig_indices, .. } | Input::StakeableLock { sig_indices, .. } => sig_indices,
+        }
+    }
+    fn pack(&self, packer: &mut Packer) {
+        match self {
+            Input::Transfer { amount, sig_indices } => {
+                packer.pack_u32(SECP256K1_TRANSFER_INPUT_ID);
+                packer.pack_u64(*amount);
+                packer.pack_u32(sig_indices.len() as u32);
+                for index in sig_indices {
+                    packer.pack_u32(*index);
+                }
+            }
+            Input::StakeableLock { locktime, amount, sig_indices } => {
+                packer.pack_u32(STAKEABLE_LOCK_INPUT_ID);
+                packer.pack_u64(*locktime);
+                Input::Transfer { amount: *amount, sig_indices: sig_indices.clone() }.pack(packer);
+            }
+        }
+    }
+    fn unpack(unpacker: &mut Unpacker) -> Result<Input, AvalancheError> {
+        match unpacker.unpack_u32()? {
+            SECP256K1_TRANSFER_INPUT_ID => {
+                let amount = unpacker.unpack_u64()?;
+                let count = unpacker.unpack_u32()?;
+                let sig_indices = (0..count).map(|_| unpacker.unpack_u32()).collect::<Result<Vec<u32>, AvalancheError>>()?;
+                Ok(Input::Transfer { amount, sig_indices })
+            }
+            STAKEABLE_LOCK_INPUT_ID => {
+                let locktime = unpacker.unpack_u64()?;
+                match Input::unpack(unpacker)? {
+                    Input::Transfer { amount, sig_indices } => Ok(Input::StakeableLock { locktime, amount, sig_indices }),
+                    Input::StakeableLock { .. } => Err(unknown_type(STAKEABLE_LOCK_INPUT_ID, "input wrapped by a stakeable lock")),
+                }
+            }
+            type_id => Err(unknown_type(type_id, "input")),
+        }
+    }
+}
+
+fn unknown_type(type_id: u32, kind: &str) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: type_id.to_string(),
+        reason: format!("unknown {} type", kind),
+    }
+}
+
+/// Input spending the UTXO `output_index` of `tx_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferableInput {
+    pub tx_id: Id,
+    pub output_index: u32,
+    pub asset_id: Id,
+    pub input: Input,
+}
+
+impl TransferableInput {
+    /// Input spending `utxo` with the signatures of the owners at `sig_indices`.
+    pub fn spend(utxo: &Utxo, sig_indices: Vec<u32>) -> Result<TransferableInput, AvalancheError> {
+        let input = match &utxo.output {
+            Output::Transfer { amount, .. } => Input::Transfer { amount: *amount, sig_indices },
+            Output::StakeableLock { locktime, amount, .. } => Input::StakeableLock { locktime: *locktime, amount: *amount, sig_indices },
+            output => {
+                return Err(AvalancheError::InvalidValue {
+                    value: utxo.id(),
+                    reason: format!("outputs of type {} are not spent by transfers", output.type_id()),
+                })
+            }
+        };
+        Ok(TransferableInput { tx_id: utxo.tx_id, output_index: utxo.output_index, asset_id: utxo.asset_id, input })
+    }
+    fn pack(&self, packer: &mut Packer) {
+        packer.pack_fixed(&self.tx_id);
+        packer.pack_u32(self.output_index);
+        packer.pack_fixed(&self.asset_id);
+        self.input.pack(packer);
+    }
+    fn unpack(unpacker: &mut Unpacker) -> Result<TransferableInput, AvalancheError> {
+        Ok(TransferableInput {
+            tx_id: unpacker.unpack_fixed()?,
+            output_index: unpacker.unpack_u32()?,
+            asset_id: unpacker.unpack_fixed()?,
+            input: Input::unpack(unpacker)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferableOutput {
+    pub asset_id: Id,
+    pub output: Output,
+}
+
+impl TransferableOutput {
+    fn pack(&self, packer: &mut Packer) {
+        packer.pack_fixed(&self.asset_id);
+        self.output.pack(packer);
+    }
+    fn unpack(unpacker: &mut Unpacker) -> Result<TransferableOutput, AvalancheError> {
+        Ok(TransferableOutput { asset_id: unpacker.unpack_fixed()?, output: Output::unpack(unpacker)? })
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut packer = Packer::new();
+        self.pack(&mut packer);
+        packer.into_bytes()
+    }
+}
+
+/// AVAX taken from an account of the C-Chain EVM, `amount` being in nAVAX.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvmInput {
+    pub address: EvmAddress,
+    pub amount: u64,
+    pub asset_id: Id,
+    /// Nonce of the account, increased by the transaction.
+    pub nonce: u64,
+}
+
+/// AVAX credited to an account of the C-Chain EVM, `amount` being in nAVAX.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvmOutput {
+    pub address: EvmAddress,
+    pub amount: u64,
+    pub asset_id: Id,
+}
+
+/// Fields shared by the transactions of the X and P chains.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BaseTx {
+    pub network_id: u32,
+    pub blockchain_id: Id,
+    pub outputs: Vec<TransferableOutput>,
+    pub inputs: Vec<TransferableInput>,
+    pub memo: Vec<u8>,
+}
+
+impl BaseTx {
+    fn pack(&self, packer: &mut Packer) {
+        packer.pack_u32(self.network_id);
+        packer.pack_fixed(&self.blockchain_id);
+        pack_outputs(packer, &self.outputs);
+        pack_inputs(packer, &self.inputs);
+        packer.pack_bytes(&self.memo);
+    }
+    fn unpack(unpacker: &mut Unpacker) -> Result<BaseTx, AvalancheError> {
+        Ok(BaseTx {
+            network_id: unpacker.unpack_u32()?,
+            blockchain_id: unpacker.unpack_fixed()?,
+            outputs: unpack_outputs(unpacker)?,
+            inputs: unpack_inputs(unpacker)?,
+            memo: unpacker.unpack_bytes()?,
+        })
+    }
+}
+
+fn pack_outputs(packer: &mut Packer, outputs: &[TransferableOutput]) {
+    packer.pack_u32(outputs.len() as u32);
+    for output in outputs {
+        output.pack(packer);
+    }
+}
+
+fn pack_inputs(packer: &mut Packer, inputs: &[TransferableInput]) {
+    packer.pack_u32(inputs.len() as u32);
+    for input in inputs {
+        input.pack(packer);
+    }
+}
+
+fn unpack_outputs(unpacker: &mut Unpacker) -> Result<Vec<TransferableOutput>, AvalancheError> {
+    let count = unpacker.unpack_u32()?;
+    (0..count).map(|_| TransferableOutput::unpack(unpacker)).collect()
+}
+
+fn unpack_inputs(unpacker: &mut Unpacker) -> Result<Vec<TransferableInput>, AvalancheError> {
+    let count = unpacker.unpack_u32()?;
+    (0..count).map(|_| TransferableInput::unpack(unpacker)).collect()
+}
+
+/// Transaction moving AVAX between the atomic memory of two chains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnsignedTx {
+    /// Import to the X or P chain of UTXOs exported from `source_chain`.
+    Import { vm: Vm, base: BaseTx, source_chain: Id, imported_inputs: Vec<TransferableInput> },
+    /// Export from the X or P chain of outputs to `destination_chain`.
+    Export { vm: Vm, base: BaseTx, destination_chain: Id, exported_outputs: Vec<TransferableOutput> },
+    EvmImport { network_id: u32, blockchain_id: Id, source_chain: Id, imported_inputs: Vec<TransferableInput>, outputs: Vec<EvmOutput> },
+    EvmExport { network_id: u32, blockchain_id: Id, destination_chain: Id, inputs: Vec<EvmInput>, exported_outputs: Vec<TransferableOutput> },
+}
+
+impl UnsignedTx {
+    pub fn vm(&self) -> Vm {
+        match self {
+            UnsignedTx::Import { vm, .. } | UnsignedTx::Export { vm, .. } => *vm,
+            UnsignedTx::EvmImport { .. } | UnsignedTx::EvmExport { .. } => Vm::Evm,
+        }
+    }
+    pub fn network_id(&self) -> u32 {
+        match self {
+            UnsignedTx::Import { base, .. } | UnsignedTx::Export { base, .. } => base.network_id,
+            UnsignedTx::EvmImport { network_id, .. } | UnsignedTx::EvmExport { network_id, .. } => *network_id,
+        }
+    }
+    /// Reads the bytes of [`UnsignedTx::to_bytes`], the type IDs depending on `vm`.
+    pub fn from_bytes(vm: Vm, bytes: &[u8]) -> Result<UnsignedTx, AvalancheError> {
+        let mut unpacker = Unpacker::new(bytes);
+        unpacker.unpack_version()?;
+        let unsigned = UnsignedTx::unpack(vm, &mut unpacker)?;
+        unpacker.finish()?;
+        Ok(unsigned)
+    }
+    /// Bytes signed by the credentials.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut packer = Packer::new();
+        packer.pack_u16(CODEC_VERSION);
+        self.pack(&mut packer);
+        packer.into_bytes()
+    }
+    fn pack(&self, packer: &mut Packer) {
+        match self {
+            UnsignedTx::Import { vm, base, source_chain, imported_inputs } => {
+                packer.pack_u32(vm.import_tx_id());
+                base.pack(packer);
+                packer.pack_fixed(source_chain);
+                pack_inputs(packer, imported_inputs);
+            }
+            UnsignedTx::Export { vm, base, destination_chain, exported_outputs } => {
+                packer.pack_u32(vm.export_tx_id());
+                base.pack(packer);
+                packer.pack_fixed(destination_chain);
+                pack_outputs(packer, exported_outputs);
+            }
+            UnsignedTx::EvmImport { network_id, blockchain_id, source_chain, imported_inputs, outputs } => {
+                packer.pack_u32(Vm::Evm.import_tx_id());
+                packer.pack_u32(*network_id);
+                packer.pack_fixed(blockchain_id);
+                packer.pack_fixed(source_chain);
+                pack_inputs(packer, imported_inputs);
+                packer.pack_u32(outputs.len() as u32);
+                for output in outputs {
+                    packer.pack_fixed(&output.address);
+                    packer.pack_u64(output.amount);
+                    packer.pack_fixed(&output.asset_id);
+                }
+            }
+            UnsignedTx::EvmExport { network_id, blockchain_id, destination_chain, inputs, exported_outputs } => {
+                packer.pack_u32(Vm::Evm.export_tx_id());
+                packer.pack_u32(*network_id);
+                packer.pack_fixed(blockchain_id);
+                packer.pack_fixed(destination_chain);
+                packer.pack_u32(inputs.len() as u32);
+                for input in inputs {
+                    packer.pack_fixed(&input.address);
+                    packer.pack_u64(input.amount);
+                    packer.pack_fixed(&input.asset_id);
+                    packer.pack_u64(input.nonce);
+                }
+                pack_outputs(packer, exported_outputs);
+            }
+        }
+    }
+    fn unpack(vm: Vm, unpacker: &mut Unpacker) -> Result<UnsignedTx, AvalancheError> {
+        let type_id = unpacker.unpack_u32()?;
+        match vm {
+            Vm::Evm if type_id == vm.import_tx_id() => Ok(UnsignedTx::EvmImport {
+                network_id: unpacker.unpack_u32()?,
+                blockchain_id: unpacker.unpack_fixed()?,
+                source_chain: unpacker.unpack_fixed()?,
+                imported_inputs: unpack_inputs(unpacker)?,
+                outputs: {
+                    let count = unpacker.unpack_u32()?;
+                    (0..count)
+                        .map(|_| {
+                            Ok(EvmOutput {
+                                address: unpacker.unpack_fixed()?,
+                                amount: unpacker.unpack_u64()?,
+                                asset_id: unpacker.unpack_fixed()?,
+                            })
+                        })
+                        .collect::<Result<Vec<EvmOutput>, AvalancheError>>()?
+                },
+            }),
+            Vm::Evm if type_id == vm.export_tx_id() => Ok(UnsignedTx::EvmExport {
+                network_id: unpacker.unpack_u32()?,
+                blockchain_id: unpacker.unpack_fixed()?,
+                destination_chain: unpacker.unpack_fixed()?,
+                inputs: {
+                    let count = unpacker.unpack_u32()?;
+                    (0..count)
+                        .map(|_| {
+                            Ok(EvmInput {
+                                address: unpacker.unpack_fixed()?,
+                                amount: unpacker.unpack_u64()?,
+                                asset_id: unpacker.unpack_fixed()?,
+                                nonce: unpacker.unpack_u64()?,
+                            })
+                        })
+                        .collect::<Result<Vec<EvmInput>, AvalancheError>>()?
+                },
+                exported_outputs: unpack_outputs(unpacker)?,
+            }),
+            Vm::Avm | Vm::Platform if type_id == vm.import_tx_id() => Ok(UnsignedTx::Import {
+                vm,
+                base: BaseTx::unpack(unpacker)?,
+                source_chain: unpacker.unpack_fixed()?,
+                imported_inputs: unpack_inputs(unpacker)?,
+            }),
+            Vm::Avm | Vm::Platform if type_id == vm.export_tx_id() => Ok(UnsignedTx::Export {
+                vm,
+                base: BaseTx::unpack(unpacker)?,
+                destination_chain: unpacker.unpack_fixed()?,
+                exported_outputs: unpack_outputs(unpacker)?,
+            }),
+            _ => Err(unknown_type(type_id, "transaction")),
+        }
+    }
+}
+
+/// Signatures of one input, in the order of its signature indices.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Credential {
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tx {
+    pub unsigned: UnsignedTx,
+    /// One credential per input, the inputs of the base transaction coming first.
+    pub credentials: Vec<Credential>,
+}
+
+impl Tx {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut packer = Packer::new();
+        packer.pack_u16(CODEC_VERSION);
+        self.unsigned.pack(&mut packer);
+        packer.pack_u32(self.credentials.len() as u32);
+        for credential in &self.credentials {
+            packer.pack_u32(SECP256K1_CREDENTIAL_ID);
+            packer.pack_u32(credential.signatures.len() as u32);
+            for signature in &credential.signatures {
+                packer.pack_fixed(signature);
+            }
+        }
+        packer.into_bytes()
+    }
+    pub fn from_bytes(vm: Vm, bytes: &[u8]) -> Result<Tx, AvalancheError> {
+        let mut unpacker = Unpacker::new(bytes);
+        unpacker.unpack_version()?;
+        let unsigned = UnsignedTx::unpack(vm, &mut unpacker)?;
+        let count = unpacker.unpack_u32()?;
+        let credentials = (0..count)
+            .map(|_| {
+                let type_id = unpacker.unpack_u32()?;
+                if type_id != SECP256K1_CREDENTIAL_ID {
+                    return Err(unknown_type(type_id, "credential"));
+                }
+                let count = unpacker.unpack_u32()?;
+                let signatures = (0..count).map(|_| unpacker.unpack_fixed()).collect::<Result<Vec<Signature>, AvalancheError>>()?;
+                Ok(Credential { signatures })
+            })
+            .collect::<Result<Vec<Credential>, AvalancheError>>()?;
+        unpacker.finish()?;
+        Ok(Tx { unsigned, credentials })
+    }
+    /// Transaction in the `hex` encoding taken by `issueTx`.
+    pub fn to_hex(&self) -> String {
+        encode_hex_with_checksum(&self.to_bytes())
+    }
+    pub fn from_hex(vm: Vm, encoded: &str) -> Result<Tx, AvalancheError> {
+        Tx::from_bytes(vm, &decode_hex_with_checksum(encoded)?)
+    }
+    /// SHA-256 of the signed transaction.
+    pub fn id(&self) -> Id {
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&Sha256::digest(&self.to_bytes()));
+        id
+    }
+    pub fn id_string(&self) -> String {
+        format_id(&self.id())
+    }
+}
+
+/// Signs `unsigned` with the keys of `keys`, `signers` listing for every credential the
+/// addresses whose signatures it holds.
+pub fn sign_tx<KP, KC>(unsigned: UnsignedTx, signers: &[Vec<ShortId>], keys: &KC) -> Result<Tx, AvalancheError>
+where
+    KP: StandardKeyPair,
+    KC: StandardKeyChain<KP>,
+{
+    let bytes = unsigned.to_bytes();
+    let credentials = signers
+        .iter()
+        .map(|addresses| {
+            let signatures = addresses
+                .iter()
+                .map(|address| {
+                    let key = keys.get_key(address).ok_or_else(|| AvalancheError::InvalidValue {
+                        value: hex::encode(address),
+                        reason: String::from("no key of the keychain has this address"),
+                    })?;
+                    key.sign(&bytes)?.try_into().map_err(|_| AvalancheError::SigningError)
+                })
+                .collect::<Result<Vec<Signature>, AvalancheError>>()?;
+            Ok(Credential { signatures })
+        })
+        .collect::<Result<Vec<Credential>, AvalancheError>>()?;
+    Ok(Tx { unsigned, credentials })
+}
+
+/// Signs `unsigned` through `signer`, as [`sign_tx`] does with a keychain.
+pub async fn sign_tx_with<S: Signer + ?Sized>(unsigned: UnsignedTx, signers: &[Vec<ShortId>], signer: &S) -> Result<Tx, AvalancheError> {
+    let hash: [u8; 32] = Sha256::digest(&unsigned.to_bytes()).into();
+    let mut credentials = Vec::with_capacity(signers.len());
+    for addresses in signers {
+        let mut signatures = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            signatures.push(signer.sign_hash(address, &hash).await?);
+        }
+        credentials.push(Credential { signatures });
+    }
+    Ok(Tx { unsigned, credentials })
+}
+
+/// Length of `unsigned` once signed by `signers`, known before signing.
+pub fn signed_len(unsigned: &UnsignedTx, signers: &[Vec<ShortId>]) -> usize {
+    // Credential count, then the type ID and signature count of each credential
+    let credentials: usize = signers.iter().map(|addresses| 8 + addresses.len() * SIGNATURE_LENGTH).sum();
+    unsigned.to_bytes().len() + 4 + credentials
+}
+
+/// Inputs spending UTXOs, sorted, with the addresses signing each of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpentUtxos {
+    pub inputs: Vec<TransferableInput>,
+    pub signers: Vec<Vec<ShortId>>,
+    /// Sum of the amounts spent.
+    pub total: u64,
+}
+
+/// Spends `utxos` with the signatures of `addresses`, which must meet the threshold of each.
+pub fn spend_utxos<'a>(utxos: impl IntoIterator<Item = &'a Utxo>, addresses: &[ShortId]) -> Result<SpentUtxos, AvalancheError> {
+    let mut inputs = Vec::new();
+    let mut total: u64 = 0;
+    for utxo in utxos {
+        let owners = utxo.output.owners().ok_or_else(|| AvalancheError::InvalidValue {
+            value: utxo.id(),
+            reason: String::from("output without owners"),
+        })?;
+        let sig_indices = owners.signer_indices(addresses).ok_or_else(|| AvalancheError::InvalidValue {
+            value: utxo.id(),
+            reason: String::from("too few owners of the output to sign"),
+        })?;
+        let signers = sig_indices.iter().map(|index| owners.addresses[*index as usize]).collect();
+        total = total.checked_add(utxo.output.amount()).ok_or_else(|| AvalancheError::InvalidValue {
+            value: utxo.id(),
+            reason: String::from("amount overflows"),
+        })?;
+        inputs.push((TransferableInput::spend(utxo, sig_indices)?, signers));
+    }
+    sort_inputs(&mut inputs);
+    let (inputs, signers) = inputs.into_iter().unzip();
+    Ok(SpentUtxos { inputs, signers, total })
+}
+
+/// Sorts inputs by UTXO, as the codec requires, keeping their signers along.
+pub(crate) fn sort_inputs(inputs: &mut [(TransferableInput, Vec<ShortId>)]) {
+    inputs.sort_by_key(|(input, _)| (input.tx_id, input.output_index));
+}
+
+/// Sorts outputs by their bytes, as the codec requires.
+pub(crate) fn sort_outputs(outputs: &mut [TransferableOutput]) {
+    outputs.sort_by_cached_key(TransferableOutput::to_bytes);
+}
+
+#[derive(Serialize)]
+pub(crate) struct ParamsIssueTx {
+    pub tx: String,
+    pub encoding: &'static str,
+}
+
+impl ParamsIssueTx {
+    pub fn new(tx: &Tx) -> ParamsIssueTx {
+        ParamsIssueTx { tx: tx.to_hex(), encoding: "hex" }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ResponseIssueTx {
+    #[serde(alias = "txID")]
+    pub tx_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::secp256k1::Secp256k1KeyChain;
+    use crate::common::utxo::OutputOwners;
+    use crate::utils::address::cb58_decode;
+    use crate::utils::constants::DEFAULT_LOCAL_GENESIS_PRIVATE_KEY;
+
+    #[test]
+    fn signed_txs_pack_credentials_after_the_unsigned_tx() {
+        let mut keys = Secp256k1KeyChain::new("X", "local");
+        let key = keys.import_key(&cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap().clone();
+        let owners = OutputOwners { locktime: 0, threshold: 1, addresses: vec![key.get_short_id()] };
+        let utxo = Utxo { tx_id: [1; 32], output_index: 0, asset_id: [2; 32], output: Output::Transfer { amount: 10, owners: owners.clone() } };
+        let unsigned = UnsignedTx::Export {
+            vm: Vm::Avm,
+            base: BaseTx { network_id: 12345, blockchain_id: [3; 32], inputs: vec![TransferableInput::spend(&utxo, vec![0]).unwrap()], ..Default::default() },
+            destination_chain: [0; 32],
+            exported_outputs: vec![TransferableOutput { asset_id: [2; 32], output: Output::Transfer { amount: 9, owners } }],
+        };
+        let tx = sign_tx(unsigned.clone(), &[vec![key.get_short_id()]], &keys).unwrap();
+        let unsigned_bytes = unsigned.to_bytes();
+        let bytes = tx.to_bytes();
+        assert_eq!(&bytes[..unsigned_bytes.len()], &unsigned_bytes[..]);
+        assert_eq!(hex::encode(&unsigned_bytes[..10]), "00000000000400003039");
+        // Credential count, credential type and signature count
+        assert_eq!(hex::encode(&bytes[unsigned_bytes.len()..unsigned_bytes.len() + 12]), "000000010000000900000001");
+        assert_eq!(bytes.len(), unsigned_bytes.len() + 12 + SIGNATURE_LENGTH);
+        assert_eq!(signed_len(&unsigned, &[vec![key.get_short_id()]]), bytes.len());
+        let signature = &tx.credentials[0].signatures[0];
+        assert_eq!(key.recover(&unsigned_bytes, signature).unwrap(), key.get_public_key());
+        assert_eq!(Tx::from_hex(Vm::Avm, &tx.to_hex()).unwrap(), tx);
+        assert_eq!(UnsignedTx::from_bytes(Vm::Avm, &unsigned_bytes).unwrap(), unsigned);
+        assert!(UnsignedTx::from_bytes(Vm::Platform, &unsigned_bytes).is_err());
+        assert!(Tx::from_bytes(Vm::Avm, &unsigned_bytes).is_err());
+        assert!(sign_tx(unsigned, &[vec![[9; 20]]], &keys).is_err());
+    }
+
+    #[test]
+    fn inputs_and_outputs_sort_in_codec_order() {
+        let input = |tx: u8, output_index| TransferableInput {
+            tx_id: [tx; 32],
+            output_index,
+            asset_id: [0; 32],
+            input: Input::Transfer { amount: 1, sig_indices: vec![0] },
+        };
+        let mut inputs = vec![(input(2, 0), vec![[2; 20]]), (input(1, 1), vec![[1; 20]]), (input(1, 0), vec![[0; 20]])];
+        sort_inputs(&mut inputs);
+        assert_eq!(inputs.iter().map(|(_, signers)| signers[0][0]).collect::<Vec<u8>>(), vec![0, 1, 2]);
+        let output = |amount| TransferableOutput { asset_id: [0; 32], output: Output::Transfer { amount, owners: OutputOwners::default() } };
+        let mut outputs = vec![output(300), output(2)];
+        sort_outputs(&mut outputs);
+        assert_eq!(outputs[0].output.amount(), 2);
+    }
+}
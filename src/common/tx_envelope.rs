@@ -0,0 +1,489 @@
+use crate::apis::evm::transaction::format_evm_address;
+use crate::common::keychain::{StandardKeyChain, StandardKeyPair};
+use crate::common::secp256k1::recover_short_id;
+use crate::common::signer::Signer;
+use crate::common::tx::{Credential, Signature, TransferableInput, Tx, UnsignedTx, Vm};
+use crate::errors::AvalancheError;
+use crate::utils::address::{format_address, parse_address, ShortId};
+use crate::utils::codec::{decode_hex_with_checksum, encode_hex_with_checksum, format_id};
+use crate::utils::helper_functions::u64_string;
+use crate::Avalanche;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Version of the envelope format, changed when envelopes of older versions cannot be read.
+pub const ENVELOPE_VERSION: u32 = 1;
+
+/// Input of an unsigned transaction with the addresses that must sign it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvelopeInput {
+    /// `<tx ID>:<output index>` of the spent UTXO, or the `0x` address of the spent C-Chain account.
+    pub source: String,
+    pub asset_id: String,
+    #[serde(with = "u64_string")]
+    pub amount: u64,
+    /// Indices of the signers in the owners of the spent output.
+    pub sig_indices: Vec<u32>,
+    /// Addresses of the signers, in the order of `sig_indices`.
+    pub signers: Vec<String>,
+    /// Signatures made so far, in hex, in the order of `signers`.
+    #[serde(default)]
+    pub signatures: Vec<Option<String>>,
+}
+
+impl EnvelopeInput {
+    fn signature(&self, index: usize) -> Option<&str> {
+        self.signatures.get(index).and_then(Option::as_deref)
+    }
+    fn set_signature(&mut self, index: usize, signature: &Signature) {
+        self.signatures.resize(self.signers.len(), None);
+        self.signatures[index] = Some(hex::encode(signature));
+    }
+}
+
+/// Unsigned transaction carried to offline signers, as JSON, with the signatures made so far.
+///
+/// Each keyholder of a multisig output adds their signatures with
+/// [`UnsignedTxEnvelope::sign_partial`] and the envelopes they return are combined with
+/// [`UnsignedTxEnvelope::merge_signatures`]. The signers are fixed when the transaction is built,
+/// their signature indices being part of the signed bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTxEnvelope {
+    pub version: u32,
+    pub vm: Vm,
+    pub network_id: u32,
+    /// Codec bytes of the unsigned transaction, in the `hex` encoding of the node APIs.
+    pub unsigned_tx: String,
+    /// One input per credential of the signed transaction.
+    pub inputs: Vec<EnvelopeInput>,
+}
+
+impl UnsignedTxEnvelope {
+    /// Envelope of `unsigned`, `signers` listing for every credential the addresses signing it
+    /// as [`crate::common::tx::sign_tx`] takes them.
+    pub fn new(unsigned: &UnsignedTx, signers: &[Vec<ShortId>], hrp: &str) -> Result<UnsignedTxEnvelope, AvalancheError> {
+        let mut inputs = spent_inputs(unsigned);
+        if inputs.len() != signers.len() {
+            return Err(AvalancheError::InvalidValue {
+                value: signers.len().to_string(),
+                reason: format!("the transaction has {} inputs to sign", inputs.len()),
+            });
+        }
+        let chain_alias = unsigned.vm().chain_alias();
+        for (input, addresses) in inputs.iter_mut().zip(signers) {
+            input.signers = addresses
+                .iter()
+                .map(|address| format_address(chain_alias, hrp, address))
+                .collect::<Result<Vec<String>, AvalancheError>>()?;
+            input.signatures = vec![None; addresses.len()];
+        }
+        let envelope = UnsignedTxEnvelope {
+            version: ENVELOPE_VERSION,
+            vm: unsigned.vm(),
+            network_id: unsigned.network_id(),
+            unsigned_tx: encode_hex_with_checksum(&unsigned.to_bytes()),
+            inputs,
+        };
+        envelope.signers()?;
+        Ok(envelope)
+    }
+    pub fn to_json(&self) -> Result<String, AvalancheError> {
+        serde_json::to_string_pretty(self).map_err(|error| invalid_json("unsigned transaction envelope", error))
+    }
+    pub fn from_json(json: &str) -> Result<UnsignedTxEnvelope, AvalancheError> {
+        let envelope: UnsignedTxEnvelope = serde_json::from_str(json).map_err(|error| invalid_json(json, error))?;
+        check_version(envelope.version)?;
+        Ok(envelope)
+    }
+    /// Decodes the unsigned transaction, failing when it does not match the inputs of the envelope.
+    pub fn unsigned_tx(&self) -> Result<UnsignedTx, AvalancheError> {
+        check_version(self.version)?;
+        let unsigned = UnsignedTx::from_bytes(self.vm, &decode_hex_with_checksum(&self.unsigned_tx)?)?;
+        if unsigned.network_id() != self.network_id {
+            return Err(AvalancheError::InvalidValue {
+                value: unsigned.network_id().to_string(),
+                reason: format!("the envelope is for the network {}", self.network_id),
+            });
+        }
+        let spent = spent_inputs(&unsigned);
+        let matches = spent.len() == self.inputs.len()
+            && spent.iter().zip(&self.inputs).all(|(spent, input)| {
+                spent.source == input.source && spent.asset_id == input.asset_id && spent.amount == input.amount && spent.sig_indices == input.sig_indices
+            });
+        if !matches {
+            return Err(AvalancheError::InvalidValue {
+                value: self.unsigned_tx.clone(),
+                reason: String::from("the inputs of the envelope are not those of the transaction"),
+            });
+        }
+        Ok(unsigned)
+    }
+    /// Signs with the keys of `keys` the inputs of their addresses, returning how many signatures
+    /// were added.
+    pub fn sign_partial<KP, KC>(&mut self, keys: &KC) -> Result<usize, AvalancheError>
+    where
+        KP: StandardKeyPair,
+        KC: StandardKeyChain<KP>,
+    {
+        let bytes = self.unsigned_tx()?.to_bytes();
+        let signers = self.signers()?;
+        let mut added = 0;
+        for (input, addresses) in self.inputs.iter_mut().zip(signers) {
+            for (index, address) in addresses.iter().enumerate() {
+                let key = match keys.get_key(address) {
+                    Some(key) if input.signature(index).is_none() => key,
+                    _ => continue,
+                };
+                let signature: Signature = key.sign(&bytes)?.try_into().map_err(|_| AvalancheError::SigningError)?;
+                input.set_signature(index, &signature);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+    /// Signs through `signer` the inputs of its addresses, as [`UnsignedTxEnvelope::sign_partial`]
+    /// does with a keychain.
+    pub async fn sign_partial_with<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<usize, AvalancheError> {
+        let hash: [u8; 32] = Sha256::digest(&self.unsigned_tx()?.to_bytes()).into();
+        let held = signer.addresses().await?;
+        let signers = self.signers()?;
+        let mut added = 0;
+        for (input, addresses) in self.inputs.iter_mut().zip(signers) {
+            for (index, address) in addresses.iter().enumerate() {
+                if held.contains(address) && input.signature(index).is_none() {
+                    input.set_signature(index, &signer.sign_hash(address, &hash).await?);
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+    /// Adds the signatures of `other`, an envelope of the same transaction signed by other
+    /// keyholders, returning how many were added. Signatures not made by their signer are refused.
+    pub fn merge_signatures(&mut self, other: &UnsignedTxEnvelope) -> Result<usize, AvalancheError> {
+        let bytes = self.unsigned_tx()?.to_bytes();
+        if other.unsigned_tx()?.to_bytes() != bytes || other.inputs.iter().map(|input| &input.signers).ne(self.inputs.iter().map(|input| &input.signers)) {
+            return Err(AvalancheError::InvalidValue {
+                value: other.unsigned_tx.clone(),
+                reason: String::from("not the transaction of the envelope"),
+            });
+        }
+        let signers = self.signers()?;
+        let mut added = 0;
+        for ((input, other), addresses) in self.inputs.iter_mut().zip(&other.inputs).zip(signers) {
+            for (index, address) in addresses.iter().enumerate() {
+                if let (None, Some(signature)) = (input.signature(index), other.signature(index)) {
+                    let signature = check_signature(&bytes, signature, address)?;
+                    input.set_signature(index, &signature);
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+    /// Signers whose signatures are missing, once for every input they sign.
+    pub fn missing_signers(&self) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .flat_map(|input| input.signers.iter().enumerate().filter(|(index, _)| input.signature(*index).is_none()).map(|(_, signer)| signer.as_str()))
+            .collect()
+    }
+    pub fn is_fully_signed(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+    /// Signed transaction, once every input holds the signatures of the threshold of its owners.
+    pub fn finalize(&self) -> Result<SignedTxEnvelope, AvalancheError> {
+        let unsigned = self.unsigned_tx()?;
+        let missing = self.missing_signers();
+        if !missing.is_empty() {
+            return Err(AvalancheError::MissingSignatures {
+                missing: missing.into_iter().map(String::from).collect(),
+            });
+        }
+        let bytes = unsigned.to_bytes();
+        let credentials = self
+            .inputs
+            .iter()
+            .zip(self.signers()?)
+            .map(|(input, addresses)| {
+                let signatures = addresses
+                    .iter()
+                    .enumerate()
+                    .map(|(index, address)| check_signature(&bytes, input.signature(index).unwrap_or_default(), address))
+                    .collect::<Result<Vec<Signature>, AvalancheError>>()?;
+                Ok(Credential { signatures })
+            })
+            .collect::<Result<Vec<Credential>, AvalancheError>>()?;
+        Ok(SignedTxEnvelope::new(&Tx { unsigned, credentials }))
+    }
+    /// Short IDs of the signers of every input, checking there is one per signature index.
+    fn signers(&self) -> Result<Vec<Vec<ShortId>>, AvalancheError> {
+        self.inputs
+            .iter()
+            .map(|input| {
+                if input.signers.len() != input.sig_indices.len() {
+                    return Err(AvalancheError::InvalidValue {
+                        value: input.source.clone(),
+                        reason: format!("{} signers for {} signature indices", input.signers.len(), input.sig_indices.len()),
+                    });
+                }
+                input.signers.iter().map(|signer| Ok(parse_address(signer)?.2)).collect()
+            })
+            .collect()
+    }
+}
+
+/// Signed transaction ready to be issued, as JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedTxEnvelope {
+    pub version: u32,
+    pub vm: Vm,
+    pub network_id: u32,
+    pub tx_id: String,
+    /// Bytes of the signed transaction, in the `hex` encoding taken by `issueTx`.
+    pub tx: String,
+}
+
+impl SignedTxEnvelope {
+    pub fn new(tx: &Tx) -> SignedTxEnvelope {
+        SignedTxEnvelope {
+            version: ENVELOPE_VERSION,
+            vm: tx.unsigned.vm(),
+            network_id: tx.unsigned.network_id(),
+            tx_id: tx.id_string(),
+            tx: tx.to_hex(),
+        }
+    }
+    pub fn to_json(&self) -> Result<String, AvalancheError> {
+        serde_json::to_string_pretty(self).map_err(|error| invalid_json("signed transaction envelope", error))
+    }
+    pub fn from_json(json: &str) -> Result<SignedTxEnvelope, AvalancheError> {
+        let envelope: SignedTxEnvelope = serde_json::from_str(json).map_err(|error| invalid_json(json, error))?;
+        check_version(envelope.version)?;
+        Ok(envelope)
+    }
+    /// Decodes the signed transaction, failing when its ID is not the one of the envelope.
+    pub fn tx(&self) -> Result<Tx, AvalancheError> {
+        check_version(self.version)?;
+        let tx = Tx::from_hex(self.vm, &self.tx)?;
+        if tx.id_string() != self.tx_id {
+            return Err(AvalancheError::InvalidValue {
+                value: self.tx_id.clone(),
+                reason: format!("the transaction has the ID {}", tx.id_string()),
+            });
+        }
+        Ok(tx)
+    }
+}
+
+fn invalid_json(value: &str, error: serde_json::Error) -> AvalancheError {
+    AvalancheError::InvalidValue {
+        value: String::from(value),
+        reason: error.to_string(),
+    }
+}
+
+/// Decodes `signature` of `bytes`, failing when `signer` did not make it.
+fn check_signature(bytes: &[u8], signature: &str, signer: &ShortId) -> Result<Signature, AvalancheError> {
+    let invalid = || AvalancheError::InvalidValue {
+        value: String::from(signature),
+        reason: format!("not a signature of the transaction by {}", hex::encode(signer)),
+    };
+    let signature: Signature = hex::decode(signature).ok().and_then(|signature| signature.try_into().ok()).ok_or_else(invalid)?;
+    if recover_short_id(bytes, &signature).ok().as_ref() != Some(signer) {
+        return Err(invalid());
+    }
+    Ok(signature)
+}
+
+fn check_version(version: u32) -> Result<(), AvalancheError> {
+    if version != ENVELOPE_VERSION {
+        return Err(AvalancheError::InvalidValue {
+            value: version.to_string(),
+            reason: format!("envelopes of version {} are read", ENVELOPE_VERSION),
+        });
+    }
+    Ok(())
+}
+
+/// Inputs of `unsigned` in the order of its credentials, without their signers.
+fn spent_inputs(unsigned: &UnsignedTx) -> Vec<EnvelopeInput> {
+    let utxo_input = |input: &TransferableInput| EnvelopeInput {
+        source: format!("{}:{}", format_id(&input.tx_id), input.output_index),
+        asset_id: format_id(&input.asset_id),
+        amount: input.input.amount(),
+        sig_indices: input.input.sig_indices().to_vec(),
+        signers: Vec::new(),
+        signatures: Vec::new(),
+    };
+    match unsigned {
+        UnsignedTx::Import { base, imported_inputs, .. } => base.inputs.iter().chain(imported_inputs).map(utxo_input).collect(),
+        UnsignedTx::Export { base, .. } => base.inputs.iter().map(utxo_input).collect(),
+        UnsignedTx::EvmImport { imported_inputs, .. } => imported_inputs.iter().map(utxo_input).collect(),
+        UnsignedTx::EvmExport { inputs, .. } => inputs
+            .iter()
+            .map(|input| EnvelopeInput {
+                source: format_evm_address(&input.address),
+                asset_id: format_id(&input.asset_id),
+                amount: input.amount,
+                sig_indices: vec![0],
+                signers: Vec::new(),
+                signatures: Vec::new(),
+            })
+            .collect(),
+    }
+}
+
+/// Signs the transaction of `envelope` with the keys of `keys`, without any call to a node.
+pub fn sign_offline<KP, KC>(envelope: &UnsignedTxEnvelope, keys: &KC) -> Result<SignedTxEnvelope, AvalancheError>
+where
+    KP: StandardKeyPair,
+    KC: StandardKeyChain<KP>,
+{
+    let mut envelope = envelope.clone();
+    envelope.sign_partial(keys)?;
+    envelope.finalize()
+}
+
+/// Issues the transaction of `envelope` to the chain of its VM, returning its ID.
+pub async fn issue_signed_tx(avalanche: &Avalanche, envelope: &SignedTxEnvelope) -> Result<String, AvalancheError> {
+    let tx = envelope.tx()?;
+    match envelope.vm {
+        Vm::Avm => avalanche.x()?.issue_tx(&tx).await,
+        Vm::Platform => avalanche.p()?.issue_tx(&tx).await,
+        Vm::Evm => avalanche.evm()?.issue_tx(&tx).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::secp256k1::Secp256k1KeyChain;
+    use crate::common::tx::{spend_utxos, BaseTx, TransferableOutput};
+    use crate::common::utxo::{Output, OutputOwners, Utxo};
+    use crate::test_utils;
+    use crate::utils::address::cb58_decode;
+    use crate::utils::codec::parse_id;
+    use crate::utils::constants::DEFAULT_LOCAL_GENESIS_PRIVATE_KEY;
+    use serde_json::json;
+
+    const AVAX_ASSET_ID: &str = "2fombhL7aGPwj3KH4bfrmJwW6PVnMobf9Y2fn9GwxiAAJyFDbe";
+
+    fn export_envelope(keys: &Secp256k1KeyChain) -> UnsignedTxEnvelope {
+        let owner = keys.keys()[0].get_short_id();
+        let asset_id = parse_id(AVAX_ASSET_ID).unwrap();
+        let owners = OutputOwners { locktime: 0, threshold: 1, addresses: vec![owner] };
+        let utxo = Utxo { tx_id: [7; 32], output_index: 1, asset_id, output: Output::Transfer { amount: 5_000_000, owners: owners.clone() } };
+        let spent = spend_utxos([&utxo], &[owner]).unwrap();
+        let unsigned = UnsignedTx::Export {
+            vm: Vm::Avm,
+            base: BaseTx { network_id: 12345, blockchain_id: [3; 32], inputs: spent.inputs, ..Default::default() },
+            destination_chain: [4; 32],
+            exported_outputs: vec![TransferableOutput { asset_id, output: Output::Transfer { amount: 4_000_000, owners } }],
+        };
+        UnsignedTxEnvelope::new(&unsigned, &spent.signers, "local").unwrap()
+    }
+
+    #[test]
+    fn envelopes_are_signed_offline_from_json() {
+        let mut keys = Secp256k1KeyChain::new("X", "local");
+        keys.import_key(&cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap();
+        let envelope = export_envelope(&keys);
+        let json = envelope.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["vm"], "avm");
+        assert_eq!(value["inputs"][0]["amount"], "5000000");
+        assert_eq!(value["inputs"][0]["signers"], json!(["X-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"]));
+        let read = UnsignedTxEnvelope::from_json(&json).unwrap();
+        assert_eq!(read, envelope);
+        let signed = sign_offline(&read, &keys).unwrap();
+        let tx = SignedTxEnvelope::from_json(&signed.to_json().unwrap()).unwrap().tx().unwrap();
+        assert_eq!(tx.unsigned, envelope.unsigned_tx().unwrap());
+        assert_eq!(tx.id_string(), signed.tx_id);
+        assert!(sign_offline(&read, &Secp256k1KeyChain::new("X", "local")).is_err());
+    }
+
+    #[test]
+    fn envelopes_not_matching_their_transaction_are_refused() {
+        let mut keys = Secp256k1KeyChain::new("X", "local");
+        keys.import_key(&cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap();
+        let mut tampered = export_envelope(&keys);
+        tampered.inputs[0].amount = 1;
+        assert!(tampered.unsigned_tx().is_err());
+        let mut unsigned_signer = export_envelope(&keys);
+        unsigned_signer.inputs[0].signers.clear();
+        assert!(sign_offline(&unsigned_signer, &keys).is_err());
+        let mut newer = export_envelope(&keys).to_json().unwrap();
+        newer = newer.replacen("\"version\": 1", "\"version\": 2", 1);
+        assert!(UnsignedTxEnvelope::from_json(&newer).is_err());
+    }
+
+    #[tokio::test]
+    async fn multisig_signatures_are_merged_into_credentials() {
+        let keyholders: Vec<Secp256k1KeyChain> = (1..=3u8)
+            .map(|seed| {
+                let mut keys = Secp256k1KeyChain::new("P", "local");
+                keys.import_key(&[seed; 32]).unwrap();
+                keys
+            })
+            .collect();
+        let short_ids: Vec<ShortId> = keyholders.iter().map(|keys| keys.keys()[0].get_short_id()).collect();
+        let owners = OutputOwners::new(0, 2, short_ids.clone()).unwrap();
+        assert!(OutputOwners::new(0, 4, short_ids.clone()).is_err());
+        let asset_id = parse_id(AVAX_ASSET_ID).unwrap();
+        let utxo = Utxo { tx_id: [5; 32], output_index: 0, asset_id, output: Output::Transfer { amount: 9_000_000, owners: owners.clone() } };
+        // The first and third keyholders sign, in the order of the owners
+        let cosigners = [short_ids[2], short_ids[0]];
+        let spent = spend_utxos([&utxo], &cosigners).unwrap();
+        let unsigned = UnsignedTx::Export {
+            vm: Vm::Platform,
+            base: BaseTx { network_id: 12345, inputs: spent.inputs, ..Default::default() },
+            destination_chain: [4; 32],
+            exported_outputs: vec![TransferableOutput { asset_id, output: Output::Transfer { amount: 8_000_000, owners: owners.clone() } }],
+        };
+        let envelope = UnsignedTxEnvelope::new(&unsigned, &spent.signers, "local").unwrap();
+        let expected_indices: Vec<u32> = owners.addresses.iter().enumerate().filter(|(_, owner)| cosigners.contains(owner)).map(|(index, _)| index as u32).collect();
+        assert_eq!(envelope.inputs[0].sig_indices, expected_indices);
+
+        let mut first = UnsignedTxEnvelope::from_json(&envelope.to_json().unwrap()).unwrap();
+        let mut third = first.clone();
+        assert_eq!(first.sign_partial(&keyholders[0]).unwrap(), 1);
+        assert_eq!(first.sign_partial(&keyholders[1]).unwrap(), 0);
+        assert_eq!(third.sign_partial_with(&keyholders[2]).await.unwrap(), 1);
+        assert!(matches!(first.finalize(), Err(AvalancheError::MissingSignatures { missing }) if missing.len() == 1));
+
+        let mut forged = third.clone();
+        forged.inputs[0].signatures = first.inputs[0].signatures.iter().rev().cloned().collect();
+        assert!(first.clone().merge_signatures(&forged).is_err());
+
+        assert_eq!(first.merge_signatures(&third).unwrap(), 1);
+        assert!(first.is_fully_signed());
+        let tx = first.finalize().unwrap().tx().unwrap();
+        let bytes = unsigned.to_bytes();
+        let recovered: Vec<ShortId> = tx.credentials[0].signatures.iter().map(|signature| recover_short_id(&bytes, signature).unwrap()).collect();
+        let mut expected_signers = cosigners.to_vec();
+        expected_signers.sort();
+        assert_eq!(recovered, expected_signers);
+    }
+
+    #[tokio::test]
+    async fn signed_envelopes_are_issued_to_the_chain_of_their_vm() {
+        let mut keys = Secp256k1KeyChain::new("X", "local");
+        keys.import_key(&cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap()).unwrap();
+        let signed = sign_offline(&export_envelope(&keys), &keys).unwrap();
+        let expected = signed.clone();
+        let node = test_utils::mock_json_rpc_node(move |method, params| match method {
+            "avm.issueTx" => {
+                assert_eq!(params["tx"], json!(expected.tx));
+                Ok(json!({"txID": expected.tx_id}))
+            }
+            _ => Err((-32601, String::from("method not found"))),
+        })
+        .await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), Some(12345), None, None, None, false).unwrap();
+        assert_eq!(avalanche.issue_signed_tx(&signed).await.unwrap(), signed.tx_id);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::common::retry::RetryPolicy;
+use crate::errors::AvalancheError;
+use serde::Deserialize;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Chain on which a transaction was issued, the C-Chain having atomic and EVM transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxChain {
+    X,
+    P,
+    CAtomic,
+    CEvm,
+}
+
+impl fmt::Display for TxChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxChain::X => write!(f, "X-Chain"),
+            TxChain::P => write!(f, "P-Chain"),
+            TxChain::CAtomic => write!(f, "C-Chain (atomic)"),
+            TxChain::CEvm => write!(f, "C-Chain (EVM)"),
+        }
+    }
+}
+
+/// Status of a transaction as reported by its chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxStatus {
+    Accepted,
+    Processing,
+    /// Not seen by the node, possibly not propagated yet.
+    Unknown,
+    Rejected { reason: Option<String> },
+    Dropped { reason: Option<String> },
+}
+
+/// Status of a transaction which will not change anymore.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FinalTxStatus {
+    Accepted,
+    Rejected { reason: Option<String> },
+    Dropped { reason: Option<String> },
+}
+
+impl TxStatus {
+    pub fn into_final(self) -> Option<FinalTxStatus> {
+        match self {
+            TxStatus::Accepted => Some(FinalTxStatus::Accepted),
+            TxStatus::Rejected { reason } => Some(FinalTxStatus::Rejected { reason }),
+            TxStatus::Dropped { reason } => Some(FinalTxStatus::Dropped { reason }),
+            TxStatus::Processing | TxStatus::Unknown => None,
+        }
+    }
+    pub fn is_final(&self) -> bool {
+        !matches!(self, TxStatus::Processing | TxStatus::Unknown)
+    }
+}
+
+/// Answer of `avm.getTxStatus`, `platform.getTxStatus` and `avax.getAtomicTxStatus`.
+#[derive(Deserialize)]
+pub(crate) struct ResponseJRPCGetTxStatus {
+    pub status: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl TryFrom<ResponseJRPCGetTxStatus> for TxStatus {
+    type Error = AvalancheError;
+
+    fn try_from(response: ResponseJRPCGetTxStatus) -> Result<Self, Self::Error> {
+        let reason = response.reason.filter(|reason| !reason.is_empty());
+        match response.status.as_str() {
+            // Committed and Aborted are the statuses of P-Chain proposals
+            "Accepted" | "Committed" => Ok(TxStatus::Accepted),
+            "Processing" => Ok(TxStatus::Processing),
+            "Unknown" => Ok(TxStatus::Unknown),
+            "Rejected" | "Aborted" => Ok(TxStatus::Rejected { reason }),
+            "Dropped" => Ok(TxStatus::Dropped { reason }),
+            _ => Err(AvalancheError::InvalidValue {
+                value: response.status,
+                reason: String::from("unknown transaction status"),
+            }),
+        }
+    }
+}
+
+/// Polls `status` with the waits of `backoff` until the status is final or `timeout` elapses.
+pub async fn wait_for_final_status<F, Fut>(
+    operation: String,
+    timeout: Duration,
+    backoff: &RetryPolicy,
+    mut status: F,
+) -> Result<FinalTxStatus, AvalancheError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<TxStatus, AvalancheError>>,
+{
+    poll_until(operation, timeout, backoff, || {
+        let status = status();
+        async move { Ok(status.await?.into_final()) }
+    })
+    .await
+}
+
+/// Polls `poll` with the waits of `backoff` until it returns a value or `timeout` elapses.
+pub async fn poll_until<T, F, Fut>(operation: String, timeout: Duration, backoff: &RetryPolicy, mut poll: F) -> Result<T, AvalancheError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>, AvalancheError>>,
+{
+    let deadline = Instant::now() + timeout;
+    let mut attempt = 0;
+    loop {
+        if let Some(value) = poll().await? {
+            return Ok(value);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(AvalancheError::Timeout { operation });
+        }
+        tokio::time::sleep(backoff.backoff(attempt).min(deadline - now)).await;
+        attempt = attempt.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: &str, reason: Option<&str>) -> Result<TxStatus, AvalancheError> {
+        TxStatus::try_from(ResponseJRPCGetTxStatus {
+            status: String::from(status),
+            reason: reason.map(String::from),
+        })
+    }
+
+    #[test]
+    fn statuses_of_every_chain_are_parsed() {
+        assert_eq!(status("Committed", None), Ok(TxStatus::Accepted));
+        assert_eq!(status("Processing", None), Ok(TxStatus::Processing));
+        assert_eq!(status("Aborted", Some("")), Ok(TxStatus::Rejected { reason: None }));
+        assert_eq!(
+            status("Dropped", Some("failed verification")),
+            Ok(TxStatus::Dropped { reason: Some(String::from("failed verification")) })
+        );
+        assert!(status("Pending", None).is_err());
+    }
+}
@@ -0,0 +1,601 @@
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::common::tx_status::FinalTxStatus;
+use crate::errors::AvalancheError;
+use crate::utils::address::ShortId;
+use crate::utils::codec::{decode_hex_with_checksum, encode_hex_with_checksum, format_id, Id, Packer, Unpacker, CODEC_VERSION};
+use crate::utils::constants::MergeRule;
+use crate::utils::helper_functions::u64_string;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+pub const SECP256K1_MINT_OUTPUT_ID: u32 = 6;
+pub const SECP256K1_TRANSFER_OUTPUT_ID: u32 = 7;
+pub const STAKEABLE_LOCK_OUTPUT_ID: u32 = 22;
+
+/// Most UTXOs a node returns per `getUTXOs` call.
+pub const MAX_UTXOS_PER_PAGE: u32 = 1024;
+
+/// Addresses of which `threshold` must sign to spend an output after `locktime` (Unix time).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputOwners {
+    pub locktime: u64,
+    pub threshold: u32,
+    pub addresses: Vec<ShortId>,
+}
+
+impl OutputOwners {
+    /// Owners sorted and deduplicated as the codec requires, `threshold` of them signing.
+    pub fn new(locktime: u64, threshold: u32, mut addresses: Vec<ShortId>) -> Result<OutputOwners, AvalancheError> {
+        addresses.sort();
+        addresses.dedup();
+        if threshold as usize > addresses.len() || (threshold == 0 && !addresses.is_empty()) {
+            return Err(AvalancheError::InvalidValue {
+                value: threshold.to_string(),
+                reason: format!("the threshold of {} owners is between 1 and their number", addresses.len()),
+            });
+        }
+        Ok(OutputOwners { locktime, threshold, addresses })
+    }
+    /// Indices in the owners of the first `threshold` of `addresses`, the signers of an input
+    /// spending the output, or `None` when `addresses` are too few.
+    pub fn signer_indices(&self, addresses: &[ShortId]) -> Option<Vec<u32>> {
+        let indices: Vec<u32> = self
+            .addresses
+            .iter()
+            .enumerate()
+            .filter(|(_, owner)| addresses.contains(owner))
+            .map(|(index, _)| index as u32)
+            .take(self.threshold as usize)
+            .collect();
+        (indices.len() == self.threshold as usize).then_some(indices)
+    }
+    /// Whether `addresses` may spend the output at `time`.
+    pub fn can_spend(&self, addresses: &[ShortId], time: u64) -> bool {
+        self.locktime <= time && self.signer_indices(addresses).is_some()
+    }
+    fn pack(&self, packer: &mut Packer) {
+        packer.pack_u64(self.locktime);
+        packer.pack_u32(self.threshold);
+        packer.pack_u32(self.addresses.len() as u32);
+        for address in &self.addresses {
+            packer.pack_fixed(address);
+        }
+    }
+    fn unpack(unpacker: &mut Unpacker) -> Result<OutputOwners, AvalancheError> {
+        let locktime = unpacker.unpack_u64()?;
+        let threshold = unpacker.unpack_u32()?;
+        let count = unpacker.unpack_u32()?;
+        let addresses = (0..count).map(|_| unpacker.unpack_fixed()).collect::<Result<Vec<ShortId>, AvalancheError>>()?;
+        Ok(OutputOwners { locktime, threshold, addresses })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    Transfer { amount: u64, owners: OutputOwners },
+    /// Right to mint more of a variable cap asset of the X-Chain.
+    Mint { owners: OutputOwners },
+    /// Output of the P-Chain spendable after `locktime` but stakeable before.
+    StakeableLock { locktime: u64, amount: u64, owners: OutputOwners },
+    /// Output of a type this crate does not read, such as NFTs, kept as its bytes.
+    Unknown { type_id: u32, bytes: Vec<u8> },
+}
+
+impl Output {
+    pub fn type_id(&self) -> u32 {
+        match self {
+            Output::Transfer { .. } => SECP256K1_TRANSFER_OUTPUT_ID,
+            Output::Mint { .. } => SECP256K1_MINT_OUTPUT_ID,
+            Output::StakeableLock { .. } => STAKEABLE_LOCK_OUTPUT_ID,
+            Output::Unknown { type_id, .. } => *type_id,
+        }
+    }
+    /// Amount of the asset held, 0 for outputs without amount.
+    pub fn amount(&self) -> u64 {
+        match self {
+            Output::Transfer { amount, .. } | Output::StakeableLock { amount, .. } => *amount,
+            Output::Mint { .. } | Output::Unknown { .. } => 0,
+        }
+    }
+    pub fn owners(&self) -> Option<&OutputOwners> {
+        match self {
+            Output::Transfer { owners, .. } | Output::Mint { owners } | Output::StakeableLock { owners, .. } => Some(owners),
+            Output::Unknown { .. } => None,
+        }
+    }
+    /// Time until which the output cannot be spent.
+    pub fn locktime(&self) -> u64 {
+        match self {
+            Output::StakeableLock { locktime, owners, .. } => (*locktime).max(owners.locktime),
+            _ => self.owners().map_or(0, |owners| owners.locktime),
+        }
+    }
+    pub(crate) fn pack(&self, packer: &mut Packer) {
+        packer.pack_u32(self.type_id());
+        match self {
+            Output::Transfer { amount, owners } => {
+                packer.pack_u64(*amount);
+                owners.pack(packer);
+            }
+            Output::Mint { owners } => owners.pack(packer),
+            Output::StakeableLock { locktime, amount, owners } => {
+                packer.pack_u64(*locktime);
+                Output::Transfer { amount: *amount, owners: owners.clone() }.pack(packer);
+            }
+            Output::Unknown { bytes, .. } => packer.pack_fixed(bytes),
+        }
+    }
+    /// Reads an output of a transaction, failing on types this crate does not read.
+    pub(crate) fn unpack(unpacker: &mut Unpacker) -> Result<Output, AvalancheError> {
+        match Output::unpack_any(unpacker)? {
+            Output::Unknown { type_id, .. } => Err(AvalancheError::InvalidValue {
+                value: type_id.to_string(),
+                reason: String::from("unknown output type"),
+            }),
+            output => Ok(output),
+        }
+    }
+    /// Reads an output ending the bytes of `unpacker`, kept as its bytes when of an unknown type.
+    fn unpack_any(unpacker: &mut Unpacker) -> Result<Output, AvalancheError> {
+        let type_id = unpacker.unpack_u32()?;
+        match type_id {
+            SECP256K1_TRANSFER_OUTPUT_ID => {
+                let amount = unpacker.unpack_u64()?;
+                Ok(Output::Transfer { amount, owners: OutputOwners::unpack(unpacker)? })
+            }
+            SECP256K1_MINT_OUTPUT_ID => Ok(Output::Mint { owners: OutputOwners::unpack(unpacker)? }),
+            STAKEABLE_LOCK_OUTPUT_ID => {
+                let locktime = unpacker.unpack_u64()?;
+                match Output::unpack(unpacker)? {
+                    Output::Transfer { amount, owners } => Ok(Output::StakeableLock { locktime, amount, owners }),
+                    output => Err(AvalancheError::InvalidValue {
+                        value: output.type_id().to_string(),
+                        reason: String::from("stakeable locks wrap transfer outputs"),
+                    }),
+                }
+            }
+            type_id => Ok(Output::Unknown { type_id, bytes: unpacker.unpack_rest().to_vec() }),
+        }
+    }
+}
+
+/// Output `output_index` of the transaction `tx_id`, holding `asset_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Utxo {
+    pub tx_id: Id,
+    pub output_index: u32,
+    pub asset_id: Id,
+    pub output: Output,
+}
+
+impl Utxo {
+    /// `<tx ID>:<output index>`, identifying the UTXO.
+    pub fn id(&self) -> String {
+        format!("{}:{}", format_id(&self.tx_id), self.output_index)
+    }
+    /// Whether `addresses` meet the signature threshold of the owners, whatever the locktime.
+    pub fn is_signable_by(&self, addresses: &[ShortId]) -> bool {
+        self.output.owners().is_some_and(|owners| owners.signer_indices(addresses).is_some())
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut packer = Packer::new();
+        packer.pack_u16(CODEC_VERSION);
+        packer.pack_fixed(&self.tx_id);
+        packer.pack_u32(self.output_index);
+        packer.pack_fixed(&self.asset_id);
+        self.output.pack(&mut packer);
+        packer.into_bytes()
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Utxo, AvalancheError> {
+        let mut unpacker = Unpacker::new(bytes);
+        unpacker.unpack_version()?;
+        let utxo = Utxo {
+            tx_id: unpacker.unpack_fixed()?,
+            output_index: unpacker.unpack_u32()?,
+            asset_id: unpacker.unpack_fixed()?,
+            output: Output::unpack_any(&mut unpacker)?,
+        };
+        unpacker.finish()?;
+        Ok(utxo)
+    }
+    /// UTXO in the `hex` encoding of the node APIs.
+    pub fn to_hex(&self) -> String {
+        encode_hex_with_checksum(&self.to_bytes())
+    }
+    pub fn from_hex(encoded: &str) -> Result<Utxo, AvalancheError> {
+        Utxo::from_bytes(&decode_hex_with_checksum(encoded)?)
+    }
+}
+
+/// Position of a page of `getUTXOs`, the next page starting after it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct UtxoIndex {
+    pub address: String,
+    pub utxo: String,
+}
+
+#[derive(Deserialize)]
+struct ResponseGetUtxos {
+    #[serde(alias = "numFetched", with = "u64_string")]
+    num_fetched: u64,
+    utxos: Vec<String>,
+    #[serde(alias = "endIndex")]
+    end_index: UtxoIndex,
+}
+
+#[derive(Serialize)]
+struct ParamsGetUtxos<'a> {
+    addresses: &'a [String],
+    limit: u32,
+    #[serde(rename = "startIndex", skip_serializing_if = "Option::is_none")]
+    start_index: Option<&'a UtxoIndex>,
+    #[serde(rename = "sourceChain", skip_serializing_if = "Option::is_none")]
+    source_chain: Option<&'a str>,
+    encoding: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UtxoPage {
+    pub utxos: Vec<Utxo>,
+    /// Index to start the next page from.
+    pub end_index: UtxoIndex,
+}
+
+/// One page of the UTXOs of `addresses` through `method` (`avm.getUTXOs` or `platform.getUTXOs`),
+/// the UTXOs exported from `source_chain` when given.
+pub(crate) async fn get_utxos_page<A: JsonRpcApi>(
+    api: &A,
+    method: &'static str,
+    addresses: &[String],
+    source_chain: Option<&str>,
+    limit: u32,
+    start_index: Option<&UtxoIndex>,
+) -> Result<UtxoPage, AvalancheError> {
+    let params = ParamsGetUtxos { addresses, limit, start_index, source_chain, encoding: "hex" };
+    let response: ResponseGetUtxos = api.call_method(method, params, None, None).await?;
+    if response.num_fetched != response.utxos.len() as u64 {
+        return Err(AvalancheError::InvalidValue {
+            value: response.num_fetched.to_string(),
+            reason: format!("{} UTXOs returned", response.utxos.len()),
+        });
+    }
+    Ok(UtxoPage {
+        utxos: response.utxos.iter().map(|utxo| Utxo::from_hex(utxo)).collect::<Result<Vec<Utxo>, AvalancheError>>()?,
+        end_index: response.end_index,
+    })
+}
+
+/// Every UTXO of `addresses`, fetching pages until one is not full.
+pub(crate) async fn get_utxo_set<A: JsonRpcApi>(
+    api: &A,
+    method: &'static str,
+    addresses: &[String],
+    source_chain: Option<&str>,
+) -> Result<UtxoSet, AvalancheError> {
+    let mut set = UtxoSet::new();
+    let mut start_index = None;
+    loop {
+        let page = get_utxos_page(api, method, addresses, source_chain, MAX_UTXOS_PER_PAGE, start_index.as_ref()).await?;
+        let fetched = page.utxos.len();
+        set.add_all(page.utxos);
+        if fetched < MAX_UTXOS_PER_PAGE as usize {
+            return Ok(set);
+        }
+        start_index = Some(page.end_index);
+    }
+}
+
+/// Amounts of an asset held by addresses in a [`UtxoSet`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UtxoBalance {
+    /// Spendable now by the addresses.
+    pub spendable: u64,
+    /// Spendable by the addresses once their locktime has passed.
+    pub locked: u64,
+    /// Consumed by transactions issued but not final.
+    pub pending: u64,
+}
+
+/// UTXOs indexed by address, asset and transaction, tracking those spent by issued transactions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UtxoSet {
+    utxos: BTreeMap<String, Utxo>,
+    by_address: HashMap<ShortId, BTreeSet<String>>,
+    by_asset: HashMap<Id, BTreeSet<String>>,
+    by_tx: HashMap<Id, BTreeSet<String>>,
+    /// UTXOs consumed by accepted transactions, never added back.
+    spent: HashSet<String>,
+    /// UTXOs consumed by transactions not final yet, with the ID of the transaction.
+    pending: HashMap<String, String>,
+}
+
+impl UtxoSet {
+    pub fn new() -> UtxoSet {
+        UtxoSet::default()
+    }
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+    pub fn get(&self, utxo_id: &str) -> Option<&Utxo> {
+        self.utxos.get(utxo_id)
+    }
+    pub fn contains(&self, utxo_id: &str) -> bool {
+        self.utxos.contains_key(utxo_id)
+    }
+    /// UTXOs ordered by ID.
+    pub fn utxos(&self) -> impl Iterator<Item = &Utxo> {
+        self.utxos.values()
+    }
+    /// Adds `utxo` unless the set has it or it was spent, returning whether it was added.
+    pub fn add(&mut self, utxo: Utxo) -> bool {
+        let id = utxo.id();
+        if self.spent.contains(&id) || self.utxos.contains_key(&id) {
+            return false;
+        }
+        for address in utxo.output.owners().map(|owners| owners.addresses.as_slice()).unwrap_or_default() {
+            self.by_address.entry(*address).or_default().insert(id.clone());
+        }
+        self.by_asset.entry(utxo.asset_id).or_default().insert(id.clone());
+        self.by_tx.entry(utxo.tx_id).or_default().insert(id.clone());
+        self.utxos.insert(id, utxo);
+        true
+    }
+    /// Adds `utxos`, returning how many were added.
+    pub fn add_all(&mut self, utxos: impl IntoIterator<Item = Utxo>) -> usize {
+        utxos.into_iter().filter(|utxo| self.add(utxo.clone())).count()
+    }
+    pub fn remove(&mut self, utxo_id: &str) -> Option<Utxo> {
+        let utxo = self.utxos.remove(utxo_id)?;
+        let unindex = |index: Option<&mut BTreeSet<String>>| {
+            if let Some(ids) = index {
+                ids.remove(utxo_id);
+            }
+        };
+        for address in utxo.output.owners().map(|owners| owners.addresses.as_slice()).unwrap_or_default() {
+            unindex(self.by_address.get_mut(address));
+        }
+        unindex(self.by_asset.get_mut(&utxo.asset_id));
+        unindex(self.by_tx.get_mut(&utxo.tx_id));
+        self.pending.remove(utxo_id);
+        Some(utxo)
+    }
+    fn lookup<'a>(&'a self, ids: Option<&'a BTreeSet<String>>) -> Vec<&'a Utxo> {
+        ids.into_iter().flatten().filter_map(|id| self.utxos.get(id)).collect()
+    }
+    /// UTXOs of which `address` is one of the owners.
+    pub fn by_address(&self, address: &ShortId) -> Vec<&Utxo> {
+        self.lookup(self.by_address.get(address))
+    }
+    pub fn by_asset(&self, asset_id: &Id) -> Vec<&Utxo> {
+        self.lookup(self.by_asset.get(asset_id))
+    }
+    /// UTXOs produced by the transaction `tx_id`.
+    pub fn by_tx(&self, tx_id: &Id) -> Vec<&Utxo> {
+        self.lookup(self.by_tx.get(tx_id))
+    }
+    /// Set of the UTXOs of this set and of `new` kept by `rule`.
+    pub fn merge_by_rule(&self, new: &UtxoSet, rule: MergeRule) -> Result<UtxoSet, AvalancheError> {
+        let only_in = |set: &'_ UtxoSet, other: &'_ UtxoSet| -> Vec<Utxo> {
+            set.utxos().filter(|utxo| !other.contains(&utxo.id())).cloned().collect()
+        };
+        let utxos: Vec<Utxo> = match rule {
+            MergeRule::Intersection => self.utxos().filter(|utxo| new.contains(&utxo.id())).cloned().collect(),
+            MergeRule::DifferenceSelf | MergeRule::UnionMinusNew => only_in(self, new),
+            MergeRule::DifferenceNew | MergeRule::UnionMinusSelf => only_in(new, self),
+            MergeRule::SymDifference => [only_in(self, new), only_in(new, self)].concat(),
+            MergeRule::Union => self.utxos().chain(new.utxos()).cloned().collect(),
+            MergeRule::Error => {
+                return Err(AvalancheError::InvalidValue {
+                    value: String::from(rule.as_str()),
+                    reason: String::from("the merge rule rejects merges"),
+                })
+            }
+        };
+        let mut merged = UtxoSet {
+            spent: self.spent.union(&new.spent).cloned().collect(),
+            ..Default::default()
+        };
+        merged.add_all(utxos);
+        merged.pending = self
+            .pending
+            .iter()
+            .chain(&new.pending)
+            .filter(|(utxo_id, _)| merged.contains(utxo_id))
+            .map(|(utxo_id, tx_id)| (utxo_id.clone(), tx_id.clone()))
+            .collect();
+        Ok(merged)
+    }
+    /// Marks `utxo_ids` as consumed by `tx_id`, issued but not final yet.
+    pub fn mark_pending(&mut self, tx_id: &str, utxo_ids: &[String]) -> Result<(), AvalancheError> {
+        for utxo_id in utxo_ids {
+            let reason = match self.pending.get(utxo_id) {
+                _ if !self.contains(utxo_id) => String::from("UTXO not in the set"),
+                Some(spender) if spender != tx_id => format!("UTXO already spent by {}", spender),
+                _ => continue,
+            };
+            return Err(AvalancheError::InvalidValue { value: utxo_id.clone(), reason });
+        }
+        for utxo_id in utxo_ids {
+            self.pending.insert(utxo_id.clone(), String::from(tx_id));
+        }
+        Ok(())
+    }
+    /// Removes the UTXOs consumed by `tx_id` once accepted, or makes them spendable again when
+    /// rejected or dropped.
+    pub fn apply_tx_status(&mut self, tx_id: &str, status: &FinalTxStatus) {
+        let consumed: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, spender)| *spender == tx_id)
+            .map(|(utxo_id, _)| utxo_id.clone())
+            .collect();
+        for utxo_id in consumed {
+            self.pending.remove(&utxo_id);
+            if *status == FinalTxStatus::Accepted {
+                self.remove(&utxo_id);
+                self.spent.insert(utxo_id);
+            }
+        }
+    }
+    pub fn is_pending(&self, utxo_id: &str) -> bool {
+        self.pending.contains_key(utxo_id)
+    }
+    pub fn is_spent(&self, utxo_id: &str) -> bool {
+        self.spent.contains(utxo_id)
+    }
+    /// UTXOs of `asset_id` that `addresses` can spend at `time` (Unix time), skipping those
+    /// consumed by pending transactions.
+    pub fn spendable(&self, addresses: &[ShortId], asset_id: &Id, time: u64) -> Vec<&Utxo> {
+        self.by_asset(asset_id)
+            .into_iter()
+            .filter(|utxo| !self.is_pending(&utxo.id()) && utxo.output.amount() > 0)
+            .filter(|utxo| utxo.output.locktime() <= time && utxo.is_signable_by(addresses))
+            .collect()
+    }
+    /// Balance of `asset_id` of `addresses` at `time`, counting only the UTXOs of which
+    /// `addresses` meet the signature threshold.
+    pub fn get_balance(&self, addresses: &[ShortId], asset_id: &Id, time: u64) -> UtxoBalance {
+        let mut balance = UtxoBalance::default();
+        for utxo in self.by_asset(asset_id) {
+            if !utxo.is_signable_by(addresses) {
+                continue;
+            }
+            let amount = utxo.output.amount();
+            if self.is_pending(&utxo.id()) {
+                balance.pending = balance.pending.saturating_add(amount);
+            } else if utxo.output.locktime() > time {
+                balance.locked = balance.locked.saturating_add(amount);
+            } else {
+                balance.spendable = balance.spendable.saturating_add(amount);
+            }
+        }
+        balance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::Avalanche;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const ALICE: ShortId = [1; 20];
+    const BOB: ShortId = [2; 20];
+    const AVAX: Id = [0xaa; 32];
+
+    fn utxo(tx: u8, output_index: u32, output: Output) -> Utxo {
+        Utxo { tx_id: [tx; 32], output_index, asset_id: AVAX, output }
+    }
+
+    fn transfer(amount: u64, locktime: u64, threshold: u32, addresses: &[ShortId]) -> Output {
+        Output::Transfer { amount, owners: OutputOwners { locktime, threshold, addresses: addresses.to_vec() } }
+    }
+
+    fn ids(set: &UtxoSet) -> Vec<String> {
+        set.utxos().map(Utxo::id).collect()
+    }
+
+    #[test]
+    fn utxos_round_trip_through_the_codec() {
+        let owners = OutputOwners { locktime: 0, threshold: 1, addresses: vec![ALICE] };
+        for output in [
+            transfer(1_000_000, 0, 1, &[ALICE]),
+            Output::Mint { owners: owners.clone() },
+            Output::StakeableLock { locktime: 1_700_000_000, amount: 5, owners },
+            Output::Unknown { type_id: 11, bytes: vec![0, 0, 0, 1] },
+        ] {
+            let utxo = utxo(3, 1, output);
+            assert_eq!(Utxo::from_hex(&utxo.to_hex()).unwrap(), utxo);
+        }
+        let bytes = utxo(3, 1, transfer(1_000_000, 0, 1, &[ALICE])).to_bytes();
+        assert_eq!(hex::encode(&bytes[70..98]), "0000000700000000000f424000000000000000000000000100000001");
+        assert!(Utxo::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Utxo::from_bytes(&[bytes.clone(), vec![0]].concat()).is_err());
+    }
+
+    #[test]
+    fn utxo_sets_index_and_merge_by_rule() {
+        let mut current = UtxoSet::new();
+        current.add_all([utxo(1, 0, transfer(1, 0, 1, &[ALICE])), utxo(1, 1, transfer(2, 0, 1, &[BOB]))]);
+        let mut new = UtxoSet::new();
+        new.add_all([utxo(1, 1, transfer(2, 0, 1, &[BOB])), utxo(2, 0, transfer(3, 0, 1, &[ALICE, BOB]))]);
+        assert_eq!(current.by_address(&ALICE).len(), 1);
+        assert_eq!(new.by_address(&ALICE)[0].output.amount(), 3);
+        assert_eq!(current.by_tx(&[1; 32]).len(), 2);
+        assert_eq!(new.by_asset(&AVAX).len(), 2);
+        let id = |tx, output_index| format!("{}:{}", format_id(&[tx; 32]), output_index);
+        let (a, b, c) = (id(1, 0), id(1, 1), id(2, 0));
+        let merge = |rule| ids(&current.merge_by_rule(&new, rule).unwrap());
+        assert_eq!(merge(MergeRule::Intersection), vec![b.clone()]);
+        assert_eq!(merge(MergeRule::DifferenceSelf), vec![a.clone()]);
+        assert_eq!(merge(MergeRule::DifferenceNew), vec![c.clone()]);
+        assert_eq!(merge(MergeRule::SymDifference), vec![a.clone(), c.clone()]);
+        assert_eq!(merge(MergeRule::Union), vec![a.clone(), b, c.clone()]);
+        assert_eq!(merge(MergeRule::UnionMinusNew), vec![a]);
+        assert_eq!(merge(MergeRule::UnionMinusSelf), vec![c]);
+        assert!(current.merge_by_rule(&new, "ERROR".parse().unwrap()).is_err());
+        let mut removed = current.clone();
+        removed.remove(&current.by_address(&ALICE)[0].id()).unwrap();
+        assert!(removed.by_address(&ALICE).is_empty());
+    }
+
+    #[test]
+    fn spendable_balances_respect_locktimes_thresholds_and_pending_txs() {
+        let mut set = UtxoSet::new();
+        let single = utxo(1, 0, transfer(10, 0, 1, &[ALICE]));
+        let locked = utxo(2, 0, transfer(20, 2_000, 1, &[ALICE]));
+        let multisig = utxo(3, 0, transfer(40, 0, 2, &[ALICE, BOB]));
+        let stakeable = utxo(4, 0, Output::StakeableLock { locktime: 2_000, amount: 80, owners: OutputOwners { locktime: 0, threshold: 1, addresses: vec![ALICE] } });
+        set.add_all([single.clone(), locked, multisig, stakeable]);
+        assert_eq!(set.get_balance(&[ALICE], &AVAX, 1_000), UtxoBalance { spendable: 10, locked: 100, pending: 0 });
+        assert_eq!(set.get_balance(&[ALICE, BOB], &AVAX, 1_000).spendable, 50);
+        assert_eq!(set.get_balance(&[ALICE], &AVAX, 2_000).spendable, 110);
+        assert_eq!(set.spendable(&[BOB], &AVAX, 1_000), Vec::<&Utxo>::new());
+
+        set.mark_pending("tx", &[single.id()]).unwrap();
+        assert!(set.mark_pending("other", &[single.id()]).is_err());
+        assert_eq!(set.get_balance(&[ALICE], &AVAX, 1_000), UtxoBalance { spendable: 0, locked: 100, pending: 10 });
+        set.apply_tx_status("tx", &FinalTxStatus::Dropped { reason: None });
+        assert_eq!(set.get_balance(&[ALICE], &AVAX, 1_000).spendable, 10);
+        set.mark_pending("tx2", &[single.id()]).unwrap();
+        set.apply_tx_status("tx2", &FinalTxStatus::Accepted);
+        assert!(set.is_spent(&single.id()) && !set.contains(&single.id()));
+        // A node lagging behind does not bring spent UTXOs back
+        assert!(!set.add(single));
+        assert_eq!(set.get_balance(&[ALICE], &AVAX, 1_000).spendable, 0);
+    }
+
+    #[tokio::test]
+    async fn utxo_sets_load_every_page() {
+        let all: Vec<String> = (0..MAX_UTXOS_PER_PAGE + 2).map(|index| utxo(9, index, transfer(1, 0, 1, &[ALICE])).to_hex()).collect();
+        let pages = Arc::new(AtomicUsize::new(0));
+        let counter = pages.clone();
+        let node = test_utils::mock_json_rpc_node(move |method, params| {
+            assert_eq!(method, "avm.getUTXOs");
+            assert_eq!(params["sourceChain"], "C");
+            let start = match params.get("startIndex") {
+                Some(index) => index["utxo"].as_str().unwrap().parse::<usize>().unwrap(),
+                None => 0,
+            };
+            counter.fetch_add(1, Ordering::SeqCst);
+            let page: Vec<&String> = all.iter().skip(start).take(params["limit"].as_u64().unwrap() as usize).collect();
+            Ok(json!({
+                "numFetched": page.len().to_string(),
+                "utxos": page,
+                "endIndex": {"address": "X-local1", "utxo": (start + page.len()).to_string()},
+                "encoding": "hex"
+            }))
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), Some(12345), None, None, None, false).unwrap();
+        let set = avalanche.x().unwrap().get_utxo_set(&[String::from("X-local1")], Some("C")).await.unwrap();
+        assert_eq!(set.len(), MAX_UTXOS_PER_PAGE as usize + 2);
+        assert_eq!(pages.load(Ordering::SeqCst), 2);
+        assert_eq!(set.get_balance(&[ALICE], &AVAX, 0).spendable, MAX_UTXOS_PER_PAGE as u64 + 2);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::apis::avm::{AssetBalance, ParamsGetAddressTxs, ParamsGetAllBalances, ResponseGetAddressTxs, ResponseGetAllBalances};
+use crate::apis::platformvm::{ParamsGetBalance, ResponseGetBalance};
+use crate::avalanche_core::AvalancheCore;
+use crate::common::batch::BatchCall;
+use crate::common::hd_wallet::{HdWallet, CHANGE_CHAIN, EXTERNAL_CHAIN};
+use crate::common::json_rpc_api::JsonRpcApi;
+use crate::errors::AvalancheError;
+use crate::utils::address::{format_address, ShortId};
+use crate::utils::constants::{PRIMARY_ASSET_ALIAS, P_CHAIN_ALIAS, X_CHAIN_ALIAS};
+use crate::Avalanche;
+
+/// Number of consecutive unused addresses after which a scan stops, as in BIP44.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanOptions {
+    pub gap_limit: u32,
+    /// Addresses queried per batch of calls.
+    pub batch_size: u32,
+    pub scan_x: bool,
+    pub scan_p: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            gap_limit: DEFAULT_GAP_LIMIT,
+            batch_size: DEFAULT_GAP_LIMIT,
+            scan_x: true,
+            scan_p: true,
+        }
+    }
+}
+
+/// Balances and history of an address on the X-Chain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XChainActivity {
+    pub balances: Vec<AssetBalance>,
+    /// First transactions moving AVAX, empty when the node has no index.
+    pub tx_ids: Vec<String>,
+}
+
+/// Address of the wallet used on at least one of the scanned chains.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScannedAddress {
+    pub change: u32,
+    pub index: u32,
+    pub short_id: ShortId,
+    pub x: Option<XChainActivity>,
+    pub p: Option<ResponseGetBalance>,
+}
+
+impl ScannedAddress {
+    pub fn address(&self, chain_alias: &str, hrp: &str) -> Result<String, AvalancheError> {
+        format_address(chain_alias, hrp, &self.short_id)
+    }
+    fn is_used(&self) -> bool {
+        let x_used = self.x.as_ref().is_some_and(|x| !x.tx_ids.is_empty() || x.balances.iter().any(|balance| balance.balance > 0));
+        let p_used = self.p.as_ref().is_some_and(|p| !p.utxo_ids.is_empty());
+        x_used || p_used
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanResult {
+    pub addresses: Vec<ScannedAddress>,
+    /// First index following the last used external address.
+    pub next_external_index: u32,
+    /// First index following the last used change address.
+    pub next_change_index: u32,
+}
+
+/// Finds the used addresses of `wallet` on its external and change chains, querying the X and P
+/// chains in batches and stopping after `gap_limit` consecutive unused addresses.
+pub async fn scan_wallet(avalanche: &Avalanche, wallet: &HdWallet, options: &ScanOptions) -> Result<ScanResult, AvalancheError> {
+    let mut result = ScanResult::default();
+    for change in [EXTERNAL_CHAIN, CHANGE_CHAIN] {
+        let (mut addresses, next_index) = scan_chain(avalanche, wallet, options, change).await?;
+        result.addresses.append(&mut addresses);
+        match change {
+            EXTERNAL_CHAIN => result.next_external_index = next_index,
+            _ => result.next_change_index = next_index,
+        }
+    }
+    Ok(result)
+}
+
+async fn scan_chain(
+    avalanche: &Avalanche,
+    wallet: &HdWallet,
+    options: &ScanOptions,
+    change: u32,
+) -> Result<(Vec<ScannedAddress>, u32), AvalancheError> {
+    let hrp = avalanche.get_hrp();
+    let batch_size = options.batch_size.max(1);
+    let mut used = Vec::new();
+    let mut next_index = 0;
+    let mut start: u32 = 0;
+    loop {
+        let mut window = Vec::with_capacity(batch_size as usize);
+        for index in start..start.saturating_add(batch_size) {
+            let short_id = wallet.derive_avax_key(change, index, X_CHAIN_ALIAS, &hrp)?.get_short_id();
+            window.push(ScannedAddress { change, index, short_id, x: None, p: None });
+        }
+        if options.scan_x {
+            query_x_chain(avalanche, &hrp, &mut window).await?;
+        }
+        if options.scan_p {
+            query_p_chain(avalanche, &hrp, &mut window).await?;
+        }
+        for address in window {
+            if address.is_used() {
+                next_index = address.index + 1;
+                used.push(address);
+            } else if address.index + 1 - next_index >= options.gap_limit {
+                return Ok((used, next_index));
+            }
+        }
+        start = start.saturating_add(batch_size);
+    }
+}
+
+async fn query_x_chain(avalanche: &Avalanche, hrp: &str, window: &mut [ScannedAddress]) -> Result<(), AvalancheError> {
+    let x = avalanche.x()?;
+    let addresses = window
+        .iter()
+        .map(|scanned| scanned.address(X_CHAIN_ALIAS, hrp))
+        .collect::<Result<Vec<String>, AvalancheError>>()?;
+    let mut batch = x.batch();
+    let calls: Vec<(BatchCall<ResponseGetAllBalances>, BatchCall<ResponseGetAddressTxs>)> = addresses
+        .iter()
+        .map(|address| {
+            let balances = batch.add("avm.getAllBalances", ParamsGetAllBalances { address });
+            let txs = batch.add("avm.getAddressTxs", ParamsGetAddressTxs {
+                address,
+                asset_id: PRIMARY_ASSET_ALIAS,
+                cursor: None,
+                page_size: Some(1),
+            });
+            (balances, txs)
+        })
+        .collect();
+    let response = batch.send().await;
+    for (scanned, (balances, txs)) in window.iter_mut().zip(calls) {
+        scanned.x = Some(XChainActivity {
+            balances: response.get(&balances)?.balances,
+            tx_ids: match response.get(&txs) {
+                Ok(txs) => txs.tx_ids,
+                // Nodes without index do not have avm.getAddressTxs
+                Err(AvalancheError::ErrorJsonRpcCall { .. }) => Vec::new(),
+                Err(error) => return Err(error),
+            },
+        });
+    }
+    Ok(())
+}
+
+async fn query_p_chain(avalanche: &Avalanche, hrp: &str, window: &mut [ScannedAddress]) -> Result<(), AvalancheError> {
+    let p = avalanche.p()?;
+    let addresses = window
+        .iter()
+        .map(|scanned| scanned.address(P_CHAIN_ALIAS, hrp).map(|address| vec![address]))
+        .collect::<Result<Vec<Vec<String>>, AvalancheError>>()?;
+    let mut batch = p.batch();
+    let calls: Vec<BatchCall<ResponseGetBalance>> = addresses
+        .iter()
+        .map(|addresses| batch.add("platform.getBalance", ParamsGetBalance { addresses }))
+        .collect();
+    let response = batch.send().await;
+    for (scanned, balance) in window.iter_mut().zip(calls) {
+        scanned.p = Some(response.get(&balance)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::keychain::StandardKeyPair;
+    use crate::test_utils;
+    use crate::utils::amount::Avax;
+    use crate::utils::constants::MNEMONIC;
+    use serde_json::json;
+
+    fn address(wallet: &HdWallet, chain_alias: &str, change: u32, index: u32) -> String {
+        wallet.derive_avax_key(change, index, chain_alias, "local").unwrap().get_address_string()
+    }
+
+    #[tokio::test]
+    async fn scans_stop_after_the_gap_limit() {
+        let wallet = HdWallet::from_mnemonic(MNEMONIC, "").unwrap();
+        let funded = address(&wallet, "X", EXTERNAL_CHAIN, 0);
+        let with_history = address(&wallet, "X", EXTERNAL_CHAIN, 3);
+        let staking = address(&wallet, "P", CHANGE_CHAIN, 1);
+        let node = test_utils::mock_json_rpc_node(move |method, params| match method {
+            "avm.getAllBalances" if params["address"] == funded => Ok(json!({"balances": [{"asset": "AVAX", "balance": "5"}]})),
+            "avm.getAllBalances" => Ok(json!({"balances": []})),
+            "avm.getAddressTxs" if params["address"] == with_history => Ok(json!({"txIDs": ["tx"], "cursor": "1"})),
+            "avm.getAddressTxs" => Ok(json!({"txIDs": [], "cursor": "0"})),
+            "platform.getBalance" if params["addresses"][0] == staking => Ok(json!({
+                "balance": "7", "unlocked": "7", "lockedStakeable": "0", "lockedNotStakeable": "0",
+                "utxoIDs": [{"txID": "tx", "outputIndex": 0}]
+            })),
+            "platform.getBalance" => Ok(json!({"balance": "0", "unlocked": "0", "lockedStakeable": "0", "lockedNotStakeable": "0", "utxoIDs": []})),
+            _ => Err((-32601, String::from("method not found"))),
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", node.port as u32, Some("http"), Some(12345), None, None, None, false).unwrap();
+        let options = ScanOptions { gap_limit: 3, batch_size: 2, ..Default::default() };
+        let result = scan_wallet(&avalanche, &wallet, &options).await.unwrap();
+        let found: Vec<(u32, u32)> = result.addresses.iter().map(|scanned| (scanned.change, scanned.index)).collect();
+        assert_eq!(found, vec![(EXTERNAL_CHAIN, 0), (EXTERNAL_CHAIN, 3), (CHANGE_CHAIN, 1)]);
+        assert_eq!((result.next_external_index, result.next_change_index), (4, 2));
+        assert_eq!(result.addresses[0].x.as_ref().unwrap().balances[0].balance, 5);
+        assert_eq!(result.addresses[2].p.as_ref().unwrap().unlocked, Avax::from_navax(7));
+        assert_eq!(result.addresses[2].address("P", "local").unwrap(), address(&wallet, "P", CHANGE_CHAIN, 1));
+        // External 0..8 and change 0..6 in windows of 2, one X and one P batch per window
+        assert_eq!(node.request_count(), 2 * (4 + 3));
+    }
+}
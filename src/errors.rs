@@ -1,3 +1,4 @@
+use crate::common::cross_chain::CrossChainTransfer;
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone, PartialEq)]
@@ -8,20 +9,105 @@ pub enum AvalancheError {
         code: String,
         message: String,
     },
+    #[error("No response to JSON RPC call {call:?} in the batch.")]
+    MissingResponse {
+        call: String
+    },
+    #[error("JSON RPC call {call:?} was answered with id {received:?} instead of {expected:?}")]
+    ResponseIdMismatch {
+        call: String,
+        expected: String,
+        received: String
+    },
     #[error("API {api:?} is not initialized.")]
     ApiNotInitialized {
         api: String
     },
+    #[error("Network {network:?} is not registered.")]
+    UnknownNetwork {
+        network: String
+    },
+    #[error("Node does not match the configured network: {mismatches:?}")]
+    NetworkMismatch {
+        mismatches: Vec<String>
+    },
     #[error("Protocol not supported.")]
     BadProtocol,
+    #[error("Invalid value {value:?}: {reason}")]
+    InvalidValue {
+        value: String,
+        reason: String
+    },
+    #[error("Signing failed.")]
+    SigningError,
+    #[error("Timed out while waiting for {operation}.")]
+    Timeout {
+        operation: String
+    },
+    #[error("Could not connect to the node: {message}")]
+    Connect {
+        message: String
+    },
+    #[error("HTTP request failed: {message}")]
+    Http {
+        message: String
+    },
+    #[error("WebSocket connection failed: {message}")]
+    WebSocket {
+        message: String
+    },
+    #[error("Node answered with HTTP status {status}: {body}")]
+    HttpStatus {
+        status: u16,
+        body: String
+    },
+    #[error("Node is rate limiting requests, retry after {retry_after:?}")]
+    RateLimited {
+        retry_after: Option<std::time::Duration>
+    },
+    #[error("No node could answer: {errors:?}")]
+    NoNodeAvailable {
+        errors: Vec<String>
+    },
+    #[error("Fewer than {quorum} nodes agreed: {answers:?}")]
+    QuorumNotReached {
+        quorum: usize,
+        answers: Vec<String>
+    },
+    #[error("Not enough {asset} to spend: {needed} needed, {available} available.")]
+    InsufficientFunds {
+        asset: String,
+        needed: u64,
+        available: u64
+    },
+    #[error("Transaction {tx_id} was not accepted: {status}")]
+    TxNotAccepted {
+        tx_id: String,
+        status: String
+    },
+    #[error("Cross-chain transfer interrupted at {:?}: {error}", .transfer.step)]
+    TransferInterrupted {
+        transfer: Box<CrossChainTransfer>,
+        error: Box<AvalancheError>
+    },
+    #[error("Transaction not fully signed, signatures of {missing:?} missing.")]
+    MissingSignatures {
+        missing: Vec<String>
+    },
     #[error("Unknown Error")]
     Unknown,
 }
 
-//TODO: Improve
 impl From<hyper::Error> for AvalancheError {
-    fn from(_error: hyper::Error) -> Self {
-        AvalancheError::Unknown
+    fn from(error: hyper::Error) -> Self {
+        if error.is_connect() {
+            return AvalancheError::Connect {
+                message: error.to_string()
+            };
+        }
+        AvalancheError::Http {
+            message: error.to_string()
+        }
     }
 }
 
@@ -0,0 +1,195 @@
+use crate::avalanche_core::{AvalancheCore, NetworkParameters};
+use crate::common::rate_limit::RateLimiter;
+use crate::common::retry::RequestOptions;
+use crate::errors::AvalancheError;
+use crate::utils::network_registry::NetworkRegistry;
+use hyper::client::ResponseFuture;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{Body, Client, Method, Request};
+use hyper_tls::HttpsConnector;
+use regex::Regex;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use url::Url;
+
+/// [`AvalancheCore`] sending requests to a single node over HTTP(S) with hyper.
+#[derive(Debug, Default)]
+pub struct HttpCore {
+    protocol: &'static str,
+    host: &'static str,
+    ip: &'static str,
+    port: u32,
+    url: String,
+    network: RwLock<NetworkParameters>,
+    headers: RwLock<HashMap<String, String>>,
+    auth: RwLock<Option<String>>,
+    request_id: AtomicU64,
+    request_options: RwLock<RequestOptions>,
+    rate_limiter: RateLimiter,
+}
+
+impl HttpCore {
+    pub fn new(
+        host: &'static str,
+        port: u32,
+        protocol: Option<&'static str>,
+    ) -> Result<HttpCore, AvalancheError> {
+        let re = Regex::new(r"[&#,@+()$~%':*?<>{}]").unwrap(); //TODO: Add "
+        let host_resolved = &re.replace_all(host, "");
+        let protocol_defined: &'static str = protocol.unwrap_or("http");
+        let protocols: Vec<&str> = vec!["http", "https"];
+        if !protocols.contains(&protocol_defined) {
+            return Err(AvalancheError::BadProtocol);
+        }
+        Ok(HttpCore {
+            host,
+            port,
+            protocol: protocol_defined,
+            url: format!("{}://{}:{}", &protocol_defined, &host_resolved, &port),
+            ..Default::default()
+        })
+    }
+    fn set_header(
+        &self,
+        mut request: hyper::Request<hyper::Body>,
+        headers: HashMap<&str, &str>,
+    ) -> hyper::Request<hyper::Body> {
+        for (key, value) in headers {
+            request.headers_mut().insert(
+                HeaderName::from_str(key).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        for (key, value) in self.headers.read().unwrap().iter() {
+            request.headers_mut().insert(
+                HeaderName::from_str(key).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        if let Some(ref token) = *self.auth.read().unwrap() {
+            request.headers_mut().insert(
+                HeaderName::from_str("Authorization").unwrap(),
+                HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+            );
+        }
+        request
+    }
+    fn request(
+        &self,
+        url: &str,
+        method: Method,
+        get_data: HashMap<&str, &str>,
+        post_data: Body,
+        headers: HashMap<&str, &str>,
+    ) -> ResponseFuture {
+        let mut uri = Url::parse(url).unwrap();
+        for (key, value) in get_data.iter() {
+            uri.query_pairs_mut().append_pair(key, value);
+        }
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri.as_str())
+            .body(post_data)
+            .expect("request builder");
+        request = self.set_header(request, headers);
+        let https = HttpsConnector::new();
+        if self.get_protocol() == "http" {
+            Client::new().request(request)
+        } else {
+            Client::builder().build::<_, hyper::Body>(https).request(request)
+        }
+    }
+}
+
+impl AvalancheCore for HttpCore {
+    fn get_protocol(&self) -> &str {
+        self.protocol
+    }
+    fn get_host(&self) -> &str {
+        self.host
+    }
+    fn get_ip(&self) -> &str {
+        self.ip
+    }
+    fn get_port(&self) -> u32 {
+        self.port
+    }
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+    fn get_headers(&self) -> HashMap<String, String> {
+        self.headers.read().unwrap().clone()
+    }
+    fn get_network(&self) -> NetworkParameters {
+        self.network.read().unwrap().clone()
+    }
+    fn set_network(&self, network: NetworkParameters) {
+        *self.network.write().unwrap() = network;
+    }
+    fn get_network_id(&self) -> u16 {
+        self.network.read().unwrap().network_id
+    }
+    fn set_network_id(&self, network_id: u16) -> Result<(), AvalancheError> {
+        let registry = NetworkRegistry::global().read().unwrap();
+        let network = registry.get(network_id)?;
+        self.set_network(NetworkParameters::from_definition(network));
+        Ok(())
+    }
+    fn get_hrp(&self) -> String {
+        self.network.read().unwrap().hrp.clone()
+    }
+    fn set_hrp(&self, hrp: &str) {
+        self.network.write().unwrap().hrp = String::from(hrp);
+    }
+    fn set_header(&self, key: &str, value: &str) {
+        self.headers.write().unwrap().insert(String::from(key), String::from(value));
+    }
+    fn remove_header(&self, key: &str) {
+        self.headers.write().unwrap().remove(key);
+    }
+    fn remove_all_headers(&self) {
+        self.headers.write().unwrap().clear();
+    }
+    fn set_auth_token(&self, token: &str) {
+        *self.auth.write().unwrap() = Some(String::from(token));
+    }
+    fn next_request_id(&self) -> u64 {
+        self.request_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+    fn get_request_options(&self) -> RequestOptions {
+        *self.request_options.read().unwrap()
+    }
+    fn set_request_options(&self, options: RequestOptions) {
+        *self.request_options.write().unwrap() = options;
+    }
+    fn get_rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+    fn get(
+        &self,
+        url: &str,
+        get_data: HashMap<&str, &str>,
+        headers: HashMap<&str, &str>,
+    ) -> ResponseFuture {
+        self.request(url, Method::GET, get_data, Body::empty(), headers)
+    }
+    fn delete(
+        &self,
+        url: &str,
+        get_data: HashMap<&str, &str>,
+        headers: HashMap<&str, &str>,
+    ) -> ResponseFuture {
+        self.request(url, Method::DELETE, get_data, Body::empty(), headers)
+    }
+    fn put(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture {
+        self.request(url, Method::PUT, HashMap::new(), post_data, headers)
+    }
+    fn post(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture {
+        self.request(url, Method::POST, HashMap::new(), post_data, headers)
+    }
+    fn patch(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture {
+        self.request(url, Method::PATCH, HashMap::new(), post_data, headers)
+    }
+}
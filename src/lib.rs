@@ -1,58 +1,69 @@
 pub mod apis;
 pub mod avalanche_core;
 pub mod errors;
+pub mod http_core;
+pub mod multi_node;
 pub mod utils;
 pub mod common;
+#[cfg(test)]
+mod test_utils;
 
-use crate::avalanche_core::AvalancheCore;
+use crate::avalanche_core::{AvalancheCore, NetworkParameters};
 use crate::errors::AvalancheError;
-use crate::utils::constants::{DEFAULT_NETWORK_ID, NETWORK};
-use crate::utils::helper_functions::get_preferred_hrp;
+use crate::http_core::HttpCore;
+use crate::utils::constants::{DEFAULT_NETWORK_ID, FALLBACK_HRP, PRIMARY_ASSET_ALIAS, X_CHAIN_ALIAS, P_CHAIN_ALIAS, C_CHAIN_ALIAS};
+use crate::utils::amount::Avax;
+use crate::utils::network_registry::NetworkRegistry;
 use crate::common::api_base::ApiBase;
+use crate::common::balance::TotalBalance;
+use crate::common::cache::CacheStats;
+use crate::common::cross_chain::{Chain, CrossChainTransfer};
+use crate::common::signer::Signer;
+use crate::common::tx_envelope::SignedTxEnvelope;
+use crate::common::rate_limit::RateLimiter;
+use crate::common::retry::{RequestOptions, RetryPolicy};
+use crate::common::tx_status::{wait_for_final_status, FinalTxStatus, TxChain, TxStatus};
+use crate::apis::avm::AvmAPI;
+use crate::apis::evm::EvmAPI;
+use crate::apis::health::HealthAPI;
 use crate::apis::info::InfoAPI;
+use crate::apis::platformvm::PlatformVMAPI;
 use hyper::client::ResponseFuture;
-use hyper::header::{HeaderName, HeaderValue};
-use hyper::{Body, Client, Method, Request};
-use regex::Regex;
+use hyper::Body;
+use std::any::{Any, TypeId};
 use std::collections::HashMap;
-use std::str::FromStr;
-use url::Url;
-use hyper_tls::HttpsConnector;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Debug, Default)]
+/// Client of a node giving access to its APIs, every API shares the same core.
+#[derive(Clone)]
 pub struct Avalanche {
-    network_id: u16,
-    hrp: &'static str,
-    protocol: &'static str,
-    host: &'static str,
-    ip: &'static str,
-    port: u32,
-    url: String,
-    headers: HashMap<String, String>,
-    auth: Option<String>,
-    apis: HashMap<&'static str, Box<dyn ApiBase>>
+    core: Arc<dyn AvalancheCore>,
+    info: Option<Arc<InfoAPI>>,
+    health: Option<Arc<HealthAPI>>,
+    evm: Option<Arc<EvmAPI>>,
+    x: Option<Arc<AvmAPI>>,
+    p: Option<Arc<PlatformVMAPI>>,
+    apis: HashMap<TypeId, Arc<dyn Any + Send + Sync>>
 }
 
-impl Clone for Avalanche {
-    fn clone(&self) -> Self {
-        Avalanche {
-            network_id: self.network_id,
-            hrp: self.hrp,
-            protocol: self.protocol,
-            host: self.host,
-            ip: self.ip,
-            port: self.port,
-            url: self.url.clone(),
-            headers: self.headers.clone(),
-            auth: self.auth.clone(),
-            apis: HashMap::new()
-        }
+impl Debug for Avalanche {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Avalanche")
+            .field("url", &self.core.get_url())
+            .field("network", &self.core.get_network())
+            .finish()
     }
 }
 
 #[allow(clippy::too_many_arguments)]
 impl Avalanche {
     // TODO: Maybe change to a builder ?
+    /// Creates a client, the missing network parameters are taken from the
+    /// [`NetworkRegistry::global`] definition of `network_id` (mainnet by default).
+    /// A network which is not registered is only accepted if its `hrp` is provided.
+    /// With `skip_init` the built-in APIs are not created.
     pub fn new(
         host: &'static str,
         port: u32,
@@ -63,171 +74,296 @@ impl Avalanche {
         hrp: Option<&'static str>,
         skip_init: bool,
     ) -> Result<Avalanche, AvalancheError> {
-        let mut avalanche: Avalanche = Avalanche::default();
-        avalanche.set_address(host, port, protocol)?;
+        let core = HttpCore::new(host, port, protocol)?;
         let network_id_resolved = network_id.unwrap_or(DEFAULT_NETWORK_ID);
-        let _x_chain_final: &'static str = match x_chain_id {
-            Some(x_chain_id_resolved) => {
-                x_chain_id_resolved
-            }
-            None => {
-                if NETWORK.contains_key(&network_id_resolved) {
-                    NETWORK.get(&network_id_resolved).unwrap().x.blockchain_id
-                } else {
-                    NETWORK.get(&12345).unwrap().x.blockchain_id
-                }
+        let mut network = {
+            let registry = NetworkRegistry::global().read().unwrap();
+            match (registry.get(network_id_resolved), hrp) {
+                (Ok(network), _) => NetworkParameters::from_definition(network),
+                (Err(_), Some(hrp_resolved)) => NetworkParameters {
+                    network_id: network_id_resolved,
+                    hrp: String::from(hrp_resolved),
+                    ..Default::default()
+                },
+                (Err(error), None) => return Err(error),
             }
         };
-        let _c_chain_final: &'static str = match c_chain_id {
-            Some(c_chain_id_resolved) => {
-                c_chain_id_resolved
-            }
-            None => {
-                if NETWORK.contains_key(&network_id_resolved) {
-                    NETWORK.get(&network_id_resolved).unwrap().c.blockchain_id
-                } else {
-                    NETWORK.get(&12345).unwrap().c.blockchain_id
-                }
-            }
+        if let Some(hrp_resolved) = hrp {
+            network.hrp = String::from(hrp_resolved);
+        }
+        if let Some(x_chain_id_resolved) = x_chain_id {
+            network.x_chain_id = Some(String::from(x_chain_id_resolved));
+        }
+        if let Some(c_chain_id_resolved) = c_chain_id {
+            network.c_chain_id = Some(String::from(c_chain_id_resolved));
+        }
+        core.set_network(network);
+        Ok(Avalanche::from_core(Arc::new(core), skip_init))
+    }
+    /// Creates a client on top of an existing core, e.g. a custom [`AvalancheCore`] implementation.
+    pub fn from_core(core: Arc<dyn AvalancheCore>, skip_init: bool) -> Avalanche {
+        let mut avalanche = Avalanche {
+            core,
+            info: None,
+            health: None,
+            evm: None,
+            x: None,
+            p: None,
+            apis: HashMap::new()
         };
-        match network_id {
-            Some(network_id_resolved) => {
-                avalanche.network_id = network_id_resolved;
+        if !skip_init {
+            avalanche.info = Some(Arc::new(InfoAPI::new(avalanche.core.clone())));
+            avalanche.health = Some(Arc::new(HealthAPI::new(avalanche.core.clone())));
+            avalanche.evm = Some(Arc::new(EvmAPI::new(avalanche.core.clone())));
+            avalanche.x = Some(Arc::new(AvmAPI::new(avalanche.core.clone())));
+            avalanche.p = Some(Arc::new(PlatformVMAPI::new(avalanche.core.clone())));
+        }
+        avalanche
+    }
+    /// Creates a client whose network parameters are read from the node: network ID,
+    /// X, P and C blockchain IDs, AVAX asset ID, fees and EVM chain ID.
+    /// Any parameter also given by the caller must match the node, otherwise
+    /// [`AvalancheError::NetworkMismatch`] lists every difference.
+    pub async fn connect(
+        host: &'static str,
+        port: u32,
+        protocol: Option<&'static str>,
+        network_id: Option<u16>,
+        x_chain_id: Option<&'static str>,
+        c_chain_id: Option<&'static str>,
+        hrp: Option<&'static str>,
+    ) -> Result<Avalanche, AvalancheError> {
+        let avalanche = Avalanche::from_core(Arc::new(HttpCore::new(host, port, protocol)?), false);
+        let info = avalanche.info()?;
+        let (node_network_id, node_x_chain_id, node_p_chain_id, node_c_chain_id, tx_fee, evm_chain_id, avax_asset) = tokio::try_join!(
+            info.get_network_id(),
+            info.get_blockchain_id(X_CHAIN_ALIAS),
+            info.get_blockchain_id(P_CHAIN_ALIAS),
+            info.get_blockchain_id(C_CHAIN_ALIAS),
+            info.get_tx_fee(),
+            avalanche.evm()?.get_chain_id(),
+            avalanche.x()?.get_asset_description(PRIMARY_ASSET_ALIAS)
+        )?;
+        let node_network_id = u16::try_from(node_network_id).map_err(|_| AvalancheError::InvalidValue {
+            value: node_network_id.to_string(),
+            reason: String::from("network ID out of range"),
+        })?;
+        let mut mismatches = Vec::new();
+        let mut check = |parameter: &str, expected: Option<String>, actual: &str| {
+            if let Some(expected) = expected {
+                if expected != actual {
+                    mismatches.push(format!("{}: expected {}, node reported {}", parameter, expected, actual));
+                }
             }
-            None => avalanche.network_id = DEFAULT_NETWORK_ID,
         };
-        match hrp {
-            Some(hrp_resolved) => {
-                avalanche.hrp = hrp_resolved;
-            }
-            None => {
-                avalanche.hrp = get_preferred_hrp(Some(avalanche.network_id));
+        check("network_id", network_id.map(|id| id.to_string()), &node_network_id.to_string());
+        check("x_chain_id", x_chain_id.map(String::from), &node_x_chain_id);
+        check("c_chain_id", c_chain_id.map(String::from), &node_c_chain_id);
+        let mut network = {
+            let registry = NetworkRegistry::global().read().unwrap();
+            match registry.get(node_network_id) {
+                Ok(network) => {
+                    check("hrp", hrp.map(String::from), &network.hrp);
+                    NetworkParameters::from_definition(network)
+                }
+                Err(_) => NetworkParameters {
+                    hrp: String::from(hrp.unwrap_or(FALLBACK_HRP)),
+                    ..Default::default()
+                },
             }
         };
-        if !skip_init {
-            avalanche.add_api("info", Box::new(InfoAPI::new(Box::new(avalanche.clone()))));
+        if !mismatches.is_empty() {
+            return Err(AvalancheError::NetworkMismatch { mismatches });
         }
+        network.network_id = node_network_id;
+        network.x_chain_id = Some(node_x_chain_id);
+        network.p_chain_id = Some(node_p_chain_id);
+        network.c_chain_id = Some(node_c_chain_id);
+        network.evm_chain_id = Some(evm_chain_id);
+        network.avax_asset_id = Some(avax_asset.asset_id);
+        network.tx_fee = Some(tx_fee.tx_fee);
+        network.creation_tx_fee = Some(tx_fee.creation_tx_fee);
+        avalanche.core.set_network(network);
         Ok(avalanche)
     }
-    fn set_header(
-        &self,
-        mut request: hyper::Request<hyper::Body>,
-        headers: HashMap<&str, &str>,
-    ) -> hyper::Request<hyper::Body> {
-        for (key, value) in headers {
-            request.headers_mut().insert(
-                HeaderName::from_str(key).unwrap(),
-                HeaderValue::from_str(value).unwrap(),
-            );
-        }
-        for (key, value) in self.headers.iter() {
-            request.headers_mut().insert(
-                HeaderName::from_str(key).unwrap(),
-                HeaderValue::from_str(value).unwrap(),
-            );
-        }
-        if let Some(ref token) = self.auth {
-            request.headers_mut().insert(
-                HeaderName::from_str("Authorization").unwrap(),
-                HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
-            );
+    /// Core shared by the APIs of this client, to be given to custom APIs.
+    pub fn core(&self) -> Arc<dyn AvalancheCore> {
+        self.core.clone()
+    }
+    pub fn get_x_chain_id(&self) -> Option<String> {
+        self.core.get_network().x_chain_id
+    }
+    pub fn get_p_chain_id(&self) -> Option<String> {
+        self.core.get_network().p_chain_id
+    }
+    pub fn get_c_chain_id(&self) -> Option<String> {
+        self.core.get_network().c_chain_id
+    }
+    pub fn info(&self) -> Result<&InfoAPI, AvalancheError> {
+        self.info.as_deref().ok_or(AvalancheError::ApiNotInitialized {
+            api: String::from("info")
+        })
+    }
+    pub fn health(&self) -> Result<&HealthAPI, AvalancheError> {
+        self.health.as_deref().ok_or(AvalancheError::ApiNotInitialized {
+            api: String::from("health")
+        })
+    }
+    pub fn evm(&self) -> Result<&EvmAPI, AvalancheError> {
+        self.evm.as_deref().ok_or(AvalancheError::ApiNotInitialized {
+            api: String::from("evm")
+        })
+    }
+    pub fn x(&self) -> Result<&AvmAPI, AvalancheError> {
+        self.x.as_deref().ok_or(AvalancheError::ApiNotInitialized {
+            api: String::from("x")
+        })
+    }
+    pub fn p(&self) -> Result<&PlatformVMAPI, AvalancheError> {
+        self.p.as_deref().ok_or(AvalancheError::ApiNotInitialized {
+            api: String::from("p")
+        })
+    }
+    fn built_in_apis(&self) -> Vec<&dyn ApiBase> {
+        let mut apis: Vec<&dyn ApiBase> = Vec::new();
+        if let Some(ref info) = self.info { apis.push(info.as_ref()); }
+        if let Some(ref health) = self.health { apis.push(health.as_ref()); }
+        if let Some(ref evm) = self.evm { apis.push(evm.as_ref()); }
+        if let Some(ref x) = self.x { apis.push(x.as_ref()); }
+        if let Some(ref p) = self.p { apis.push(p.as_ref()); }
+        apis
+    }
+    /// Turns response caching of the built-in APIs on or off, disabling it drops the cached responses.
+    pub fn set_cache_enabled(&self, enabled: bool) {
+        for api in self.built_in_apis() {
+            api.get_cache().set_enabled(enabled);
         }
-        request
     }
-    fn request(
+    /// Hits, misses and entries summed over the caches of the built-in APIs.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.built_in_apis()
+            .iter()
+            .fold(CacheStats::default(), |stats, api| stats.merge(api.get_cache().stats()))
+    }
+    /// Waits until the transaction `tx_id` issued on `chain` is accepted, rejected or dropped,
+    /// polling its status with the default [`RetryPolicy`] backoff.
+    pub async fn wait_for_tx(&self, tx_id: &str, chain: TxChain, timeout: Duration) -> Result<FinalTxStatus, AvalancheError> {
+        self.wait_for_tx_with_backoff(tx_id, chain, timeout, &RetryPolicy::default()).await
+    }
+    /// [`Avalanche::wait_for_tx`] waiting `backoff.backoff(attempt)` between two polls.
+    pub async fn wait_for_tx_with_backoff(
         &self,
-        url: &str,
-        method: Method,
-        get_data: HashMap<&str, &str>,
-        post_data: Body,
-        headers: HashMap<&str, &str>,
-    ) -> ResponseFuture {
-        let mut uri = Url::parse(url).unwrap();
-        for (key, value) in get_data.iter() {
-            uri.query_pairs_mut().append_pair(key, value);
-        }
-        let mut request = Request::builder()
-            .method(method)
-            .uri(uri.as_str())
-            .body(post_data)
-            .expect("request builder");
-        request = self.set_header(request, headers);
-        let https = HttpsConnector::new();
-        if self.get_protocol() == "http" {
-            Client::new().request(request)
-        } else {
-            Client::builder().build::<_, hyper::Body>(https).request(request)
+        tx_id: &str,
+        chain: TxChain,
+        timeout: Duration,
+        backoff: &RetryPolicy,
+    ) -> Result<FinalTxStatus, AvalancheError> {
+        let operation = format!("status of {} on the {}", tx_id, chain);
+        wait_for_final_status(operation, timeout, backoff, || self.get_tx_status(tx_id, chain)).await
+    }
+    async fn get_tx_status(&self, tx_id: &str, chain: TxChain) -> Result<TxStatus, AvalancheError> {
+        match chain {
+            TxChain::X => self.x()?.get_tx_status(tx_id).await,
+            TxChain::P => self.p()?.get_tx_status(tx_id).await,
+            TxChain::CAtomic => self.evm()?.get_atomic_tx_status(tx_id).await,
+            TxChain::CEvm => self.evm()?.get_transaction_status(tx_id).await,
         }
     }
-    pub fn info(&self) -> Result<&Box<dyn ApiBase>, AvalancheError> {
-        self.apis.get("info").ok_or(AvalancheError::ApiNotInitialized {
-            api: String::from("info")
-        })
+    /// AVAX of `addresses` on the X, P and C chains, given as X- and P- bech32 addresses, bech32
+    /// addresses without chain alias read on both, or `0x` EVM addresses of the C-Chain.
+    /// Other X-Chain assets are listed when `include_assets` is set.
+    pub async fn get_total_balance(&self, addresses: &[&str], include_assets: bool) -> Result<TotalBalance, AvalancheError> {
+        common::balance::get_total_balance(self, addresses, include_assets).await
+    }
+    /// Moves `amount` of AVAX from `from` to `to`, exporting it with the keys of `signer`, waiting
+    /// for the export, then importing it to its first key, which pays the import fee out of `amount`.
+    /// A failure returns [`AvalancheError::TransferInterrupted`] with the transfer to resume.
+    pub async fn transfer_cross_chain(&self, from: Chain, to: Chain, amount: Avax, signer: &dyn Signer) -> Result<CrossChainTransfer, AvalancheError> {
+        self.resume_cross_chain(CrossChainTransfer::new(from, to, amount)?, signer).await
+    }
+    /// Carries `transfer` on from its last successful step.
+    pub async fn resume_cross_chain(&self, transfer: CrossChainTransfer, signer: &dyn Signer) -> Result<CrossChainTransfer, AvalancheError> {
+        common::cross_chain::run_transfer(self, transfer, signer).await
+    }
+    /// Issues a transaction signed offline to the chain of its VM, returning its ID.
+    pub async fn issue_signed_tx(&self, envelope: &SignedTxEnvelope) -> Result<String, AvalancheError> {
+        common::tx_envelope::issue_signed_tx(self, envelope).await
+    }
+    /// Registers a custom API, replacing any API of the same type.
+    pub fn add_api<T: Any + Send + Sync>(&mut self, api: T) {
+        self.apis.insert(TypeId::of::<T>(), Arc::new(api));
+    }
+    /// Returns the custom API of type `T` registered with [`Avalanche::add_api`].
+    pub fn api<T: Any + Send + Sync>(&self) -> Result<&T, AvalancheError> {
+        self.apis
+            .get(&TypeId::of::<T>())
+            .and_then(|api| api.downcast_ref::<T>())
+            .ok_or(AvalancheError::ApiNotInitialized {
+                api: String::from(std::any::type_name::<T>())
+            })
     }
 }
 
 impl AvalancheCore for Avalanche {
-    fn set_address(
-        &mut self,
-        host: &'static str,
-        port: u32,
-        protocol: Option<&'static str>,
-    ) -> Result<(), AvalancheError> {
-        let re = Regex::new(r"[&#,@+()$~%':*?<>{}]").unwrap(); //TODO: Add "
-        let host_resolved = &re.replace_all(host, "");
-        let protocol_defined: &'static str = protocol.unwrap_or("http");
-        let protocols: Vec<&str> = vec!["http", "https"];
-        if !protocols.contains(&protocol_defined) {
-            return Err(AvalancheError::BadProtocol);
-        }
-        self.host = host;
-        self.port = port;
-        self.protocol = protocol_defined;
-        self.url = format!("{}://{}:{}", &protocol_defined, &host_resolved, &port);
-        Ok(())
-    }
     fn get_protocol(&self) -> &str {
-        self.protocol
+        self.core.get_protocol()
     }
     fn get_host(&self) -> &str {
-        self.host
+        self.core.get_host()
     }
     fn get_ip(&self) -> &str {
-        self.ip
+        self.core.get_ip()
     }
     fn get_port(&self) -> u32 {
-        self.port
+        self.core.get_port()
     }
     fn get_url(&self) -> &str {
-        &self.url
+        self.core.get_url()
+    }
+    fn get_headers(&self) -> HashMap<String, String> {
+        self.core.get_headers()
     }
-    fn get_headers(&self) -> &HashMap<String, String> {
-        &self.headers
+    fn get_network(&self) -> NetworkParameters {
+        self.core.get_network()
+    }
+    fn set_network(&self, network: NetworkParameters) {
+        self.core.set_network(network)
     }
     fn get_network_id(&self) -> u16 {
-        self.network_id
+        self.core.get_network_id()
+    }
+    fn set_network_id(&self, network_id: u16) -> Result<(), AvalancheError> {
+        self.core.set_network_id(network_id)
+    }
+    fn get_hrp(&self) -> String {
+        self.core.get_hrp()
+    }
+    fn set_hrp(&self, hrp: &str) {
+        self.core.set_hrp(hrp)
     }
-    fn set_network_id(&mut self, network_id: u16) {
-        self.network_id = network_id;
-        self.hrp = get_preferred_hrp(Some(network_id));
+    fn set_header(&self, key: &str, value: &str) {
+        self.core.set_header(key, value)
     }
-    fn get_hrp(&self) -> &str {
-        self.hrp
+    fn remove_header(&self, key: &str) {
+        self.core.remove_header(key)
     }
-    fn set_hrp(&mut self, hrp: &'static str) {
-        self.hrp = hrp;
+    fn remove_all_headers(&self) {
+        self.core.remove_all_headers()
     }
-    fn set_header(&mut self, key: &str, value: &str) {
-        self.headers.insert(String::from(key), String::from(value));
+    fn set_auth_token(&self, token: &str) {
+        self.core.set_auth_token(token)
     }
-    fn remove_header(&mut self, key: &str) {
-        self.headers.remove(key);
+    fn next_request_id(&self) -> u64 {
+        self.core.next_request_id()
     }
-    fn remove_all_headers(&mut self) {
-        self.headers.clear();
+    fn get_request_options(&self) -> RequestOptions {
+        self.core.get_request_options()
     }
-    fn set_auth_token(&mut self, token: &str) {
-        self.auth = Some(String::from(token));
+    fn set_request_options(&self, options: RequestOptions) {
+        self.core.set_request_options(options)
+    }
+    fn get_rate_limiter(&self) -> &RateLimiter {
+        self.core.get_rate_limiter()
     }
     fn get(
         &self,
@@ -235,7 +371,7 @@ impl AvalancheCore for Avalanche {
         get_data: HashMap<&str, &str>,
         headers: HashMap<&str, &str>,
     ) -> ResponseFuture {
-        self.request(url, Method::GET, get_data, Body::empty(), headers)
+        self.core.get(url, get_data, headers)
     }
     fn delete(
         &self,
@@ -243,33 +379,32 @@ impl AvalancheCore for Avalanche {
         get_data: HashMap<&str, &str>,
         headers: HashMap<&str, &str>,
     ) -> ResponseFuture {
-        self.request(url, Method::DELETE, get_data, Body::empty(), headers)
+        self.core.delete(url, get_data, headers)
     }
     fn put(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture {
-        self.request(url, Method::PUT, HashMap::new(), post_data, headers)
+        self.core.put(url, post_data, headers)
     }
     fn post(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture {
-        self.request(url, Method::POST, HashMap::new(), post_data, headers)
+        self.core.post(url, post_data, headers)
     }
     fn patch(&self, url: &str, post_data: Body, headers: HashMap<&str, &str>) -> ResponseFuture {
-        self.request(url, Method::PATCH, HashMap::new(), post_data, headers)
-    }
-    fn add_api(&mut self, api_name: &'static str, api: Box<dyn ApiBase>) {
-        self.apis.insert(api_name, api);
+        self.core.patch(url, post_data, headers)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::amount::Avax;
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn it_works() {
         let avalanche: Result<Avalanche, AvalancheError> =
             Avalanche::new("example.com", 8000, Some("http"), None, None, None, None, false);
         match avalanche {
             Ok(avalanche) => {
-                assert_eq!(avalanche.url, "http://example.com:8000");
+                assert_eq!(avalanche.get_url(), "http://example.com:8000");
             }
             Err(_) => {
                 assert!(false);
@@ -278,12 +413,13 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn bad_character_in_host() {
         let avalanche: Result<Avalanche, AvalancheError> =
             Avalanche::new("e&&xample.com", 8000, Some("http"), None, None, None, None, false);
         match avalanche {
             Ok(avalanche) => {
-                assert_eq!(avalanche.url, "http://example.com:8000");
+                assert_eq!(avalanche.get_url(), "http://example.com:8000");
             }
             Err(_) => {
                 assert!(false);
@@ -292,6 +428,188 @@ mod tests {
     }
 
     #[test]
+    fn network_parameters_come_from_the_registry() {
+        let avalanche = Avalanche::new("example.com", 8000, Some("http"), Some(5), None, None, None, true).unwrap();
+        assert_eq!(avalanche.get_hrp(), "fuji");
+        assert_eq!(avalanche.get_x_chain_id().as_deref(), Some("2JVSBoinj9C2J33VntvzYtVJNZdN2NKiwwKjcumHUWEb5DbBrm"));
+        assert_eq!(avalanche.get_c_chain_id().as_deref(), Some("yH8D7ThNJkxmtkuv2jgBa4P1Rn3Qpr4pPr7QYNfcdoS6k6HWp"));
+    }
+
+    #[test]
+    fn unknown_network() {
+        let avalanche = Avalanche::new("example.com", 8000, Some("http"), Some(4242), None, None, None, true);
+        assert_eq!(avalanche.unwrap_err(), AvalancheError::UnknownNetwork { network: String::from("4242") });
+        let avalanche = Avalanche::new("example.com", 8000, Some("http"), Some(4242), Some("xchain"), None, Some("dev"), true).unwrap();
+        assert_eq!(avalanche.get_hrp(), "dev");
+        assert_eq!(avalanche.get_x_chain_id().as_deref(), Some("xchain"));
+        let avalanche = Avalanche::new("example.com", 8000, Some("http"), None, None, None, None, true).unwrap();
+        assert!(avalanche.set_network_id(4242).is_err());
+        assert_eq!(avalanche.get_network_id(), 1);
+    }
+
+    fn mock_node(network_id: &'static str) -> impl Fn(&str, &serde_json::Value) -> serde_json::Value {
+        move |method, params| match method {
+            "info.getNetworkID" => serde_json::json!({"networkID": network_id}),
+            "info.getBlockchainID" => match params["alias"].as_str().unwrap() {
+                "X" => serde_json::json!({"blockchainID": "2JVSBoinj9C2J33VntvzYtVJNZdN2NKiwwKjcumHUWEb5DbBrm"}),
+                "P" => serde_json::json!({"blockchainID": "11111111111111111111111111111111LpoYY"}),
+                _ => serde_json::json!({"blockchainID": "yH8D7ThNJkxmtkuv2jgBa4P1Rn3Qpr4pPr7QYNfcdoS6k6HWp"}),
+            },
+            "info.getTxFee" => serde_json::json!({"txFee": "1000000", "creationTxFee": "10000000"}),
+            "eth_chainId" => serde_json::json!("0xa869"),
+            "avm.getAssetDescription" => serde_json::json!({
+                "assetID": "U8iRqJoiJm8xZHAacmvYyZVwqQx6uDNtQeP3CQ6fcgQk3JqnK",
+                "name": "Avalanche",
+                "symbol": "AVAX",
+                "denomination": "9"
+            }),
+            _ => panic!("unexpected method {}", method),
+        }
+    }
+
+    #[tokio::test]
+    async fn connect_discovers_network_parameters() {
+        let port = test_utils::mock_json_rpc_server(mock_node("5")).await;
+        let avalanche = Avalanche::connect("127.0.0.1", port as u32, Some("http"), None, None, None, None).await.unwrap();
+        assert_eq!(avalanche.get_network_id(), 5);
+        assert_eq!(avalanche.get_hrp(), "fuji");
+        let network = avalanche.get_network();
+        assert_eq!(network.x_chain_id.as_deref(), Some("2JVSBoinj9C2J33VntvzYtVJNZdN2NKiwwKjcumHUWEb5DbBrm"));
+        assert_eq!(network.p_chain_id.as_deref(), Some("11111111111111111111111111111111LpoYY"));
+        assert_eq!(network.evm_chain_id, Some(43113));
+        assert_eq!(network.avax_asset_id.as_deref(), Some("U8iRqJoiJm8xZHAacmvYyZVwqQx6uDNtQeP3CQ6fcgQk3JqnK"));
+        assert_eq!(network.tx_fee, Some(Avax::from_navax(1000000)));
+        assert!(avalanche.info().is_ok());
+    }
+
+    #[tokio::test]
+    async fn connect_to_unregistered_network() {
+        let port = test_utils::mock_json_rpc_server(mock_node("1337")).await;
+        let avalanche = Avalanche::connect("127.0.0.1", port as u32, Some("http"), None, None, None, None).await.unwrap();
+        assert_eq!(avalanche.get_network_id(), 1337);
+        assert_eq!(avalanche.get_hrp(), "custom");
+        assert_eq!(avalanche.get_network().creation_tx_fee, Some(Avax::from_navax(10000000)));
+    }
+
+    #[tokio::test]
+    async fn connect_flags_mismatches() {
+        let port = test_utils::mock_json_rpc_server(mock_node("5")).await;
+        let avalanche = Avalanche::connect("127.0.0.1", port as u32, Some("http"), Some(1), None, None, Some("fuji")).await;
+        assert_eq!(avalanche.unwrap_err(), AvalancheError::NetworkMismatch {
+            mismatches: vec![String::from("network_id: expected 1, node reported 5")]
+        });
+        let avalanche = Avalanche::connect("127.0.0.1", port as u32, Some("http"), None, Some("other"), None, Some("avax")).await;
+        assert_eq!(avalanche.unwrap_err(), AvalancheError::NetworkMismatch {
+            mismatches: vec![
+                String::from("x_chain_id: expected other, node reported 2JVSBoinj9C2J33VntvzYtVJNZdN2NKiwwKjcumHUWEb5DbBrm"),
+                String::from("hrp: expected avax, node reported fuji")
+            ]
+        });
+    }
+
+    #[test]
+    fn apis_share_the_client_core() {
+        let avalanche = Avalanche::new("example.com", 8000, Some("http"), None, None, None, None, false).unwrap();
+        let cloned = avalanche.clone();
+        avalanche.set_header("X-Test", "1");
+        avalanche.set_network_id(5).unwrap();
+        assert_eq!(cloned.info().unwrap().get_core().get_headers().get("X-Test").map(String::as_str), Some("1"));
+        assert_eq!(cloned.p().unwrap().get_core().get_hrp(), "fuji");
+        assert_eq!(cloned.x().unwrap().get_api_base_url(), "/ext/bc/X");
+        assert_eq!(cloned.health().unwrap().get_api_base_url(), "/ext/health");
+        assert_eq!(cloned.evm().unwrap().get_api_base_url(), "/ext/bc/C/avax");
+    }
+
+    #[test]
+    fn skip_init_does_not_create_apis() {
+        let avalanche = Avalanche::new("example.com", 8000, Some("http"), None, None, None, None, true).unwrap();
+        assert_eq!(avalanche.info().err(), Some(AvalancheError::ApiNotInitialized { api: String::from("info") }));
+        assert!(avalanche.p().is_err());
+    }
+
+    struct CustomAPI {
+        core: Arc<dyn AvalancheCore>
+    }
+
+    #[test]
+    fn custom_apis_are_retrieved_by_type() {
+        let mut avalanche = Avalanche::new("example.com", 8000, Some("http"), None, None, None, None, false).unwrap();
+        assert!(avalanche.api::<CustomAPI>().is_err());
+        avalanche.add_api(CustomAPI { core: avalanche.core() });
+        let cloned = avalanche.clone();
+        assert_eq!(cloned.api::<CustomAPI>().unwrap().core.get_url(), "http://example.com:8000");
+    }
+
+    #[tokio::test]
+    async fn cached_responses_are_served_without_a_call() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let port = test_utils::mock_json_rpc_server(move |method, params| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match method {
+                "info.getBlockchainID" => serde_json::json!({"blockchainID": params["alias"]}),
+                _ => serde_json::json!({"nodeID": "NodeID-1"}),
+            }
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let info = avalanche.info().unwrap();
+        info.get_blockchain_id("X").await.unwrap();
+        info.get_blockchain_id("X").await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(avalanche.cache_stats(), CacheStats::default());
+
+        avalanche.set_cache_enabled(true);
+        assert_eq!(info.get_blockchain_id("X").await.unwrap(), "X");
+        assert_eq!(info.get_blockchain_id("X").await.unwrap(), "X");
+        assert_eq!(info.get_blockchain_id("P").await.unwrap(), "P");
+        info.get_node_id().await.unwrap();
+        info.get_node_id().await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 6);
+        assert_eq!(avalanche.cache_stats(), CacheStats { hits: 1, misses: 2, entries: 2 });
+    }
+
+    #[tokio::test]
+    async fn wait_for_tx_polls_until_a_final_status() {
+        let polls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = polls.clone();
+        let port = test_utils::mock_json_rpc_server(move |method, _| {
+            let poll = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match method {
+                "avm.getTxStatus" if poll < 2 => serde_json::json!({"status": "Processing"}),
+                "avm.getTxStatus" => serde_json::json!({"status": "Accepted"}),
+                "platform.getTxStatus" => serde_json::json!({"status": "Dropped", "reason": "failed verification"}),
+                "avax.getAtomicTxStatus" => serde_json::json!({"status": "Processing"}),
+                "eth_getTransactionReceipt" => serde_json::json!({
+                    "transactionHash": "0x01", "blockHash": "0x02", "blockNumber": "0x3",
+                    "from": "0x04", "gasUsed": "0x5208", "status": "0x0", "logs": []
+                }),
+                _ => serde_json::Value::Null,
+            }
+        }).await;
+        let avalanche = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        let backoff = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+        assert_eq!(avalanche.wait_for_tx_with_backoff("tx", TxChain::X, timeout, &backoff).await, Ok(FinalTxStatus::Accepted));
+        assert_eq!(polls.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(
+            avalanche.wait_for_tx_with_backoff("tx", TxChain::P, timeout, &backoff).await,
+            Ok(FinalTxStatus::Dropped { reason: Some(String::from("failed verification")) })
+        );
+        assert_eq!(
+            avalanche.wait_for_tx_with_backoff("0x01", TxChain::CEvm, timeout, &backoff).await,
+            Ok(FinalTxStatus::Rejected { reason: Some(String::from("execution reverted")) })
+        );
+        assert_eq!(
+            avalanche.wait_for_tx_with_backoff("tx", TxChain::CAtomic, Duration::from_millis(50), &backoff).await,
+            Err(AvalancheError::Timeout { operation: String::from("status of tx on the C-Chain (atomic)") })
+        );
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn bad_protocol() {
         let avalanche: Result<Avalanche, AvalancheError> =
             Avalanche::new("example.com", 8000, Some("test"), None, None, None, None, false);
@@ -0,0 +1,440 @@
+use crate::avalanche_core::AvalancheCore;
+use crate::common::retry::{is_transient, is_unsent, Idempotency};
+use crate::errors::AvalancheError;
+use crate::Avalanche;
+use futures::future::join_all;
+use rand::Rng;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Weight of the last sample in the moving average of the latency of a node.
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// How [`MultiNodeClient`] picks the node of a call among the healthy ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoutingStrategy {
+    RoundRobin,
+    /// Random pick weighted by the inverse of the average latency of each node.
+    LatencyWeighted,
+}
+
+/// Probe deciding whether a node is healthy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthCheck {
+    /// `info.isBootstrapped` on the given chain alias.
+    IsBootstrapped(&'static str),
+    /// `health.health` reporting the node healthy.
+    Health,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub latency: Option<Duration>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<AvalancheError>,
+}
+
+struct Node {
+    client: Avalanche,
+    status: RwLock<NodeStatus>,
+}
+
+impl Node {
+    fn record_success(&self, latency: Duration) {
+        let mut status = self.status.write().unwrap();
+        status.healthy = true;
+        status.consecutive_failures = 0;
+        status.latency = Some(match status.latency {
+            Some(average) => average.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING),
+            None => latency,
+        });
+    }
+    fn record_failure(&self, error: AvalancheError) {
+        let mut status = self.status.write().unwrap();
+        status.healthy = false;
+        status.consecutive_failures += 1;
+        status.last_error = Some(error);
+    }
+}
+
+/// Client over several nodes of the same network, routing every call to a healthy node
+/// and failing over to the next one on transport errors.
+pub struct MultiNodeClient {
+    nodes: Vec<Node>,
+    strategy: RoutingStrategy,
+    health_check: HealthCheck,
+    next: AtomicUsize,
+    sticky: RwLock<HashMap<String, usize>>,
+}
+
+impl MultiNodeClient {
+    pub fn new(clients: Vec<Avalanche>, strategy: RoutingStrategy) -> Result<MultiNodeClient, AvalancheError> {
+        if clients.is_empty() {
+            return Err(AvalancheError::InvalidValue {
+                value: String::from("[]"),
+                reason: String::from("a multi-node client needs at least one node"),
+            });
+        }
+        let nodes = clients
+            .into_iter()
+            .map(|client| Node {
+                status: RwLock::new(NodeStatus {
+                    url: String::from(client.get_url()),
+                    healthy: true,
+                    ..Default::default()
+                }),
+                client,
+            })
+            .collect();
+        Ok(MultiNodeClient {
+            nodes,
+            strategy,
+            health_check: HealthCheck::IsBootstrapped("X"),
+            next: AtomicUsize::new(0),
+            sticky: RwLock::new(HashMap::new()),
+        })
+    }
+    pub fn with_health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = health_check;
+        self
+    }
+    pub fn clients(&self) -> Vec<&Avalanche> {
+        self.nodes.iter().map(|node| &node.client).collect()
+    }
+    pub fn statuses(&self) -> Vec<NodeStatus> {
+        self.nodes.iter().map(|node| node.status.read().unwrap().clone()).collect()
+    }
+    /// Probes every node and updates its health and latency.
+    pub async fn check_health(&self) {
+        for node in &self.nodes {
+            let start = Instant::now();
+            let result = match self.health_check {
+                HealthCheck::IsBootstrapped(chain) => match node.client.info() {
+                    Ok(info) => info.is_bootstrapped(chain).await,
+                    Err(error) => Err(error),
+                },
+                HealthCheck::Health => match node.client.health() {
+                    Ok(health) => health.health("").await.map(|response| response.healthy),
+                    Err(error) => Err(error),
+                },
+            };
+            match result {
+                Ok(true) => node.record_success(start.elapsed()),
+                Ok(false) => node.record_failure(AvalancheError::InvalidValue {
+                    value: String::from(node.client.get_url()),
+                    reason: String::from("node is not healthy"),
+                }),
+                Err(error) => node.record_failure(error),
+            }
+        }
+    }
+    /// Runs [`MultiNodeClient::check_health`] every `interval` until the handle is aborted.
+    pub fn spawn_health_checks(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let client = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                client.check_health().await;
+            }
+        })
+    }
+    /// Order in which the nodes are tried for the next call: the routed node first, then the
+    /// other healthy nodes, then the unhealthy ones as a last resort.
+    fn candidates(&self) -> Vec<usize> {
+        let statuses = self.statuses();
+        let (mut healthy, unhealthy): (Vec<usize>, Vec<usize>) =
+            (0..self.nodes.len()).partition(|&i| statuses[i].healthy);
+        if !healthy.is_empty() {
+            let first = match self.strategy {
+                RoutingStrategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % healthy.len(),
+                RoutingStrategy::LatencyWeighted => weighted_pick(&healthy, &statuses),
+            };
+            healthy.rotate_left(first);
+        }
+        healthy.extend(unhealthy);
+        healthy
+    }
+    /// Runs `call` on the routed node, failing over to the next candidate on transport errors.
+    /// A [`Idempotency::NonIdempotent`] call, such as `Idempotency::of("avm.send")`, fails over
+    /// only when it could not reach the node: once sent, a timeout does not tell whether the
+    /// node executed it.
+    pub async fn execute<F, Fut, T>(&self, idempotency: Idempotency, call: F) -> Result<T, AvalancheError>
+    where
+        F: Fn(Avalanche) -> Fut,
+        Fut: Future<Output = Result<T, AvalancheError>>,
+    {
+        self.execute_on(self.candidates(), idempotency, call).await.map(|(_, result)| result)
+    }
+    /// Runs `call` on the node pinned to `key`, e.g. the sender of nonce-sensitive writes, so that
+    /// its transactions reach the same mempool in order. The key is pinned to another node
+    /// only when its node fails.
+    pub async fn execute_sticky<F, Fut, T>(&self, key: &str, idempotency: Idempotency, call: F) -> Result<T, AvalancheError>
+    where
+        F: Fn(Avalanche) -> Fut,
+        Fut: Future<Output = Result<T, AvalancheError>>,
+    {
+        let mut candidates = self.candidates();
+        if let Some(&pinned) = self.sticky.read().unwrap().get(key) {
+            candidates.retain(|&i| i != pinned);
+            candidates.insert(0, pinned);
+        }
+        let (node, result) = self.execute_on(candidates, idempotency, call).await?;
+        self.sticky.write().unwrap().insert(String::from(key), node);
+        Ok(result)
+    }
+    async fn execute_on<F, Fut, T>(&self, candidates: Vec<usize>, idempotency: Idempotency, call: F) -> Result<(usize, T), AvalancheError>
+    where
+        F: Fn(Avalanche) -> Fut,
+        Fut: Future<Output = Result<T, AvalancheError>>,
+    {
+        let mut errors = Vec::new();
+        for i in candidates {
+            let node = &self.nodes[i];
+            let start = Instant::now();
+            match call(node.client.clone()).await {
+                Ok(result) => {
+                    node.record_success(start.elapsed());
+                    return Ok((i, result));
+                }
+                Err(error) if is_transient(&error) && (idempotency.is_retryable() || is_unsent(&error)) => {
+                    errors.push(format!("{}: {}", node.client.get_url(), error));
+                    node.record_failure(error);
+                }
+                Err(error) => {
+                    if is_transient(&error) {
+                        node.record_failure(error.clone());
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Err(AvalancheError::NoNodeAvailable { errors })
+    }
+    /// Runs `call` on every node at once and returns the answer given by at least `quorum`
+    /// of them, answers being compared by their JSON serialization.
+    pub async fn execute_quorum<F, Fut, T>(&self, quorum: usize, call: F) -> Result<T, AvalancheError>
+    where
+        F: Fn(Avalanche) -> Fut,
+        Fut: Future<Output = Result<T, AvalancheError>>,
+        T: Serialize,
+    {
+        if quorum == 0 || quorum > self.nodes.len() {
+            return Err(AvalancheError::InvalidValue {
+                value: quorum.to_string(),
+                reason: format!("the quorum must be between 1 and the {} nodes", self.nodes.len()),
+            });
+        }
+        let calls = self.nodes.iter().map(|node| {
+            let call = call(node.client.clone());
+            async move {
+                let start = Instant::now();
+                let result = call.await;
+                match &result {
+                    Ok(_) => node.record_success(start.elapsed()),
+                    Err(error) if is_transient(error) => node.record_failure(error.clone()),
+                    Err(_) => {}
+                }
+                result
+            }
+        });
+        let results = join_all(calls).await;
+        let answers: Vec<Result<Value, String>> = results
+            .iter()
+            .map(|result| match result {
+                Ok(value) => serde_json::to_value(value).map_err(|error| error.to_string()),
+                Err(error) => Err(error.to_string()),
+            })
+            .collect();
+        let mut counts: Vec<(&Value, usize)> = Vec::new();
+        for answer in answers.iter().flatten() {
+            match counts.iter_mut().find(|(value, _)| *value == answer) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((answer, 1)),
+            }
+        }
+        if let Some((agreed, _)) = counts.iter().find(|(_, count)| *count >= quorum) {
+            let index = answers.iter().position(|answer| answer.as_ref().ok() == Some(*agreed)).unwrap();
+            return Ok(results.into_iter().nth(index).unwrap().ok().unwrap());
+        }
+        Err(AvalancheError::QuorumNotReached {
+            quorum,
+            answers: self
+                .nodes
+                .iter()
+                .zip(&answers)
+                .map(|(node, answer)| match answer {
+                    Ok(value) => format!("{}: {}", node.client.get_url(), value),
+                    Err(error) => format!("{}: error {}", node.client.get_url(), error),
+                })
+                .collect(),
+        })
+    }
+}
+
+fn weighted_pick(candidates: &[usize], statuses: &[NodeStatus]) -> usize {
+    let known: Vec<f64> = candidates
+        .iter()
+        .filter_map(|&i| statuses[i].latency)
+        .map(|latency| latency.as_secs_f64().max(1e-6))
+        .collect();
+    // Nodes without measure yet get the average latency so that they are tried
+    let default = match known.len() {
+        0 => 1.0,
+        n => known.iter().sum::<f64>() / n as f64,
+    };
+    let weights: Vec<f64> = candidates
+        .iter()
+        .map(|&i| 1.0 / statuses[i].latency.map_or(default, |latency| latency.as_secs_f64().max(1e-6)))
+        .collect();
+    let mut pick = rand::thread_rng().gen_range(0.0..weights.iter().sum::<f64>());
+    for (position, weight) in weights.iter().enumerate() {
+        if pick < *weight {
+            return position;
+        }
+        pick -= weight;
+    }
+    weights.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::json_rpc_api::JsonRpcApi;
+    use crate::common::retry::{RequestOptions, RetryPolicy};
+    use crate::test_utils::{self, MockNode};
+    use crate::utils::amount::Avax;
+    use serde_json::json;
+
+    async fn node(bootstrapped: bool) -> MockNode {
+        test_utils::mock_json_rpc_node(move |method, _| match method {
+            "info.isBootstrapped" => Ok(json!({"isBootstrapped": bootstrapped})),
+            _ => Ok(json!({"nodeID": "NodeID-1"})),
+        })
+        .await
+    }
+
+    fn client(port: u16) -> Avalanche {
+        let client = Avalanche::new("127.0.0.1", port as u32, Some("http"), None, None, None, None, false).unwrap();
+        client.set_request_options(RequestOptions::default().with_retry(RetryPolicy::none()));
+        client
+    }
+
+    /// Port on which nothing listens, connections being refused.
+    fn dead_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    async fn node_id(client: Avalanche) -> Result<String, AvalancheError> {
+        client.info()?.get_node_id().await
+    }
+
+    #[tokio::test]
+    async fn round_robin_skips_unhealthy_nodes() {
+        let (a, b, c) = (node(true).await, node(true).await, node(false).await);
+        let multi = MultiNodeClient::new(vec![client(a.port), client(b.port), client(c.port)], RoutingStrategy::RoundRobin).unwrap();
+        multi.check_health().await;
+        assert_eq!(multi.statuses().iter().map(|status| status.healthy).collect::<Vec<_>>(), vec![true, true, false]);
+        for _ in 0..4 {
+            multi.execute(Idempotency::ReadOnly, node_id).await.unwrap();
+        }
+        assert_eq!((a.request_count(), b.request_count(), c.request_count()), (3, 3, 1));
+    }
+
+    #[tokio::test]
+    async fn transport_errors_fail_over() {
+        let b = node(true).await;
+        let multi = MultiNodeClient::new(vec![client(dead_port()), client(b.port)], RoutingStrategy::RoundRobin).unwrap();
+        for _ in 0..3 {
+            assert_eq!(multi.execute(Idempotency::ReadOnly, node_id).await.unwrap(), "NodeID-1");
+        }
+        let statuses = multi.statuses();
+        assert!(!statuses[0].healthy);
+        assert_eq!(statuses[0].consecutive_failures, 1);
+        assert!(statuses[1].healthy && statuses[1].latency.is_some());
+
+        let multi = MultiNodeClient::new(vec![client(dead_port())], RoutingStrategy::RoundRobin).unwrap();
+        assert!(matches!(multi.execute(Idempotency::ReadOnly, node_id).await, Err(AvalancheError::NoNodeAvailable { errors }) if errors.len() == 1));
+    }
+
+    // The slow node blocks a worker thread of the runtime while the client times out on another
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sent_writes_do_not_fail_over() {
+        let slow = test_utils::mock_json_rpc_node(|_, _| {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok(json!({"txID": "tx"}))
+        })
+        .await;
+        let b = node(true).await;
+        let slow_client = client(slow.port);
+        slow_client.set_request_options(
+            RequestOptions::default().with_retry(RetryPolicy::none()).with_timeout(Some(Duration::from_millis(50))),
+        );
+        let send = |client: Avalanche| async move {
+            let value: Value = client.info()?.call_method("avm.send", json!({}), None, None).await?;
+            Ok(value)
+        };
+        let multi = MultiNodeClient::new(vec![slow_client, client(b.port)], RoutingStrategy::RoundRobin).unwrap();
+        let result = multi.execute_sticky("0xsender", Idempotency::of("avm.send"), send).await;
+        assert!(matches!(result, Err(AvalancheError::Timeout { .. })));
+        assert_eq!((slow.request_count(), b.request_count()), (1, 0));
+        assert!(!multi.statuses()[0].healthy);
+
+        // Refused connections never reached the node, the write goes to the next one
+        let multi = MultiNodeClient::new(vec![client(dead_port()), client(b.port)], RoutingStrategy::RoundRobin).unwrap();
+        assert!(multi.execute(Idempotency::of("avm.send"), send).await.is_ok());
+        assert_eq!(b.request_count(), 1);
+    }
+
+    #[test]
+    fn faster_nodes_are_picked_more_often() {
+        let statuses = vec![
+            NodeStatus { latency: Some(Duration::from_millis(1)), ..Default::default() },
+            NodeStatus { latency: Some(Duration::from_secs(1)), ..Default::default() },
+        ];
+        let fast = (0..1000).filter(|_| weighted_pick(&[0, 1], &statuses) == 0).count();
+        assert!(fast > 950);
+    }
+
+    #[tokio::test]
+    async fn sticky_keys_stay_on_their_node() {
+        let (a, b) = (node(true).await, node(true).await);
+        let multi = MultiNodeClient::new(vec![client(a.port), client(b.port)], RoutingStrategy::RoundRobin).unwrap();
+        for _ in 0..4 {
+            multi.execute_sticky("0xsender", Idempotency::ReadOnly, node_id).await.unwrap();
+        }
+        assert_eq!((a.request_count(), b.request_count()), (4, 0));
+        multi.execute(Idempotency::ReadOnly, node_id).await.unwrap();
+        multi.execute(Idempotency::ReadOnly, node_id).await.unwrap();
+        multi.execute_sticky("0xsender", Idempotency::ReadOnly, node_id).await.unwrap();
+        assert_eq!((a.request_count(), b.request_count()), (6, 1));
+    }
+
+    #[tokio::test]
+    async fn quorum_reads_need_enough_agreeing_nodes() {
+        let fee = |tx_fee: &'static str| {
+            test_utils::mock_json_rpc_node(move |_, _| Ok(json!({"txFee": tx_fee, "creationTxFee": "10000000"})))
+        };
+        let (a, b, c) = (fee("1000000").await, fee("1000000").await, fee("2000000").await);
+        let multi = MultiNodeClient::new(vec![client(a.port), client(b.port), client(c.port), client(dead_port())], RoutingStrategy::RoundRobin).unwrap();
+        let tx_fee = |client: Avalanche| async move { client.info()?.get_tx_fee().await };
+        assert_eq!(multi.execute_quorum(2, tx_fee).await.unwrap().tx_fee, Avax::from_navax(1000000));
+        let error = multi.execute_quorum(3, tx_fee).await.unwrap_err();
+        let AvalancheError::QuorumNotReached { quorum, answers } = error else {
+            panic!("unexpected error {:?}", error);
+        };
+        assert_eq!(quorum, 3);
+        assert_eq!(answers.len(), 4);
+        assert!(answers[2].ends_with(r#"{"creation_tx_fee":"10000000","tx_fee":"2000000"}"#));
+        assert!(answers[3].contains(": error "));
+        assert!(multi.execute_quorum(5, tx_fee).await.is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Mock node started by [`mock_json_rpc_node`].
+pub struct MockNode {
+    pub port: u16,
+    /// Number of HTTP requests received, a batch counting as one.
+    pub requests: Arc<AtomicUsize>,
+}
+
+impl MockNode {
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+fn answer<F>(handler: &F, call: &Value) -> Value
+where
+    F: Fn(&str, &Value) -> Result<Value, (i64, String)>,
+{
+    let method = call["method"].as_str().unwrap_or_default();
+    match handler(method, &call["params"]) {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": call["id"], "result": result}),
+        Err((code, message)) => json!({"jsonrpc": "2.0", "id": call["id"], "error": {"code": code, "message": message}}),
+    }
+}
+
+/// Starts a server on a random local port answering every JSON request with the
+/// response returned by `respond(request)`.
+pub async fn mock_raw_http_server<F>(respond: F) -> MockNode
+where
+    F: Fn(Value) -> Response<Body> + Send + Sync + 'static,
+{
+    let respond = Arc::new(respond);
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let make_service = make_service_fn(move |_| {
+        let respond = respond.clone();
+        let counter = counter.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let respond = respond.clone();
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                    Ok::<_, Infallible>(respond(serde_json::from_slice(&body).unwrap()))
+                }
+            }))
+        }
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let port = server.local_addr().port();
+    tokio::spawn(server);
+    MockNode { port, requests }
+}
+
+/// Starts a server on a random local port answering every JSON request with
+/// the JSON value returned by `respond(request)`.
+pub async fn mock_http_server<F>(respond: F) -> MockNode
+where
+    F: Fn(Value) -> Value + Send + Sync + 'static,
+{
+    mock_raw_http_server(move |request| Response::new(Body::from(respond(request).to_string()))).await
+}
+
+/// Starts a JSON-RPC server on a random local port answering every call with
+/// `handler(method, params)`, an `Err((code, message))` being sent as a JSON-RPC
+/// error. Batches are answered in reverse order so that clients have to match
+/// the responses by id.
+pub async fn mock_json_rpc_node<F>(handler: F) -> MockNode
+where
+    F: Fn(&str, &Value) -> Result<Value, (i64, String)> + Send + Sync + 'static,
+{
+    mock_http_server(move |call| match call {
+        Value::Array(calls) => Value::Array(calls.iter().rev().map(|call| answer(&handler, call)).collect()),
+        call => answer(&handler, &call),
+    })
+    .await
+}
+
+/// Starts a JSON-RPC server on a random local port answering every call with
+/// the result returned by `handler(method, params)`.
+pub async fn mock_json_rpc_server<F>(handler: F) -> u16
+where
+    F: Fn(&str, &Value) -> Value + Send + Sync + 'static,
+{
+    mock_json_rpc_node(move |method, params| Ok(handler(method, params))).await.port
+}
@@ -0,0 +1,124 @@
+use crate::errors::AvalancheError;
+use crate::utils::constants::PRIVATE_KEY_PREFIX;
+use bech32::{FromBase32, ToBase32, Variant};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Address of a key on the X and P chains, the RIPEMD-160 of the SHA-256 of its compressed public key.
+pub type ShortId = [u8; 20];
+
+const CHECKSUM_LENGTH: usize = 4;
+
+/// Encodes `data` followed by the last 4 bytes of its SHA-256 in base 58.
+pub fn cb58_encode(data: &[u8]) -> String {
+    let mut bytes = data.to_vec();
+    bytes.extend_from_slice(&Sha256::digest(data)[32 - CHECKSUM_LENGTH..]);
+    bs58::encode(bytes).into_string()
+}
+
+pub fn cb58_decode(encoded: &str) -> Result<Vec<u8>, AvalancheError> {
+    let invalid = |reason: &str| AvalancheError::InvalidValue {
+        value: String::from(encoded),
+        reason: String::from(reason),
+    };
+    let mut bytes = bs58::decode(encoded).into_vec().map_err(|e| invalid(&e.to_string()))?;
+    if bytes.len() < CHECKSUM_LENGTH {
+        return Err(invalid("too short for a cb58 checksum"));
+    }
+    let checksum = bytes.split_off(bytes.len() - CHECKSUM_LENGTH);
+    if Sha256::digest(&bytes)[32 - CHECKSUM_LENGTH..] != checksum[..] {
+        return Err(invalid("invalid cb58 checksum"));
+    }
+    Ok(bytes)
+}
+
+/// Formats a private key as `PrivateKey-<cb58>`.
+pub fn format_private_key(private_key: &[u8]) -> String {
+    format!("{}{}", PRIVATE_KEY_PREFIX, cb58_encode(private_key))
+}
+
+/// Parses a `PrivateKey-<cb58>` or a hex (EVM) private key, wiped from memory when dropped.
+pub fn parse_private_key(private_key: &str) -> Result<Zeroizing<Vec<u8>>, AvalancheError> {
+    let bytes = Zeroizing::new(match private_key.strip_prefix(PRIVATE_KEY_PREFIX) {
+        Some(encoded) => cb58_decode(encoded)?,
+        None => hex::decode(private_key.trim_start_matches("0x")).map_err(|e| AvalancheError::InvalidValue {
+            value: String::from("<private key>"),
+            reason: e.to_string(),
+        })?,
+    });
+    if bytes.len() != 32 {
+        return Err(AvalancheError::InvalidValue {
+            value: String::from("<private key>"),
+            reason: String::from("a private key is 32 bytes long"),
+        });
+    }
+    Ok(bytes)
+}
+
+pub fn public_key_to_short_id(compressed_public_key: &[u8]) -> ShortId {
+    let mut short_id = [0u8; 20];
+    short_id.copy_from_slice(&Ripemd160::digest(&Sha256::digest(compressed_public_key)));
+    short_id
+}
+
+/// Formats `short_id` as `<chain>-<bech32>`, e.g. `X-avax1...`.
+pub fn format_address(chain_alias: &str, hrp: &str, short_id: &ShortId) -> Result<String, AvalancheError> {
+    let encoded = bech32::encode(hrp, short_id.to_base32(), Variant::Bech32).map_err(|e| AvalancheError::InvalidValue {
+        value: String::from(hrp),
+        reason: e.to_string(),
+    })?;
+    Ok(format!("{}-{}", chain_alias, encoded))
+}
+
+/// Splits a `<chain>-<bech32>` address, the chain being optional, into its chain alias, HRP and short ID.
+pub fn parse_address(address: &str) -> Result<(Option<String>, String, ShortId), AvalancheError> {
+    let invalid = |reason: String| AvalancheError::InvalidValue {
+        value: String::from(address),
+        reason,
+    };
+    let (chain_alias, encoded) = match address.split_once('-') {
+        Some((chain_alias, encoded)) => (Some(String::from(chain_alias)), encoded),
+        None => (None, address),
+    };
+    let (hrp, data, variant) = bech32::decode(encoded).map_err(|e| invalid(e.to_string()))?;
+    if variant != Variant::Bech32 {
+        return Err(invalid(String::from("addresses are bech32, not bech32m")));
+    }
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|e| invalid(e.to_string()))?;
+    let short_id: ShortId = bytes
+        .try_into()
+        .map_err(|_| invalid(String::from("an address is 20 bytes long")))?;
+    Ok((chain_alias, hrp, short_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::constants::{DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY};
+
+    #[test]
+    fn cb58_round_trips_and_checks_the_checksum() {
+        let private_key = cb58_decode(DEFAULT_LOCAL_GENESIS_PRIVATE_KEY).unwrap();
+        assert_eq!(format!("0x{}", hex::encode(&private_key)), DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY);
+        assert_eq!(cb58_encode(&private_key), DEFAULT_LOCAL_GENESIS_PRIVATE_KEY);
+        assert!(cb58_decode("ewoqjP7PxY4yr3iLTpLisriqt94hdyDFNgchSxGGztUrTXtNM").is_err());
+        assert_eq!(
+            parse_private_key(&format!("{}{}", PRIVATE_KEY_PREFIX, DEFAULT_LOCAL_GENESIS_PRIVATE_KEY)).unwrap(),
+            parse_private_key(DEFAULT_EVM_LOCAL_GENESIS_PRIVATE_KEY).unwrap()
+        );
+    }
+
+    #[test]
+    fn addresses_round_trip() {
+        let address = "X-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u";
+        let (chain_alias, hrp, short_id) = parse_address(address).unwrap();
+        assert_eq!((chain_alias.as_deref(), hrp.as_str()), (Some("X"), "local"));
+        assert_eq!(format_address("X", "local", &short_id).unwrap(), address);
+        assert_eq!(
+            format_address("P", "avax", &short_id).unwrap(),
+            "P-avax18jma8ppw3nhx5r4ap8clazz0dps7rv5ukulre5"
+        );
+        assert!(parse_address("X-local18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96v").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::errors::AvalancheError;
+use num_bigint::BigInt;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of wei in one nAVAX, the C-Chain uses 18 decimals where X and P use 9.
+pub const WEI_PER_NAVAX: u128 = 1_000_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Denomination {
+    Avax,
+    DeciAvax,
+    CentiAvax,
+    MilliAvax,
+    MicroAvax,
+    NanoAvax,
+}
+
+impl Denomination {
+    pub const fn decimals(self) -> u32 {
+        match self {
+            Denomination::Avax => 9,
+            Denomination::DeciAvax => 8,
+            Denomination::CentiAvax => 7,
+            Denomination::MilliAvax => 6,
+            Denomination::MicroAvax => 3,
+            Denomination::NanoAvax => 0,
+        }
+    }
+    pub const fn navax(self) -> u64 {
+        10u64.pow(self.decimals())
+    }
+}
+
+/// An amount of AVAX stored in nAVAX, the unit used by the X and P chains.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Avax(u64);
+
+impl Avax {
+    pub const ZERO: Avax = Avax(0);
+    pub const ONE: Avax = Avax(Denomination::Avax.navax());
+
+    pub const fn from_navax(navax: u64) -> Avax {
+        Avax(navax)
+    }
+    pub const fn navax(self) -> u64 {
+        self.0
+    }
+    pub fn from_denomination(amount: u64, denomination: Denomination) -> Option<Avax> {
+        amount.checked_mul(denomination.navax()).map(Avax)
+    }
+    pub fn checked_add(self, other: Avax) -> Option<Avax> {
+        self.0.checked_add(other.0).map(Avax)
+    }
+    pub fn checked_sub(self, other: Avax) -> Option<Avax> {
+        self.0.checked_sub(other.0).map(Avax)
+    }
+    pub fn checked_mul(self, factor: u64) -> Option<Avax> {
+        self.0.checked_mul(factor).map(Avax)
+    }
+    pub fn checked_div(self, divisor: u64) -> Option<Avax> {
+        self.0.checked_div(divisor).map(Avax)
+    }
+    pub fn saturating_sub(self, other: Avax) -> Avax {
+        Avax(self.0.saturating_sub(other.0))
+    }
+    pub fn to_wei(self) -> Wei {
+        Wei(self.0 as u128 * WEI_PER_NAVAX)
+    }
+    /// Formats the amount as a decimal string of the given denomination, e.g. `1.5` AVAX.
+    pub fn format(self, denomination: Denomination) -> String {
+        format_decimal(self.0 as u128, denomination.decimals())
+    }
+    /// Parses a decimal string expressed in the given denomination.
+    pub fn parse(amount: &str, denomination: Denomination) -> Result<Avax, AvalancheError> {
+        let navax = parse_decimal(amount, denomination.decimals())?;
+        u64::try_from(navax).map(Avax).map_err(|_| AvalancheError::InvalidValue {
+            value: String::from(amount),
+            reason: String::from("amount overflows"),
+        })
+    }
+}
+
+impl fmt::Display for Avax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(Denomination::Avax))
+    }
+}
+
+impl FromStr for Avax {
+    type Err = AvalancheError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Avax::parse(s, Denomination::Avax)
+    }
+}
+
+impl From<Avax> for BigInt {
+    fn from(amount: Avax) -> Self {
+        BigInt::from(amount.0)
+    }
+}
+
+// Nodes return nAVAX amounts as decimal strings
+impl Serialize for Avax {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Avax {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NavaxVisitor;
+
+        impl<'de> Visitor<'de> for NavaxVisitor {
+            type Value = Avax;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an amount of nAVAX as a string or an integer")
+            }
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Avax, E> {
+                Ok(Avax(value))
+            }
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Avax, E> {
+                value.parse::<u64>().map(Avax).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(NavaxVisitor)
+    }
+}
+
+/// An amount of AVAX on the C-Chain, stored in wei (18 decimals).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wei(u128);
+
+impl Wei {
+    pub const ZERO: Wei = Wei(0);
+    pub const ONE_GWEI: Wei = Wei(1_000_000_000);
+    pub const ONE_AVAX: Wei = Wei(Denomination::Avax.navax() as u128 * WEI_PER_NAVAX);
+
+    pub const fn from_wei(wei: u128) -> Wei {
+        Wei(wei)
+    }
+    pub const fn wei(self) -> u128 {
+        self.0
+    }
+    pub fn checked_add(self, other: Wei) -> Option<Wei> {
+        self.0.checked_add(other.0).map(Wei)
+    }
+    pub fn checked_sub(self, other: Wei) -> Option<Wei> {
+        self.0.checked_sub(other.0).map(Wei)
+    }
+    pub fn checked_mul(self, factor: u128) -> Option<Wei> {
+        self.0.checked_mul(factor).map(Wei)
+    }
+    pub fn checked_div(self, divisor: u128) -> Option<Wei> {
+        self.0.checked_div(divisor).map(Wei)
+    }
+    /// Converts to nAVAX, failing if the amount has a non zero part below 1 nAVAX.
+    pub fn to_avax(self) -> Result<Avax, AvalancheError> {
+        if !self.0.is_multiple_of(WEI_PER_NAVAX) {
+            return Err(AvalancheError::InvalidValue {
+                value: self.0.to_string(),
+                reason: String::from("amount is not a whole number of nAVAX"),
+            });
+        }
+        self.to_avax_truncated()
+    }
+    /// Converts to nAVAX, dropping the part below 1 nAVAX.
+    pub fn to_avax_truncated(self) -> Result<Avax, AvalancheError> {
+        u64::try_from(self.0 / WEI_PER_NAVAX).map(Avax).map_err(|_| AvalancheError::InvalidValue {
+            value: self.0.to_string(),
+            reason: String::from("amount overflows"),
+        })
+    }
+    pub fn from_hex(quantity: &str) -> Result<Wei, AvalancheError> {
+        crate::apis::evm::transaction::parse_hex_quantity(quantity).map(Wei)
+    }
+    pub fn to_hex(self) -> String {
+        crate::apis::evm::transaction::format_hex_quantity(self.0)
+    }
+}
+
+impl From<Avax> for Wei {
+    fn from(amount: Avax) -> Self {
+        amount.to_wei()
+    }
+}
+
+impl fmt::Display for Wei {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_decimal(self.0, 18))
+    }
+}
+
+impl FromStr for Wei {
+    type Err = AvalancheError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_decimal(s, 18).map(Wei)
+    }
+}
+
+fn format_decimal(amount: u128, decimals: u32) -> String {
+    let unit = 10u128.pow(decimals);
+    let integer = amount / unit;
+    let fraction = amount % unit;
+    if fraction == 0 {
+        return integer.to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", integer, fraction.trim_end_matches('0'))
+}
+
+fn parse_decimal(amount: &str, decimals: u32) -> Result<u128, AvalancheError> {
+    let invalid = |reason: &str| AvalancheError::InvalidValue {
+        value: String::from(amount),
+        reason: String::from(reason),
+    };
+    let (integer, fraction) = match amount.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (amount, ""),
+    };
+    if integer.is_empty() || !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid("expected a positive decimal number"));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(invalid("too many decimals"));
+    }
+    let padded = format!("{}{:0<width$}", integer, fraction, width = decimals as usize);
+    padded.parse::<u128>().map_err(|_| invalid("amount overflows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denominations() {
+        assert_eq!(Avax::ONE.navax(), 1_000_000_000);
+        assert_eq!(Avax::from_denomination(1, Denomination::MilliAvax).unwrap().navax(), 1_000_000);
+        assert_eq!(Avax::from_denomination(u64::MAX, Denomination::Avax), None);
+    }
+
+    #[test]
+    fn constants_are_expressed_in_navax() {
+        use crate::utils::constants::{AVAX_STAKE_CAP, MILLIAVAX, NANOAVAX, N_1X, ONE_AVAX};
+        assert_eq!(*ONE_AVAX, BigInt::from(1_000_000_000u64));
+        assert_eq!(*NANOAVAX, BigInt::from(1u64));
+        assert_eq!(*MILLIAVAX, BigInt::from(Avax::from_denomination(1, Denomination::MilliAvax).unwrap()));
+        assert_eq!(*AVAX_STAKE_CAP, BigInt::from(3_000_000_000_000_000u64));
+        assert_eq!(N_1X.tx_fee, Some(BigInt::from(1_000_000u64)));
+    }
+
+    #[test]
+    fn parse_and_format_avax() {
+        assert_eq!("1.5".parse::<Avax>().unwrap(), Avax::from_navax(1_500_000_000));
+        assert_eq!("0.000000001".parse::<Avax>().unwrap(), Avax::from_navax(1));
+        assert_eq!(Avax::from_navax(1_500_000_000).to_string(), "1.5");
+        assert_eq!(Avax::from_navax(25_000_000_000).to_string(), "25");
+        assert_eq!(Avax::from_navax(1_000_000).format(Denomination::MilliAvax), "1");
+        assert_eq!(Avax::parse("2", Denomination::MilliAvax).unwrap().navax(), 2_000_000);
+        assert!("0.0000000001".parse::<Avax>().is_err());
+        assert!("-1".parse::<Avax>().is_err());
+        assert!("1.".parse::<Avax>().is_ok());
+        assert!("abc".parse::<Avax>().is_err());
+        assert!("99999999999".parse::<Avax>().is_err());
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(Avax::ONE.checked_add(Avax::ONE), Some(Avax::from_navax(2_000_000_000)));
+        assert_eq!(Avax::ZERO.checked_sub(Avax::ONE), None);
+        assert_eq!(Avax::from_navax(u64::MAX).checked_add(Avax::ONE), None);
+        assert_eq!(Avax::ONE.checked_div(0), None);
+        assert_eq!(Avax::ZERO.saturating_sub(Avax::ONE), Avax::ZERO);
+    }
+
+    #[test]
+    fn wei_conversions() {
+        assert_eq!(Avax::ONE.to_wei(), Wei::ONE_AVAX);
+        assert_eq!(Wei::ONE_AVAX.to_avax().unwrap(), Avax::ONE);
+        assert!(Wei::from_wei(1).to_avax().is_err());
+        assert_eq!(Wei::from_wei(1_999_999_999).to_avax_truncated().unwrap(), Avax::from_navax(1));
+        assert_eq!("1.000000000000000001".parse::<Wei>().unwrap().wei(), 1_000_000_000_000_000_001);
+        assert_eq!(Wei::from_hex("0x5d21dba00").unwrap().to_string(), "0.000000025");
+    }
+
+    #[test]
+    fn serde_uses_navax_strings() {
+        assert_eq!(serde_json::to_string(&Avax::ONE).unwrap(), "\"1000000000\"");
+        assert_eq!(serde_json::from_str::<Avax>("\"1000000\"").unwrap(), Avax::from_navax(1_000_000));
+        assert_eq!(serde_json::from_str::<Avax>("1000000").unwrap(), Avax::from_navax(1_000_000));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::errors::AvalancheError;
+use crate::utils::address::{cb58_decode, cb58_encode};
+use sha2::{Digest, Sha256};
+
+/// Version of the codec prefixing transactions and UTXOs.
+pub const CODEC_VERSION: u16 = 0;
+
+/// ID of a transaction, an asset or a chain, formatted in cb58.
+pub type Id = [u8; 32];
+
+const CHECKSUM_LENGTH: usize = 4;
+
+pub fn format_id(id: &Id) -> String {
+    cb58_encode(id)
+}
+
+pub fn parse_id(id: &str) -> Result<Id, AvalancheError> {
+    cb58_decode(id)?.try_into().map_err(|_| AvalancheError::InvalidValue {
+        value: String::from(id),
+        reason: String::from("an ID is 32 bytes long"),
+    })
+}
+
+/// Encodes `bytes` in the `hex` encoding of the node APIs, `0x` and the bytes followed by the last
+/// 4 bytes of their SHA-256.
+pub fn encode_hex_with_checksum(bytes: &[u8]) -> String {
+    let checksum = &Sha256::digest(bytes)[32 - CHECKSUM_LENGTH..];
+    format!("0x{}{}", hex::encode(bytes), hex::encode(checksum))
+}
+
+pub fn decode_hex_with_checksum(encoded: &str) -> Result<Vec<u8>, AvalancheError> {
+    let invalid = |reason: String| AvalancheError::InvalidValue {
+        value: String::from(encoded),
+        reason,
+    };
+    let mut bytes = hex::decode(encoded.trim_start_matches("0x")).map_err(|e| invalid(e.to_string()))?;
+    if bytes.len() < CHECKSUM_LENGTH {
+        return Err(invalid(String::from("too short for a checksum")));
+    }
+    let checksum = bytes.split_off(bytes.len() - CHECKSUM_LENGTH);
+    if Sha256::digest(&bytes)[32 - CHECKSUM_LENGTH..] != checksum[..] {
+        return Err(invalid(String::from("invalid checksum")));
+    }
+    Ok(bytes)
+}
+
+/// Writes values in the big-endian layout of the Avalanche codec.
+#[derive(Clone, Debug, Default)]
+pub struct Packer {
+    bytes: Vec<u8>,
+}
+
+impl Packer {
+    pub fn new() -> Packer {
+        Packer::default()
+    }
+    pub fn pack_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    pub fn pack_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    pub fn pack_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    /// Writes `bytes` as they are, for fixed length values such as IDs.
+    pub fn pack_fixed(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+    /// Writes `bytes` prefixed with their length.
+    pub fn pack_bytes(&mut self, bytes: &[u8]) {
+        self.pack_u32(bytes.len() as u32);
+        self.pack_fixed(bytes);
+    }
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads values written by a [`Packer`].
+#[derive(Clone, Debug)]
+pub struct Unpacker<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Unpacker<'a> {
+    pub fn new(bytes: &'a [u8]) -> Unpacker<'a> {
+        Unpacker { bytes, offset: 0 }
+    }
+    fn take(&mut self, length: usize) -> Result<&'a [u8], AvalancheError> {
+        let end = self.offset.checked_add(length).filter(|end| *end <= self.bytes.len()).ok_or_else(|| AvalancheError::InvalidValue {
+            value: hex::encode(self.bytes),
+            reason: format!("{} bytes missing at offset {}", length, self.offset),
+        })?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+    /// Reads the codec version prefixing a value, failing on versions this crate does not read.
+    pub fn unpack_version(&mut self) -> Result<(), AvalancheError> {
+        let version = self.unpack_u16()?;
+        if version != CODEC_VERSION {
+            return Err(AvalancheError::InvalidValue {
+                value: version.to_string(),
+                reason: String::from("unknown codec version"),
+            });
+        }
+        Ok(())
+    }
+    pub fn unpack_u16(&mut self) -> Result<u16, AvalancheError> {
+        Ok(u16::from_be_bytes(self.unpack_fixed()?))
+    }
+    pub fn unpack_u32(&mut self) -> Result<u32, AvalancheError> {
+        Ok(u32::from_be_bytes(self.unpack_fixed()?))
+    }
+    pub fn unpack_u64(&mut self) -> Result<u64, AvalancheError> {
+        Ok(u64::from_be_bytes(self.unpack_fixed()?))
+    }
+    pub fn unpack_fixed<const N: usize>(&mut self) -> Result<[u8; N], AvalancheError> {
+        let mut value = [0u8; N];
+        value.copy_from_slice(self.take(N)?);
+        Ok(value)
+    }
+    /// Reads bytes prefixed with their length.
+    pub fn unpack_bytes(&mut self) -> Result<Vec<u8>, AvalancheError> {
+        let length = self.unpack_u32()? as usize;
+        Ok(self.take(length)?.to_vec())
+    }
+    /// Reads the bytes left.
+    pub fn unpack_rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.offset..];
+        self.offset = self.bytes.len();
+        rest
+    }
+    /// Fails if bytes are left, the value read being followed by garbage.
+    pub fn finish(&self) -> Result<(), AvalancheError> {
+        if self.offset != self.bytes.len() {
+            return Err(AvalancheError::InvalidValue {
+                value: hex::encode(self.bytes),
+                reason: format!("{} trailing bytes", self.bytes.len() - self.offset),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_values_unpack_in_order() {
+        let mut packer = Packer::new();
+        packer.pack_u16(CODEC_VERSION);
+        packer.pack_u32(7);
+        packer.pack_u64(1_000_000);
+        packer.pack_bytes(b"memo");
+        let bytes = packer.into_bytes();
+        assert_eq!(hex::encode(&bytes), "00000000000700000000000f4240000000046d656d6f");
+        let mut unpacker = Unpacker::new(&bytes);
+        assert_eq!(unpacker.unpack_u16().unwrap(), CODEC_VERSION);
+        assert_eq!(unpacker.unpack_u32().unwrap(), 7);
+        assert!(unpacker.finish().is_err());
+        assert_eq!(unpacker.unpack_u64().unwrap(), 1_000_000);
+        assert_eq!(unpacker.unpack_bytes().unwrap(), b"memo");
+        assert!(unpacker.finish().is_ok());
+        assert!(unpacker.unpack_u32().is_err());
+    }
+
+    #[test]
+    fn hex_encoding_checks_the_checksum() {
+        let encoded = encode_hex_with_checksum(b"utxo");
+        assert_eq!(decode_hex_with_checksum(&encoded).unwrap(), b"utxo");
+        let mut tampered = encoded.clone();
+        tampered.replace_range(2..4, "00");
+        assert!(decode_hex_with_checksum(&tampered).is_err());
+        let asset_id = "2fombhL7aGPwj3KH4bfrmJwW6PVnMobf9Y2fn9GwxiAAJyFDbe";
+        assert_eq!(format_id(&parse_id(asset_id).unwrap()), asset_id);
+    }
+}
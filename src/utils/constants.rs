@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use lazy_static::lazy_static;
 use std::ops::{Div, Mul};
 use crate::apis::evm::constants::{X, P, C, EnumNetwork, Network};
+use crate::utils::amount::Avax;
+use crate::errors::AvalancheError;
 
 pub const PRIVATE_KEY_PREFIX: &str = "PrivateKey-";
 pub const NODE_ID_PREFIX: &str = "NodeID-";
@@ -85,8 +87,8 @@ pub const MNEMONIC: &str = "output tooth keep tooth bracket fox city sustain blo
 
 
 lazy_static! {
-  pub static ref ONE_AVAX: BigInt = BigInt::parse_bytes("10".as_bytes(), 10).unwrap();
-  
+  pub static ref ONE_AVAX: BigInt = BigInt::from(Avax::ONE);
+
   
   pub static ref DECIAVAX: BigInt = {
     ONE_AVAX.clone().div(BigInt::parse_bytes("10".as_bytes(), 10).unwrap())
@@ -444,8 +446,26 @@ lazy_static! {
   //End Local Network
 }
 
-#[non_exhaustive]
-struct MergeRule;
+/// How a UTXO set is merged with a new one, named as in the Avalanche SDKs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MergeRule {
+  /// UTXOs in both sets.
+  Intersection,
+  /// UTXOs of the current set missing from the new one.
+  DifferenceSelf,
+  /// UTXOs of the new set missing from the current one.
+  DifferenceNew,
+  /// UTXOs in exactly one of the sets.
+  SymDifference,
+  /// UTXOs in either set.
+  Union,
+  /// Union of the sets without the UTXOs of the new one.
+  UnionMinusNew,
+  /// Union of the sets without the UTXOs of the current one.
+  UnionMinusSelf,
+  /// Rejects the merge.
+  Error,
+}
 
 impl MergeRule {
   pub const INTERSECTION: &'static str = "intersection";
@@ -456,6 +476,42 @@ impl MergeRule {
   pub const UNION_MINUS_NEW: &'static str = "unionMinusNew";
   pub const UNION_MINUS_SELF: &'static str = "unionMinusSelf";
   pub const ERROR: &'static str = "ERROR";
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      MergeRule::Intersection => MergeRule::INTERSECTION,
+      MergeRule::DifferenceSelf => MergeRule::DIFFERENCE_SELF,
+      MergeRule::DifferenceNew => MergeRule::DIFFERENCE_NEW,
+      MergeRule::SymDifference => MergeRule::SYM_DIFFERENCE,
+      MergeRule::Union => MergeRule::UNION,
+      MergeRule::UnionMinusNew => MergeRule::UNION_MINUS_NEW,
+      MergeRule::UnionMinusSelf => MergeRule::UNION_MINUS_SELF,
+      MergeRule::Error => MergeRule::ERROR,
+    }
+  }
+}
+
+impl std::str::FromStr for MergeRule {
+  type Err = AvalancheError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    [
+      MergeRule::Intersection,
+      MergeRule::DifferenceSelf,
+      MergeRule::DifferenceNew,
+      MergeRule::SymDifference,
+      MergeRule::Union,
+      MergeRule::UnionMinusNew,
+      MergeRule::UnionMinusSelf,
+      MergeRule::Error,
+    ]
+    .into_iter()
+    .find(|rule| rule.as_str() == s)
+    .ok_or_else(|| AvalancheError::InvalidValue {
+      value: String::from(s),
+      reason: String::from("not a merge rule"),
+    })
+  }
 }
 
 lazy_static! {
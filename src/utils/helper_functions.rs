@@ -9,3 +9,34 @@ pub fn get_preferred_hrp(network_id: Option<u16>) -> &'static str {
         None => FALLBACK_HRP,
     }
 }
+
+/// (De)serializes a `u64` that nodes send as a decimal string, with `#[serde(with = "u64_string")]`.
+pub mod u64_string {
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        struct U64Visitor;
+
+        impl<'de> Visitor<'de> for U64Visitor {
+            type Value = u64;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an unsigned integer as a string or an integer")
+            }
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+                Ok(value)
+            }
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+                value.parse::<u64>().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(U64Visitor)
+    }
+}
@@ -1,3 +1,7 @@
+pub mod address;
+pub mod amount;
+pub mod codec;
 #[allow(dead_code)]
 pub mod constants;
-pub mod helper_functions;
\ No newline at end of file
+pub mod helper_functions;
+pub mod network_registry;
\ No newline at end of file
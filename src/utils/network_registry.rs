@@ -0,0 +1,387 @@
+use crate::errors::AvalancheError;
+use crate::utils::amount::Avax;
+use crate::utils::constants::{
+    FALLBACK_HRP, FALLBACK_NETWORK_NAME, NETWORK, NETWORK_ID_TO_HRP, NETWORK_ID_TO_NETWORK_NAMES,
+    C_CHAIN_ALIAS, C_CHAIN_VM_NAME, PLATFORM_CHAIN_ID, P_CHAIN_ALIAS, P_CHAIN_VM_NAME,
+    X_CHAIN_ALIAS, X_CHAIN_VM_NAME,
+};
+use lazy_static::lazy_static;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref GLOBAL_REGISTRY: RwLock<NetworkRegistry> = RwLock::new(NetworkRegistry::with_defaults());
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChainDefinition {
+    /// `None` when the ID is not known yet, e.g. for a network loaded from its genesis.
+    #[serde(default)]
+    pub blockchain_id: Option<String>,
+    pub alias: String,
+    pub vm: String,
+    #[serde(default)]
+    pub tx_fee: Option<Avax>,
+    #[serde(default)]
+    pub creation_tx_fee: Option<Avax>,
+    /// EIP-155 chain ID, only set on EVM chains.
+    #[serde(default)]
+    pub evm_chain_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NetworkDefinition {
+    pub network_id: u16,
+    pub hrp: String,
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub avax_asset_id: Option<String>,
+    pub x: ChainDefinition,
+    pub p: ChainDefinition,
+    pub c: ChainDefinition,
+    /// Subnet blockchains running on this network.
+    #[serde(default)]
+    pub chains: Vec<ChainDefinition>,
+}
+
+#[derive(Deserialize)]
+struct NetworkFile {
+    networks: Vec<NetworkDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonNetworkFile {
+    Networks(NetworkFile),
+    List(Vec<NetworkDefinition>),
+}
+
+#[derive(Deserialize)]
+struct Genesis {
+    #[serde(alias = "networkID")]
+    network_id: u16,
+    #[serde(alias = "cChainGenesis")]
+    c_chain_genesis: Option<String>,
+}
+
+fn to_avax(amount: &Option<BigInt>) -> Option<Avax> {
+    amount.as_ref().and_then(|amount| u64::try_from(amount).ok()).map(Avax::from_navax)
+}
+
+impl NetworkDefinition {
+    /// Builds the definition of a network from an avalanchego genesis file.
+    /// The X and C blockchain IDs are derived from the genesis transactions and are left
+    /// unknown here; `Avalanche::connect` fills them from the node.
+    pub fn from_genesis(genesis: &str) -> Result<NetworkDefinition, AvalancheError> {
+        let genesis: Genesis = serde_json::from_str(genesis).map_err(|e| AvalancheError::InvalidValue {
+            value: String::from("genesis"),
+            reason: e.to_string(),
+        })?;
+        let evm_chain_id = match genesis.c_chain_genesis {
+            Some(c_chain_genesis) => serde_json::from_str::<serde_json::Value>(&c_chain_genesis)
+                .map_err(|e| AvalancheError::InvalidValue {
+                    value: String::from("cChainGenesis"),
+                    reason: e.to_string(),
+                })?["config"]["chainId"]
+                .as_u64(),
+            None => None,
+        };
+        let chain = |alias: &str, vm: &str| ChainDefinition {
+            blockchain_id: None,
+            alias: String::from(alias),
+            vm: String::from(vm),
+            tx_fee: None,
+            creation_tx_fee: None,
+            evm_chain_id: None,
+        };
+        let mut p = chain(P_CHAIN_ALIAS, P_CHAIN_VM_NAME);
+        p.blockchain_id = Some(String::from(PLATFORM_CHAIN_ID));
+        let mut c = chain(C_CHAIN_ALIAS, C_CHAIN_VM_NAME);
+        c.evm_chain_id = evm_chain_id;
+        Ok(NetworkDefinition {
+            network_id: genesis.network_id,
+            hrp: String::from(*NETWORK_ID_TO_HRP.get(&genesis.network_id).unwrap_or(&FALLBACK_HRP)),
+            names: vec![String::from(FALLBACK_NETWORK_NAME)],
+            avax_asset_id: None,
+            x: chain(X_CHAIN_ALIAS, X_CHAIN_VM_NAME),
+            p,
+            c,
+            chains: vec![],
+        })
+    }
+
+    pub fn all_chains(&self) -> impl Iterator<Item = &ChainDefinition> {
+        [&self.x, &self.p, &self.c].into_iter().chain(self.chains.iter())
+    }
+
+    pub fn chain_by_blockchain_id(&self, blockchain_id: &str) -> Option<&ChainDefinition> {
+        self.all_chains()
+            .find(|chain| chain.blockchain_id.as_deref() == Some(blockchain_id))
+    }
+}
+
+/// Networks known by the library, indexed by network ID.
+/// The process wide registry used by `Avalanche` is reachable through [`NetworkRegistry::global`].
+#[derive(Clone, Debug, Default)]
+pub struct NetworkRegistry {
+    networks: HashMap<u16, NetworkDefinition>,
+}
+
+impl NetworkRegistry {
+    pub fn new() -> NetworkRegistry {
+        NetworkRegistry::default()
+    }
+
+    /// Registry containing the public networks from `constants`.
+    pub fn with_defaults() -> NetworkRegistry {
+        let mut registry = NetworkRegistry::new();
+        for (network_id, network) in NETWORK.iter() {
+            let hrp = NETWORK_ID_TO_HRP.get(network_id).unwrap_or(&FALLBACK_HRP);
+            let names = NETWORK_ID_TO_NETWORK_NAMES.get(network_id).cloned().unwrap_or_default();
+            registry.register(NetworkDefinition {
+                network_id: *network_id,
+                hrp: String::from(*hrp),
+                names: names.into_iter().map(String::from).collect(),
+                avax_asset_id: network.x.avax_asset_id.map(String::from),
+                x: ChainDefinition {
+                    blockchain_id: Some(String::from(network.x.blockchain_id)),
+                    alias: String::from(network.x.alias),
+                    vm: String::from(network.x.vm),
+                    tx_fee: to_avax(&network.x.tx_fee).or_else(|| to_avax(&network.x.fee)),
+                    creation_tx_fee: to_avax(&network.x.creation_tx_fee),
+                    evm_chain_id: None,
+                },
+                p: ChainDefinition {
+                    blockchain_id: Some(String::from(network.p.blockchain_id)),
+                    alias: String::from(network.p.alias),
+                    vm: String::from(network.p.vm),
+                    tx_fee: to_avax(&network.p.tx_fee).or_else(|| to_avax(&network.p.fee)),
+                    creation_tx_fee: to_avax(&network.p.creation_tx_fee),
+                    evm_chain_id: None,
+                },
+                c: ChainDefinition {
+                    blockchain_id: Some(String::from(network.c.blockchain_id)),
+                    alias: String::from(network.c.alias),
+                    vm: String::from(network.c.vm),
+                    tx_fee: to_avax(&network.c.tx_fee).or_else(|| to_avax(&network.c.fee)),
+                    creation_tx_fee: None,
+                    evm_chain_id: network.c.chain_id.map(|chain_id| chain_id as u64),
+                },
+                chains: vec![],
+            });
+        }
+        registry
+    }
+
+    /// Registry used by `Avalanche` to resolve network parameters.
+    pub fn global() -> &'static RwLock<NetworkRegistry> {
+        &GLOBAL_REGISTRY
+    }
+
+    /// Parses either `{"networks": [...]}` or a bare list of networks.
+    pub fn from_json(json: &str) -> Result<NetworkRegistry, AvalancheError> {
+        let networks = match serde_json::from_str::<JsonNetworkFile>(json) {
+            Ok(JsonNetworkFile::Networks(file)) => file.networks,
+            Ok(JsonNetworkFile::List(networks)) => networks,
+            Err(e) => return Err(AvalancheError::InvalidValue {
+                value: String::from("network registry"),
+                reason: e.to_string(),
+            }),
+        };
+        Ok(NetworkRegistry::from_networks(networks))
+    }
+
+    /// Parses a TOML document made of `[[networks]]` tables.
+    pub fn from_toml(document: &str) -> Result<NetworkRegistry, AvalancheError> {
+        let file: NetworkFile = toml::from_str(document).map_err(|e| AvalancheError::InvalidValue {
+            value: String::from("network registry"),
+            reason: e.to_string(),
+        })?;
+        Ok(NetworkRegistry::from_networks(file.networks))
+    }
+
+    fn from_networks(networks: Vec<NetworkDefinition>) -> NetworkRegistry {
+        let mut registry = NetworkRegistry::new();
+        for network in networks {
+            registry.register(network);
+        }
+        registry
+    }
+
+    /// Adds a network, replacing and returning any network registered with the same ID.
+    pub fn register(&mut self, network: NetworkDefinition) -> Option<NetworkDefinition> {
+        self.networks.insert(network.network_id, network)
+    }
+
+    pub fn unregister(&mut self, network_id: u16) -> Option<NetworkDefinition> {
+        self.networks.remove(&network_id)
+    }
+
+    /// Registers every network of `other`, its definitions take precedence.
+    pub fn extend(&mut self, other: NetworkRegistry) {
+        self.networks.extend(other.networks);
+    }
+
+    pub fn network_ids(&self) -> Vec<u16> {
+        let mut ids: Vec<u16> = self.networks.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn get(&self, network_id: u16) -> Result<&NetworkDefinition, AvalancheError> {
+        self.networks.get(&network_id).ok_or(AvalancheError::UnknownNetwork {
+            network: network_id.to_string(),
+        })
+    }
+
+    /// Networks in increasing network ID order, so lookups shared by several networks are stable.
+    fn sorted_networks(&self) -> impl Iterator<Item = &NetworkDefinition> {
+        self.network_ids().into_iter().filter_map(|network_id| self.networks.get(&network_id))
+    }
+
+    /// Genesis files of unknown networks all have the `custom` HRP, it resolves to the lowest network ID.
+    pub fn get_by_hrp(&self, hrp: &str) -> Result<&NetworkDefinition, AvalancheError> {
+        self.sorted_networks()
+            .find(|network| network.hrp == hrp)
+            .ok_or(AvalancheError::UnknownNetwork {
+                network: String::from(hrp),
+            })
+    }
+
+    /// Names are matched case insensitively, e.g. `fuji` or `Testnet`.
+    pub fn get_by_name(&self, name: &str) -> Result<&NetworkDefinition, AvalancheError> {
+        self.sorted_networks()
+            .find(|network| network.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+            .ok_or(AvalancheError::UnknownNetwork {
+                network: String::from(name),
+            })
+    }
+
+    /// Returns the network of a blockchain and the chain itself.
+    /// The P-Chain ID is shared by every network so it resolves to the lowest network ID.
+    pub fn get_by_blockchain_id(&self, blockchain_id: &str) -> Result<(&NetworkDefinition, &ChainDefinition), AvalancheError> {
+        self.sorted_networks()
+            .find_map(|network| network.chain_by_blockchain_id(blockchain_id).map(|chain| (network, chain)))
+            .ok_or(AvalancheError::UnknownNetwork {
+                network: String::from(blockchain_id),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTOM_NETWORK_JSON: &str = r#"{
+        "networks": [{
+            "network_id": 1337,
+            "hrp": "custom",
+            "names": ["Devnet"],
+            "avax_asset_id": "2fombhL7aGPwj3KH4bfrmJwW6PVnMobf9Y2fn9GwxiAAJyFDbe",
+            "x": {"blockchain_id": "2eNy1mUFdmaxXNj1eQHUe7Np4gju9sJsEtWQ4MX3ToiNKuADed", "alias": "X", "vm": "avm", "tx_fee": "1000000"},
+            "p": {"blockchain_id": "11111111111111111111111111111111LpoYY", "alias": "P", "vm": "platformvm"},
+            "c": {"blockchain_id": "2CA6j5zYzasynPsFeNoqWkmTCt3VScMvXUZHbfDJ8k3oGzAPtU", "alias": "C", "vm": "evm", "evm_chain_id": 43112},
+            "chains": [{"blockchain_id": "2bRCr6B4MiEfSjidDwxDpdCyviwnfUVqB2HGwhm947w9YYqb7r", "alias": "dfk", "vm": "subnetevm", "evm_chain_id": 53935}]
+        }]
+    }"#;
+
+    #[test]
+    fn defaults_contain_public_networks() {
+        let registry = NetworkRegistry::with_defaults();
+        let mainnet = registry.get(1).unwrap();
+        assert_eq!(mainnet.hrp, "avax");
+        assert_eq!(mainnet.c.evm_chain_id, Some(43114));
+        assert_eq!(mainnet.x.tx_fee, Some(Avax::from_navax(1_000_000)));
+        assert_eq!(registry.get_by_hrp("fuji").unwrap().network_id, 5);
+        assert_eq!(registry.get_by_name("testnet").unwrap().network_id, 5);
+        let (network, chain) = registry.get_by_blockchain_id("2q9e4r6Mu3U68nU1fYjgbR6JvwrRx36CohpAX5UQxse55x1Q5").unwrap();
+        assert_eq!(network.network_id, 1);
+        assert_eq!(chain.alias, "C");
+    }
+
+    #[test]
+    fn unknown_networks_are_errors() {
+        let registry = NetworkRegistry::with_defaults();
+        assert_eq!(registry.get(42).unwrap_err(), AvalancheError::UnknownNetwork { network: String::from("42") });
+        assert!(registry.get_by_hrp("nope").is_err());
+        assert!(registry.get_by_name("nope").is_err());
+        assert!(registry.get_by_blockchain_id("nope").is_err());
+    }
+
+    #[test]
+    fn load_from_json_and_extend() {
+        let mut registry = NetworkRegistry::with_defaults();
+        registry.extend(NetworkRegistry::from_json(CUSTOM_NETWORK_JSON).unwrap());
+        let network = registry.get_by_name("devnet").unwrap();
+        assert_eq!(network.network_id, 1337);
+        assert_eq!(network.x.tx_fee, Some(Avax::from_navax(1_000_000)));
+        let (network, chain) = registry.get_by_blockchain_id("2bRCr6B4MiEfSjidDwxDpdCyviwnfUVqB2HGwhm947w9YYqb7r").unwrap();
+        assert_eq!(network.network_id, 1337);
+        assert_eq!(chain.evm_chain_id, Some(53935));
+        assert_eq!(registry.get(1).unwrap().hrp, "avax");
+    }
+
+    #[test]
+    fn load_from_toml() {
+        let registry = NetworkRegistry::from_toml(r#"
+            [[networks]]
+            network_id = 1337
+            hrp = "custom"
+            names = ["Devnet"]
+
+            [networks.x]
+            alias = "X"
+            vm = "avm"
+
+            [networks.p]
+            blockchain_id = "11111111111111111111111111111111LpoYY"
+            alias = "P"
+            vm = "platformvm"
+
+            [networks.c]
+            alias = "C"
+            vm = "evm"
+            evm_chain_id = 43112
+        "#).unwrap();
+        let network = registry.get(1337).unwrap();
+        assert_eq!(network.c.evm_chain_id, Some(43112));
+        assert_eq!(network.x.blockchain_id, None);
+        assert!(registry.get(1).is_err());
+    }
+
+    #[test]
+    fn load_from_genesis() {
+        let genesis = r#"{
+            "networkID": 1337,
+            "allocations": [],
+            "startTime": 1630987200,
+            "cChainGenesis": "{\"config\":{\"chainId\":43112,\"homesteadBlock\":0},\"gasLimit\":\"0x5f5e100\"}"
+        }"#;
+        let network = NetworkDefinition::from_genesis(genesis).unwrap();
+        assert_eq!(network.network_id, 1337);
+        assert_eq!(network.hrp, "custom");
+        assert_eq!(network.c.evm_chain_id, Some(43112));
+        assert_eq!(network.p.blockchain_id.as_deref(), Some(PLATFORM_CHAIN_ID));
+        assert!(NetworkDefinition::from_genesis("{}").is_err());
+
+        let mut registry = NetworkRegistry::new();
+        for network_id in [4242, 1337, 2024] {
+            registry.register(NetworkDefinition::from_genesis(&genesis.replace("1337", &network_id.to_string())).unwrap());
+        }
+        assert_eq!(registry.get_by_hrp("custom").unwrap().network_id, 1337);
+    }
+
+    #[test]
+    fn register_replaces_existing_network() {
+        let mut registry = NetworkRegistry::with_defaults();
+        let mut fuji = registry.get(5).unwrap().clone();
+        fuji.hrp = String::from("fujisub");
+        assert!(registry.register(fuji).is_some());
+        assert_eq!(registry.get_by_hrp("fujisub").unwrap().network_id, 5);
+        assert!(registry.unregister(5).is_some());
+        assert!(registry.get(5).is_err());
+    }
+}